@@ -41,6 +41,60 @@ pub struct MissingContent {
     pub upload: Vec<UploadEndpoint>,
 }
 
+/// Represents the query parameters of a content upload request.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadContentQuery {
+    /// The byte offset of the content being uploaded.
+    ///
+    /// A non-zero offset resumes a previously interrupted upload and must
+    /// not exceed the number of bytes already received by the registry.
+    #[serde(default)]
+    pub offset: u64,
+}
+
+/// Represents the status of a content upload to an upload endpoint.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadStatus {
+    /// The number of bytes of the content received so far.
+    pub received: u64,
+    /// The digest of the bytes received so far.
+    ///
+    /// This is `None` if no bytes have been received.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<AnyHash>,
+}
+
+/// Represents the query parameters of a list packages request.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListPackagesQuery<'a> {
+    /// The prefix the listed package ids must start with, such as a
+    /// namespace followed by `:`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<Cow<'a, str>>,
+    /// The cursor returned by the previous page of the listing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<Cow<'a, str>>,
+    /// The maximum number of package ids to return.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u16>,
+}
+
+/// Represents a page of a list packages response.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListPackagesResponse {
+    /// The package ids in the page, in ascending order.
+    pub packages: Vec<PackageId>,
+    /// The cursor to request the next page with.
+    ///
+    /// This is `None` for the last page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
 /// Represents a request to publish a record to a package log.
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -54,6 +108,13 @@ pub struct PublishRecordRequest<'a> {
     /// A registry may not support specifying content sources directly.
     #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub content_sources: HashMap<AnyHash, Vec<ContentSource>>,
+    /// The key used to deduplicate retries of the publish, if any.
+    ///
+    /// The key must be the identifier of the record. If the registry has
+    /// already stored the record, it responds with `200 OK` and the current
+    /// state of the original record rather than storing it again.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<String>,
 }
 
 /// Represents a package record API entity in a registry.
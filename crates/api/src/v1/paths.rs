@@ -12,6 +12,16 @@ pub fn fetch_checkpoint() -> &'static str {
     "v1/fetch/checkpoint"
 }
 
+/// The path of the "fetch checkpoint before" API.
+pub fn fetch_checkpoint_before(timestamp: u64) -> String {
+    format!("v1/fetch/checkpoint/before/{timestamp}")
+}
+
+/// The path of the "list packages" API.
+pub fn list_packages() -> &'static str {
+    "v1/package"
+}
+
 /// The path of the "publish package record" API.
 pub fn publish_package_record(log_id: &LogId) -> String {
     format!("v1/package/{log_id}/record")
@@ -1,23 +1,44 @@
 //! A module for Warg registry API clients.
 
 use anyhow::{anyhow, Result};
-use bytes::Bytes;
-use futures_util::{Stream, TryStreamExt};
-use reqwest::{Body, IntoUrl, Response, StatusCode};
-use serde::de::DeserializeOwned;
-use std::borrow::Cow;
+use bytes::{Bytes, BytesMut};
+use futures_util::{Stream, StreamExt, TryStreamExt};
+use rand::Rng;
+use reqwest::{
+    header::{
+        HeaderMap, HeaderName, HeaderValue, ACCEPT_ENCODING, AUTHORIZATION, CONTENT_ENCODING,
+        CONTENT_RANGE, RANGE, RETRY_AFTER, USER_AGENT,
+    },
+    Body, Identity, IntoUrl, Method, Proxy, RequestBuilder, Response, StatusCode,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    future::Future,
+    io::Write,
+    net::SocketAddr,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant, SystemTime},
+};
 use thiserror::Error;
+use url::Url;
 use warg_api::v1::{
     fetch::{FetchError, FetchLogsRequest, FetchLogsResponse},
     package::{
-        ContentSource, PackageError, PackageRecord, PackageRecordState, PublishRecordRequest,
+        ContentSource, ListPackagesQuery, ListPackagesResponse, PackageError, PackageRecord,
+        PackageRecordState, PublishRecordRequest, UploadContentQuery, UploadStatus,
     },
     paths,
     proof::{
         ConsistencyRequest, ConsistencyResponse, InclusionRequest, InclusionResponse, ProofError,
     },
 };
-use warg_crypto::hash::{AnyHash, HashError, Sha256};
+use warg_crypto::hash::{AnyHash, HashAlgorithm, HashError, Sha256};
 use warg_protocol::{
     registry::{Checkpoint, LogId, LogLeaf, MapLeaf, RecordId, TimestampedCheckpoint},
     SerdeEnvelope,
@@ -27,7 +48,7 @@ use warg_transparency::{
     map::MapProofBundle,
 };
 
-use crate::registry_url::RegistryUrl;
+use crate::{metrics::Metrics, registry_url::RegistryUrl};
 
 /// Represents an error that occurred while communicating with the registry.
 #[derive(Debug, Error)]
@@ -52,6 +73,45 @@ pub enum ClientError {
         /// The error message.
         message: String,
     },
+    /// The registry requires authentication or rejected the provided token.
+    #[error("the registry requires authentication or rejected the provided authentication token")]
+    Unauthorized,
+    /// The registry does not support the requested operation.
+    #[error("the registry does not support {operation}")]
+    Unsupported {
+        /// A description of the unsupported operation.
+        operation: String,
+    },
+    /// The registry uses a hash algorithm the client does not support.
+    #[error("the registry uses unsupported hash algorithm `{algorithm}`")]
+    UnsupportedHashAlgorithm {
+        /// The name of the unsupported hash algorithm.
+        algorithm: String,
+    },
+    /// A connection to the registry could not be established in time.
+    #[error("failed to connect to the registry within {timeout:?}")]
+    ConnectTimedOut {
+        /// The connect timeout that elapsed.
+        timeout: Duration,
+    },
+    /// A request to the registry did not complete in time.
+    #[error("the request did not complete within {timeout:?}")]
+    RequestTimedOut {
+        /// The request timeout that elapsed.
+        timeout: Duration,
+    },
+    /// A content transfer made no progress in time.
+    #[error("the content transfer made no progress for {timeout:?}")]
+    TransferStalled {
+        /// The content transfer timeout that elapsed.
+        timeout: Duration,
+    },
+    /// A response from the registry exceeded the maximum size.
+    #[error("the registry response exceeds the limit of {limit} bytes")]
+    ResponseTooLarge {
+        /// The maximum size of the response, in bytes.
+        limit: u64,
+    },
     /// The provided root for a consistency proof was incorrect.
     #[error(
         "the client failed to prove consistency: found root `{found}` but was given root `{root}`"
@@ -85,25 +145,58 @@ pub enum ClientError {
     Other(#[from] anyhow::Error),
 }
 
-async fn deserialize<T: DeserializeOwned>(response: Response) -> Result<T, ClientError> {
+/// Reads the body of a response.
+///
+/// Fails with [`ClientError::ResponseTooLarge`] as soon as the body exceeds
+/// the given limit.
+async fn read_body(response: Response, limit: Option<u64>) -> Result<Bytes, ClientError> {
+    let status = response.status();
+    let read_error = |e: reqwest::Error| ClientError::UnexpectedResponse {
+        status,
+        message: format!("failed to read response: {e}"),
+    };
+
+    let Some(limit) = limit else {
+        return response.bytes().await.map_err(read_error);
+    };
+
+    if response.content_length().map_or(false, |len| len > limit) {
+        return Err(ClientError::ResponseTooLarge { limit });
+    }
+
+    let mut body = BytesMut::new();
+    let mut stream = response.bytes_stream();
+    while let Some(bytes) = stream.next().await {
+        let bytes = bytes.map_err(read_error)?;
+        if (body.len() + bytes.len()) as u64 > limit {
+            return Err(ClientError::ResponseTooLarge { limit });
+        }
+
+        body.extend_from_slice(&bytes);
+    }
+
+    Ok(body.freeze())
+}
+
+async fn deserialize<T: DeserializeOwned>(
+    response: Response,
+    limit: Option<u64>,
+) -> Result<T, ClientError> {
     let status = response.status();
     match response.headers().get("content-type") {
         Some(content_type) if content_type == "application/json" => {
-            let bytes = response
-                .bytes()
-                .await
-                .map_err(|e| ClientError::UnexpectedResponse {
-                    status,
-                    message: format!("failed to read response: {e}"),
-                })?;
+            let bytes = read_body(response, limit).await?;
             serde_json::from_slice(&bytes).map_err(|e| {
                 tracing::debug!(
                     "Unexpected response body: {}",
                     String::from_utf8_lossy(&bytes)
                 );
-                ClientError::UnexpectedResponse {
-                    status,
-                    message: format!("failed to deserialize JSON response: {e}"),
+                match unsupported_hash_algorithm(&bytes) {
+                    Some(algorithm) => ClientError::UnsupportedHashAlgorithm { algorithm },
+                    None => ClientError::UnexpectedResponse {
+                        status,
+                        message: format!("failed to deserialize JSON response: {e}"),
+                    },
                 }
             })
         }
@@ -121,31 +214,850 @@ async fn deserialize<T: DeserializeOwned>(response: Response) -> Result<T, Clien
     }
 }
 
+/// Finds a digest with a hash algorithm the client does not support in a
+/// JSON response body that failed to deserialize.
+fn unsupported_hash_algorithm(bytes: &[u8]) -> Option<String> {
+    fn find(value: &serde_json::Value) -> Option<String> {
+        let digest = |s: &str| {
+            let (algorithm, hex) = s.split_once(':')?;
+            let is_digest = !algorithm.is_empty()
+                && algorithm.bytes().all(|b| b.is_ascii_alphanumeric())
+                && hex.len() >= 32
+                && hex.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'));
+            (is_digest && algorithm.parse::<HashAlgorithm>().is_err())
+                .then(|| algorithm.to_string())
+        };
+
+        match value {
+            serde_json::Value::String(s) => digest(s),
+            serde_json::Value::Array(values) => values.iter().find_map(find),
+            serde_json::Value::Object(map) => map
+                .iter()
+                .find_map(|(key, value)| digest(key).or_else(|| find(value))),
+            _ => None,
+        }
+    }
+
+    find(&serde_json::from_slice(bytes).ok()?)
+}
+
 async fn into_result<T: DeserializeOwned, E: DeserializeOwned + Into<ClientError>>(
     response: Response,
+) -> Result<T, ClientError> {
+    into_limited_result::<T, E>(response, None).await
+}
+
+/// Converts a response into a result like [`into_result`], reading at most
+/// the given number of bytes of the response.
+async fn into_limited_result<T: DeserializeOwned, E: DeserializeOwned + Into<ClientError>>(
+    response: Response,
+    limit: Option<u64>,
 ) -> Result<T, ClientError> {
     if response.status().is_success() {
-        deserialize::<T>(response).await
+        deserialize::<T>(response, limit).await
     } else {
-        Err(deserialize::<E>(response).await?.into())
+        Err(deserialize::<E>(response, limit).await?.into())
+    }
+}
+
+/// The default maximum number of times a request is retried.
+pub const DEFAULT_MAX_RETRIES: u32 = 3;
+
+/// The default delay before the first retry of a request.
+pub const DEFAULT_RETRY_BASE_DELAY: Duration = Duration::from_millis(250);
+
+/// The default maximum size of a package log fetch or proof response, in
+/// bytes.
+pub const DEFAULT_MAX_RESPONSE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// The default maximum number of idle connections kept open to each host.
+pub const DEFAULT_POOL_MAX_IDLE_PER_HOST: usize = 32;
+
+/// The default time an idle connection is kept open before it is closed.
+///
+/// This is longer than the default of `reqwest` so that a long-running
+/// client reuses its connections between periodic operations.
+pub const DEFAULT_POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// The default `User-Agent` header sent with every request.
+pub const DEFAULT_USER_AGENT: &str =
+    concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+/// Determines if a response status indicates the request was not processed
+/// and may be retried.
+fn is_retriable_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// Gets the delay requested by the `Retry-After` header of a response.
+///
+/// The header may either be a number of seconds or an HTTP date.
+fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+    match value.parse::<u64>() {
+        Ok(secs) => Some(Duration::from_secs(secs)),
+        Err(_) => Some(
+            httpdate::parse_http_date(value)
+                .ok()?
+                .duration_since(SystemTime::now())
+                .unwrap_or_default(),
+        ),
+    }
+}
+
+/// Represents a token bucket limiting the rate of requests.
+///
+/// The bucket holds up to one second's worth of tokens so that short bursts
+/// of requests are sent without delay.
+struct RateLimiter {
+    rate: f64,
+    bucket: Mutex<(f64, Instant)>,
+}
+
+impl RateLimiter {
+    /// Creates a rate limiter for the given number of requests per second.
+    fn new(requests_per_second: u32) -> Self {
+        let rate = f64::from(requests_per_second);
+        Self {
+            rate,
+            bucket: Mutex::new((rate, Instant::now())),
+        }
     }
+
+    /// Waits until a request may be sent.
+    async fn acquire(&self) {
+        let delay = {
+            let mut bucket = self.bucket.lock().unwrap();
+            let (tokens, refilled) = &mut *bucket;
+            let now = Instant::now();
+            *tokens =
+                (*tokens + now.duration_since(*refilled).as_secs_f64() * self.rate).min(self.rate);
+            *refilled = now;
+
+            // Take a token even if the bucket is empty; the deficit is the
+            // time the requests already waiting will take
+            *tokens -= 1.0;
+            Duration::from_secs_f64((-*tokens).max(0.0) / self.rate)
+        };
+
+        if !delay.is_zero() {
+            tracing::debug!("delaying request by {delay:?} to limit the request rate");
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+/// Gets the first byte position of a partial content response.
+fn content_range_start(response: &Response) -> Option<u64> {
+    response
+        .headers()
+        .get(CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .strip_prefix("bytes ")?
+        .split_once('-')?
+        .0
+        .parse()
+        .ok()
+}
+
+/// Determines if a request error is transient and the request may be retried.
+fn is_retriable_error(e: &reqwest::Error) -> bool {
+    e.is_connect() || e.is_timeout() || e.is_request()
+}
+
+/// A stream of downloaded content.
+pub type ContentStream = Pin<Box<dyn Stream<Item = Result<Bytes>> + Send + Sync>>;
+
+/// Applies a stall timeout to the given content stream.
+///
+/// The stream fails with [`ClientError::TransferStalled`] if no bytes are
+/// received within the timeout.
+fn with_stall_timeout(
+    stream: impl Stream<Item = Result<Bytes>> + Send + Sync + 'static,
+    timeout: Option<Duration>,
+) -> Pin<Box<dyn Stream<Item = Result<Bytes>> + Send + Sync>> {
+    let stream: Pin<Box<dyn Stream<Item = Result<Bytes>> + Send + Sync>> = Box::pin(stream);
+    let Some(timeout) = timeout else {
+        return stream;
+    };
+
+    Box::pin(futures_util::stream::unfold(
+        Some(stream),
+        move |stream| async move {
+            let mut stream = stream?;
+            match tokio::time::timeout(timeout, stream.next()).await {
+                Ok(next) => next.map(|bytes| (bytes, Some(stream))),
+                Err(_) => Some((Err(ClientError::TransferStalled { timeout }.into()), None)),
+            }
+        },
+    ))
+}
+
+/// Applies a size limit to the given content stream that starts at the given
+/// offset of the content.
+///
+/// The stream fails with [`ClientError::ResponseTooLarge`] once the content
+/// exceeds the limit.
+fn with_size_limit(
+    stream: Pin<Box<dyn Stream<Item = Result<Bytes>> + Send + Sync>>,
+    offset: u64,
+    limit: Option<u64>,
+) -> Pin<Box<dyn Stream<Item = Result<Bytes>> + Send + Sync>> {
+    let Some(limit) = limit else {
+        return stream;
+    };
+
+    let mut received = offset;
+    Box::pin(stream.map(move |bytes| {
+        let bytes = bytes?;
+        received += bytes.len() as u64;
+        if received > limit {
+            return Err(ClientError::ResponseTooLarge { limit }.into());
+        }
+
+        Ok(bytes)
+    }))
+}
+
+/// The content coding used for compressed content transfers.
+const ZSTD: &str = "zstd";
+
+/// Determines if the given header lists the zstd content coding.
+fn lists_zstd(headers: &HeaderMap, name: HeaderName) -> bool {
+    headers.get_all(name).iter().any(|value| {
+        value.to_str().map_or(false, |value| {
+            value
+                .split(',')
+                .any(|coding| coding.split(';').next().unwrap_or_default().trim() == ZSTD)
+        })
+    })
+}
+
+/// Represents a zstd encoder or decoder of a content stream.
+enum Codec {
+    Encode(zstd::stream::write::Encoder<'static, Vec<u8>>),
+    Decode(zstd::stream::write::Decoder<'static, Vec<u8>>),
+}
+
+impl Codec {
+    /// Writes the given bytes, returning the output available so far.
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self {
+            Self::Encode(encoder) => {
+                encoder.write_all(bytes)?;
+                Ok(std::mem::take(encoder.get_mut()))
+            }
+            Self::Decode(decoder) => {
+                decoder.write_all(bytes)?;
+                decoder.flush()?;
+                Ok(std::mem::take(decoder.get_mut()))
+            }
+        }
+    }
+
+    /// Finishes the stream, returning the remaining output.
+    fn finish(self) -> std::io::Result<Vec<u8>> {
+        match self {
+            Self::Encode(encoder) => encoder.finish(),
+            Self::Decode(mut decoder) => {
+                decoder.flush()?;
+                Ok(decoder.into_inner())
+            }
+        }
+    }
+}
+
+/// Applies the given zstd codec to the given content stream.
+fn with_codec(
+    stream: Pin<Box<dyn Stream<Item = Result<Bytes>> + Send + Sync>>,
+    codec: Codec,
+) -> Pin<Box<dyn Stream<Item = Result<Bytes>> + Send + Sync>> {
+    Box::pin(futures_util::stream::unfold(
+        Some((stream, Mutex::new(codec))),
+        |state| async move {
+            let (mut stream, codec) = state?;
+            loop {
+                let bytes = match stream.next().await {
+                    Some(Ok(bytes)) => bytes,
+                    Some(Err(e)) => return Some((Err(e), None)),
+                    None => {
+                        return match codec.into_inner().unwrap().finish() {
+                            Ok(output) if output.is_empty() => None,
+                            Ok(output) => Some((Ok(output.into()), None)),
+                            Err(e) => Some((Err(e.into()), None)),
+                        }
+                    }
+                };
+
+                let output = codec.lock().unwrap().write(&bytes);
+                match output {
+                    Ok(output) if output.is_empty() => continue,
+                    Ok(output) => return Some((Ok(output.into()), Some((stream, codec)))),
+                    Err(e) => return Some((Err(e.into()), None)),
+                }
+            }
+        },
+    ))
+}
+
+/// Represents the HTTP version used for requests.
+///
+/// With HTTP/2, concurrent requests to a host are multiplexed over a single
+/// connection rather than each using a connection of their own.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HttpVersion {
+    /// HTTP/2 is used when negotiated with the server during the TLS
+    /// handshake; otherwise, HTTP/1.1 is used.
+    ///
+    /// Requests to `http` URLs always use HTTP/1.1.
+    #[default]
+    Auto,
+    /// Only HTTP/1.1 is used, for servers or proxies that mishandle HTTP/2.
+    Http1,
+    /// Only HTTP/2 is used, without negotiation.
+    ///
+    /// This is required for HTTP/2 with `http` URLs; every server the client
+    /// connects to must support HTTP/2.
+    Http2,
 }
 
 /// Represents a Warg API client for communicating with
 /// a Warg registry server.
 pub struct Client {
     url: RegistryUrl,
+    mirrors: Vec<RegistryUrl>,
     client: reqwest::Client,
+    auth_token: Option<String>,
+    default_headers: HeaderMap,
+    user_agent: HeaderValue,
+    max_retries: u32,
+    base_delay: Duration,
+    connect_timeout: Option<Duration>,
+    request_timeout: Option<Duration>,
+    transfer_timeout: Option<Duration>,
+    max_response_bytes: u64,
+    max_content_bytes: Option<u64>,
+    proxies: Vec<Proxy>,
+    identity: Option<Identity>,
+    host_overrides: HashMap<String, SocketAddr>,
+    pool_max_idle_per_host: usize,
+    pool_idle_timeout: Duration,
+    http_version: HttpVersion,
+    compression: bool,
+    accepts_zstd: AtomicBool,
+    rate_limiter: Option<RateLimiter>,
+    content_url_rewriter: Option<Arc<dyn Fn(Url) -> Url + Send + Sync>>,
+    metrics: Arc<Metrics>,
 }
 
 impl Client {
     /// Creates a new API client with the given URL.
     pub fn new(url: impl IntoUrl) -> Result<Self> {
-        let url = RegistryUrl::new(url)?;
-        Ok(Self {
-            url,
+        Self::from_registry_url(&RegistryUrl::new(url)?)
+    }
+
+    /// Creates a new API client for the given registry URL.
+    pub(crate) fn from_registry_url(url: &RegistryUrl) -> Result<Self> {
+        let mut client = Self {
+            url: url.clone(),
+            mirrors: Vec::new(),
             client: reqwest::Client::new(),
-        })
+            auth_token: None,
+            default_headers: HeaderMap::new(),
+            user_agent: HeaderValue::from_static(DEFAULT_USER_AGENT),
+            max_retries: DEFAULT_MAX_RETRIES,
+            base_delay: DEFAULT_RETRY_BASE_DELAY,
+            connect_timeout: None,
+            request_timeout: None,
+            transfer_timeout: None,
+            max_response_bytes: DEFAULT_MAX_RESPONSE_BYTES,
+            max_content_bytes: None,
+            proxies: Vec::new(),
+            identity: None,
+            host_overrides: HashMap::new(),
+            pool_max_idle_per_host: DEFAULT_POOL_MAX_IDLE_PER_HOST,
+            pool_idle_timeout: DEFAULT_POOL_IDLE_TIMEOUT,
+            http_version: HttpVersion::Auto,
+            compression: false,
+            accepts_zstd: AtomicBool::new(false),
+            rate_limiter: None,
+            content_url_rewriter: None,
+            metrics: Default::default(),
+        };
+        client.client = client.build_http_client()?;
+        Ok(client)
+    }
+
+    /// Sets the maximum number of times a failed request is retried.
+    ///
+    /// Idempotent requests are retried on connection errors and on responses
+    /// indicating the registry is temporarily unavailable; other requests are
+    /// only retried on the latter.
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Sets the delay before the first retry of a failed request.
+    ///
+    /// The delay doubles with each subsequent retry and a random jitter of up
+    /// to half the delay is added. A `Retry-After` header of the failed
+    /// response takes precedence over the delay.
+    pub fn with_retry_base_delay(mut self, delay: Duration) -> Self {
+        self.base_delay = delay;
+        self
+    }
+
+    /// Sets the maximum number of requests sent per second.
+    ///
+    /// Requests, including retries, are delayed as needed to stay within the
+    /// limit; bursts of up to one second's worth of requests are sent without
+    /// delay. A limit of zero does not limit requests.
+    pub fn with_max_requests_per_second(mut self, max: u32) -> Self {
+        self.rate_limiter = (max > 0).then(|| RateLimiter::new(max));
+        self
+    }
+
+    /// Sets the bearer token used to authenticate with the registry.
+    ///
+    /// The token is only sent with requests to the registry itself and never
+    /// with requests to its mirrors or to other content sources.
+    pub fn with_auth_token(mut self, token: impl Into<String>) -> Self {
+        self.auth_token = Some(token.into());
+        self
+    }
+
+    /// Sets the headers sent with every request.
+    ///
+    /// The authorization header sent for the bearer token takes precedence
+    /// over any default authorization header.
+    pub fn with_default_headers(mut self, headers: HashMap<String, String>) -> Result<Self> {
+        self.default_headers = headers
+            .into_iter()
+            .map(|(name, value)| {
+                let header = HeaderName::from_bytes(name.as_bytes())
+                    .map_err(|_| anyhow!("invalid header name `{name}`"))?;
+                let value = HeaderValue::from_str(&value)
+                    .map_err(|_| anyhow!("invalid value for header `{name}`"))?;
+                Ok((header, value))
+            })
+            .collect::<Result<_>>()?;
+        Ok(self)
+    }
+
+    /// Sets the `User-Agent` header sent with every request.
+    ///
+    /// A default `User-Agent` header takes precedence over the user agent.
+    pub fn with_user_agent(mut self, user_agent: &str) -> Result<Self> {
+        self.user_agent = HeaderValue::from_str(user_agent)
+            .map_err(|_| anyhow!("invalid user agent `{user_agent}`"))?;
+        Ok(self)
+    }
+
+    /// Sets the timeout for establishing a connection to the registry.
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Result<Self> {
+        self.connect_timeout = Some(timeout);
+        self.client = self.build_http_client()?;
+        Ok(self)
+    }
+
+    /// Sets the proxies used for requests to the registry, its mirrors, and
+    /// content sources.
+    ///
+    /// If no proxies are set, the proxies of the system environment are used.
+    pub fn with_proxies(mut self, proxies: impl IntoIterator<Item = Proxy>) -> Result<Self> {
+        self.proxies = proxies.into_iter().collect();
+        self.client = self.build_http_client()?;
+        Ok(self)
+    }
+
+    /// Sets the TLS client identity presented to servers requiring mutual
+    /// TLS.
+    pub fn with_identity(mut self, identity: Identity) -> Result<Self> {
+        self.identity = Some(identity);
+        self.client = self.build_http_client()?;
+        Ok(self)
+    }
+
+    /// Sets the addresses that connections to the given hosts are made to
+    /// instead of resolving the hosts.
+    ///
+    /// Requests keep the host of their URL, so the `Host` header and the TLS
+    /// server name are unchanged. The port of an address is ignored; the
+    /// port of the URL is always used.
+    pub fn with_host_overrides(mut self, overrides: HashMap<String, SocketAddr>) -> Result<Self> {
+        self.host_overrides = overrides;
+        self.client = self.build_http_client()?;
+        Ok(self)
+    }
+
+    /// Sets the maximum number of idle connections kept open to each host.
+    ///
+    /// Defaults to [`DEFAULT_POOL_MAX_IDLE_PER_HOST`]; zero closes every
+    /// connection once its request completes.
+    pub fn with_pool_max_idle_per_host(mut self, max: usize) -> Result<Self> {
+        self.pool_max_idle_per_host = max;
+        self.client = self.build_http_client()?;
+        Ok(self)
+    }
+
+    /// Sets the time an idle connection is kept open before it is closed.
+    ///
+    /// Defaults to [`DEFAULT_POOL_IDLE_TIMEOUT`].
+    pub fn with_pool_idle_timeout(mut self, timeout: Duration) -> Result<Self> {
+        self.pool_idle_timeout = timeout;
+        self.client = self.build_http_client()?;
+        Ok(self)
+    }
+
+    /// Sets the HTTP version used for requests.
+    ///
+    /// Defaults to [`HttpVersion::Auto`].
+    pub fn with_http_version(mut self, version: HttpVersion) -> Result<Self> {
+        self.http_version = version;
+        self.client = self.build_http_client()?;
+        Ok(self)
+    }
+
+    /// Sets the HTTP client used for all requests.
+    ///
+    /// The given client replaces the one built from the connect timeout,
+    /// proxies, TLS client identity, host overrides, connection pool, and
+    /// HTTP version settings of this client;
+    /// setting any of them afterwards builds a new client.
+    /// Authentication, default headers, and the request and transfer timeouts
+    /// still apply to each request.
+    pub fn with_http_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    fn build_http_client(&self) -> Result<reqwest::Client> {
+        let mut builder = reqwest::Client::builder()
+            .pool_max_idle_per_host(self.pool_max_idle_per_host)
+            .pool_idle_timeout(self.pool_idle_timeout);
+        match self.http_version {
+            HttpVersion::Auto => {}
+            HttpVersion::Http1 => builder = builder.http1_only(),
+            HttpVersion::Http2 => builder = builder.http2_prior_knowledge(),
+        }
+
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+
+        for proxy in &self.proxies {
+            builder = builder.proxy(proxy.clone());
+        }
+
+        if let Some(identity) = &self.identity {
+            builder = builder.identity(identity.clone());
+        }
+
+        for (host, addr) in &self.host_overrides {
+            builder = builder.resolve(host, *addr);
+        }
+
+        Ok(builder.build()?)
+    }
+
+    /// Sets the timeout for a request to the registry to complete, including
+    /// reading the response.
+    ///
+    /// Content transfers are not subject to the request timeout; see
+    /// [`Client::with_transfer_timeout`].
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the timeout for a content transfer to make progress.
+    ///
+    /// A content upload or download fails if no bytes are transferred within
+    /// the timeout, regardless of how long the transfer takes overall.
+    pub fn with_transfer_timeout(mut self, timeout: Duration) -> Self {
+        self.transfer_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the maximum size of a package log fetch or proof response, in
+    /// bytes.
+    ///
+    /// A larger response fails with [`ClientError::ResponseTooLarge`] once the
+    /// limit is exceeded, before the rest of the response is read. Defaults to
+    /// [`DEFAULT_MAX_RESPONSE_BYTES`].
+    pub fn with_max_response_bytes(mut self, max: u64) -> Self {
+        self.max_response_bytes = max;
+        self
+    }
+
+    /// Sets the maximum size of downloaded content, in bytes.
+    ///
+    /// Content is streamed rather than read into memory and is not subject
+    /// to the maximum response size; by default, its size is not limited.
+    pub fn with_max_content_bytes(mut self, max: u64) -> Self {
+        self.max_content_bytes = Some(max);
+        self
+    }
+
+    /// Sets whether content transfers are compressed with zstd.
+    ///
+    /// When enabled, downloads accept zstd-encoded content and uploads are
+    /// compressed once the registry advertises support for zstd with an
+    /// `Accept-Encoding` response header. Content is always stored and
+    /// verified uncompressed.
+    pub fn with_compression(mut self, compression: bool) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Sets the function that rewrites each content source URL before
+    /// content is downloaded from it.
+    ///
+    /// Downloaded content is always verified against its digest, regardless
+    /// of the URL it was downloaded from.
+    pub fn with_content_url_rewriter(
+        mut self,
+        rewriter: Arc<dyn Fn(Url) -> Url + Send + Sync>,
+    ) -> Self {
+        self.content_url_rewriter = Some(rewriter);
+        self
+    }
+
+    /// Sets the metrics that requests sent by the API client are recorded
+    /// in.
+    pub(crate) fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Gets the URL to download content from for the given content source
+    /// URL, applying the content URL rewriter, if any.
+    ///
+    /// A source URL that fails to parse is not rewritten.
+    fn content_url(&self, url: &str) -> String {
+        let Some(rewrite) = &self.content_url_rewriter else {
+            return url.to_string();
+        };
+
+        match Url::parse(url) {
+            Ok(parsed) => {
+                let rewritten = rewrite(parsed).to_string();
+                tracing::debug!("rewrote content URL `{url}` to `{rewritten}`");
+                rewritten
+            }
+            Err(e) => {
+                tracing::debug!("not rewriting invalid content URL `{url}`: {e}");
+                url.to_string()
+            }
+        }
+    }
+
+    /// Runs the given content transfer, failing with
+    /// [`ClientError::TransferStalled`] if the given count of transferred bytes
+    /// does not change within the transfer timeout.
+    pub(crate) async fn watch_transfer<T>(
+        &self,
+        transferred: &AtomicU64,
+        transfer: impl Future<Output = Result<T, ClientError>>,
+    ) -> Result<T, ClientError> {
+        let Some(timeout) = self.transfer_timeout else {
+            return transfer.await;
+        };
+
+        let watchdog = async {
+            let mut last = transferred.load(Ordering::Relaxed);
+            loop {
+                tokio::time::sleep(timeout).await;
+                let current = transferred.load(Ordering::Relaxed);
+                if current == last {
+                    return ClientError::TransferStalled { timeout };
+                }
+                last = current;
+            }
+        };
+
+        tokio::select! {
+            res = transfer => res,
+            e = watchdog => Err(e),
+        }
+    }
+
+    /// Converts a request error into a client error, distinguishing the
+    /// configured timeouts.
+    fn request_error(&self, e: reqwest::Error) -> ClientError {
+        if e.is_timeout() {
+            if e.is_connect() {
+                if let Some(timeout) = self.connect_timeout {
+                    return ClientError::ConnectTimedOut { timeout };
+                }
+            } else if let Some(timeout) = self.request_timeout {
+                return ClientError::RequestTimedOut { timeout };
+            }
+        }
+
+        ClientError::Communication(e)
+    }
+
+    /// Creates a request for the given URL, authenticating the request if
+    /// it is to the registry.
+    ///
+    /// The request is subject to the request timeout.
+    fn request(&self, method: Method, url: impl AsRef<str>) -> RequestBuilder {
+        let request = self.transfer_request(method, url);
+        match self.request_timeout {
+            Some(timeout) => request.timeout(timeout),
+            None => request,
+        }
+    }
+
+    /// Creates a content transfer request for the given URL, authenticating
+    /// the request if it is to the registry.
+    ///
+    /// The request is not subject to the request timeout.
+    fn transfer_request(&self, method: Method, url: impl AsRef<str>) -> RequestBuilder {
+        let url = url.as_ref();
+        let token = self
+            .auth_token
+            .as_ref()
+            .filter(|_| url.starts_with(&self.url.join("")));
+
+        let mut request = self.client.request(method, url);
+        if !self.default_headers.contains_key(USER_AGENT) {
+            request = request.header(USER_AGENT, self.user_agent.clone());
+        }
+
+        for (name, value) in &self.default_headers {
+            if token.is_none() || name != AUTHORIZATION {
+                request = request.header(name, value);
+            }
+        }
+
+        match token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    /// Sets the mirrors of the registry to fall back to for reads.
+    ///
+    /// Mirrors are tried in order when the registry is unreachable or
+    /// responds with a server error; publishing never uses a mirror.
+    pub fn with_mirrors(mut self, mirrors: impl IntoIterator<Item = RegistryUrl>) -> Self {
+        self.mirrors = mirrors.into_iter().collect();
+        self
+    }
+
+    /// Sends the read request built by the given function for the given
+    /// path, falling back to each mirror in order on a failure.
+    ///
+    /// The last response or error is returned once all mirrors have failed.
+    async fn send_read(
+        &self,
+        path: &str,
+        request: impl Fn(String) -> RequestBuilder,
+    ) -> Result<Response, ClientError> {
+        let mut sources = std::iter::once(&self.url).chain(&self.mirrors).peekable();
+        while let Some(source) = sources.next() {
+            let url = source.join(path);
+            let res = self.send(true, || request(url.clone())).await;
+            let failure = match &res {
+                Ok(response) if response.status().is_server_error() => {
+                    format!("status {status}", status = response.status())
+                }
+                Err(ClientError::Communication(e)) if is_retriable_error(e) => e.to_string(),
+                Err(
+                    e @ (ClientError::ConnectTimedOut { .. } | ClientError::RequestTimedOut { .. }),
+                ) => e.to_string(),
+                _ => {
+                    tracing::debug!("request to `{url}` was served by `{source}`");
+                    return res;
+                }
+            };
+
+            if sources.peek().is_none() {
+                return res;
+            }
+
+            tracing::warn!("registry `{source}` failed with {failure}; trying the next mirror");
+        }
+
+        unreachable!("the registry is always a source")
+    }
+
+    /// Sends the request built by the given function, retrying on transient
+    /// failures.
+    ///
+    /// Requests that are not idempotent are only retried when the registry
+    /// responds with a status indicating the request was not processed.
+    ///
+    /// The last response or error is returned once retries are exhausted.
+    async fn send(
+        &self,
+        idempotent: bool,
+        request: impl Fn() -> RequestBuilder,
+    ) -> Result<Response, ClientError> {
+        let mut attempt = 0;
+        loop {
+            self.limit_rate().await;
+            self.metrics.record_request();
+            let mut retry_delay = None;
+            let retriable = match request().send().await {
+                Ok(response) if is_retriable_status(response.status()) => {
+                    if attempt >= self.max_retries {
+                        return Ok(response);
+                    }
+
+                    retry_delay = retry_after(&response);
+                    format!("status {status}", status = response.status())
+                }
+                Ok(response) if response.status() == StatusCode::UNAUTHORIZED => {
+                    return Err(ClientError::Unauthorized)
+                }
+                Ok(response) => {
+                    if self.compression && lists_zstd(response.headers(), ACCEPT_ENCODING) {
+                        self.accepts_zstd.store(true, Ordering::Relaxed);
+                    }
+
+                    return Ok(response);
+                }
+                Err(e) if idempotent && is_retriable_error(&e) => {
+                    if attempt >= self.max_retries {
+                        return Err(self.request_error(e));
+                    }
+
+                    e.to_string()
+                }
+                Err(e) => return Err(self.request_error(e)),
+            };
+
+            let delay = retry_delay.unwrap_or_else(|| {
+                let delay = self.base_delay.saturating_mul(2u32.saturating_pow(attempt));
+                delay + delay.mul_f64(rand::thread_rng().gen_range(0.0..0.5))
+            });
+            attempt += 1;
+
+            tracing::debug!(
+                "retrying request ({attempt}/{max}) in {delay:?} after {retriable}",
+                max = self.max_retries
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Waits until the rate limit, if any, allows sending a request.
+    async fn limit_rate(&self) {
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire().await;
+        }
     }
 
     /// Gets the URL of the API client.
@@ -153,13 +1065,51 @@ impl Client {
         &self.url
     }
 
+    /// Gets whether content transfers are compressed with zstd.
+    pub fn compression(&self) -> bool {
+        self.compression
+    }
+
     /// Gets the latest checkpoint from the registry.
     pub async fn latest_checkpoint(
         &self,
     ) -> Result<SerdeEnvelope<TimestampedCheckpoint>, ClientError> {
-        let url = self.url.join(paths::fetch_checkpoint());
-        tracing::debug!("getting latest checkpoint at `{url}`");
-        into_result::<_, FetchError>(reqwest::get(url).await?).await
+        tracing::debug!("getting latest checkpoint");
+        let response = self
+            .send_read(paths::fetch_checkpoint(), |url| {
+                self.request(Method::GET, url)
+            })
+            .await?;
+        into_result::<_, FetchError>(response).await
+    }
+
+    /// Gets the latest checkpoint from the registry timestamped before the
+    /// given number of seconds since the Unix epoch.
+    ///
+    /// Returns `None` if no checkpoint is timestamped before the given time.
+    ///
+    /// Returns [`ClientError::Unsupported`] if the registry does not support
+    /// fetching checkpoints by time.
+    pub async fn checkpoint_before(
+        &self,
+        timestamp: u64,
+    ) -> Result<Option<SerdeEnvelope<TimestampedCheckpoint>>, ClientError> {
+        tracing::debug!("getting checkpoint before timestamp {timestamp}");
+        let response = self
+            .send_read(&paths::fetch_checkpoint_before(timestamp), |url| {
+                self.request(Method::GET, url)
+            })
+            .await?;
+        if matches!(
+            response.status(),
+            StatusCode::NOT_FOUND | StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED
+        ) {
+            return Err(ClientError::Unsupported {
+                operation: "fetching checkpoints by time".to_string(),
+            });
+        }
+
+        into_result::<_, FetchError>(response).await
     }
 
     /// Fetches package log entries from the registry.
@@ -167,14 +1117,47 @@ impl Client {
         &self,
         request: FetchLogsRequest<'_>,
     ) -> Result<FetchLogsResponse, ClientError> {
-        let url = self.url.join(paths::fetch_logs());
-        tracing::debug!("fetching logs at `{url}`");
+        tracing::debug!("fetching logs");
 
-        let response = self.client.post(url).json(&request).send().await?;
-        into_result::<_, FetchError>(response).await
+        let response = self
+            .send_read(paths::fetch_logs(), |url| {
+                self.request(Method::POST, url).json(&request)
+            })
+            .await?;
+        into_limited_result::<_, FetchError>(response, Some(self.max_response_bytes)).await
+    }
+
+    /// Lists a page of the packages in the registry.
+    ///
+    /// Returns [`ClientError::Unsupported`] if the registry does not support
+    /// listing packages.
+    pub async fn list_packages(
+        &self,
+        query: &ListPackagesQuery<'_>,
+    ) -> Result<ListPackagesResponse, ClientError> {
+        tracing::debug!("listing packages");
+
+        let response = self
+            .send_read(paths::list_packages(), |url| {
+                self.request(Method::GET, url).query(query)
+            })
+            .await?;
+        if matches!(
+            response.status(),
+            StatusCode::NOT_FOUND | StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED
+        ) {
+            return Err(ClientError::Unsupported {
+                operation: "listing packages".to_string(),
+            });
+        }
+
+        into_result::<_, PackageError>(response).await
     }
 
     /// Publish a new record to a package log.
+    ///
+    /// A request with an idempotency key is retried like an idempotent
+    /// request, as the registry deduplicates a record it already stored.
     pub async fn publish_package_record(
         &self,
         log_id: &LogId,
@@ -186,8 +1169,22 @@ impl Client {
             id = request.id
         );
 
-        let response = self.client.post(url).json(&request).send().await?;
-        into_result::<_, PackageError>(response).await
+        let idempotent = request.idempotency_key.is_some();
+        let response = self
+            .send(idempotent, || {
+                self.request(Method::POST, &url).json(&request)
+            })
+            .await?;
+        let replayed = idempotent && response.status() == StatusCode::OK;
+        let record: PackageRecord = into_result::<_, PackageError>(response).await?;
+        if replayed {
+            tracing::debug!(
+                "registry already stored record `{record_id}`",
+                record_id = record.id
+            );
+        }
+
+        Ok(record)
     }
 
     /// Gets a package record from the registry.
@@ -199,20 +1196,49 @@ impl Client {
         let url = self.url.join(&paths::package_record(log_id, record_id));
         tracing::debug!("getting record `{record_id}` for package `{log_id}` at `{url}`");
 
-        let response = reqwest::get(url).await?;
+        let response = self.send(true, || self.request(Method::GET, &url)).await?;
+        if matches!(
+            response.status(),
+            StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED
+        ) {
+            return Err(ClientError::Unsupported {
+                operation: "fetching a single record".to_string(),
+            });
+        }
+
         into_result::<_, PackageError>(response).await
     }
 
-    /// Downloads the content associated with a given record.
-    pub async fn download_content(
+    /// Gets a published package record from the registry or its mirrors.
+    async fn get_published_package_record(
+        &self,
+        log_id: &LogId,
+        record_id: &RecordId,
+    ) -> Result<PackageRecord, ClientError> {
+        tracing::debug!("getting record `{record_id}` for package `{log_id}`");
+
+        let response = self
+            .send_read(&paths::package_record(log_id, record_id), |url| {
+                self.request(Method::GET, url)
+            })
+            .await?;
+        into_result::<_, PackageError>(response).await
+    }
+
+    /// Gets the URLs of the sources of the content associated with a given
+    /// record, in the order the registry lists them.
+    ///
+    /// The first URL is the primary source and any others are mirrors of it.
+    /// Each URL is rewritten by the content URL rewriter.
+    pub async fn content_sources(
         &self,
         log_id: &LogId,
         record_id: &RecordId,
         digest: &AnyHash,
-    ) -> Result<impl Stream<Item = Result<Bytes>>, ClientError> {
+    ) -> Result<Vec<String>, ClientError> {
         tracing::debug!("fetching record `{record_id}` for package `{log_id}`");
 
-        let record = self.get_package_record(log_id, record_id).await?;
+        let record = self.get_published_package_record(log_id, record_id).await?;
         let sources = match &record.state {
             PackageRecordState::Published {
                 content_sources, ..
@@ -224,26 +1250,177 @@ impl Client {
             }
         };
 
-        for source in sources {
-            let url = match source {
-                ContentSource::Http { url } => url,
-            };
+        Ok(sources
+            .iter()
+            .map(|source| match source {
+                ContentSource::Http { url } => self.content_url(url),
+            })
+            .collect())
+    }
 
-            tracing::debug!("downloading content `{digest}` from `{url}`");
+    /// Downloads the content associated with a given record.
+    ///
+    /// If the offset is non-zero, the content is requested starting at the
+    /// offset; a source that does not support range requests serves the
+    /// entire content instead.
+    ///
+    /// If `identity` is set, the content is requested with
+    /// `Accept-Encoding: identity`, even when compression is enabled.
+    ///
+    /// Returns the URL of the source the content is served from, the offset
+    /// the served content starts at, the total length of the content, if
+    /// known, and a stream of the content from the offset.
+    pub async fn download_content(
+        &self,
+        log_id: &LogId,
+        record_id: &RecordId,
+        digest: &AnyHash,
+        offset: u64,
+        identity: bool,
+    ) -> Result<(String, u64, Option<u64>, ContentStream), ClientError> {
+        for url in self.content_sources(log_id, record_id, digest).await? {
+            if let Some((start, len, stream)) = self
+                .try_download_from(&url, digest, offset, identity)
+                .await?
+            {
+                return Ok((url, start, len, stream));
+            }
+        }
 
-            let response = reqwest::get(url).await?;
-            if !response.status().is_success() {
-                tracing::debug!(
-                    "failed to download content `{digest}` from `{url}`: {status}",
-                    status = response.status()
-                );
-                continue;
+        Err(ClientError::AllSourcesFailed(digest.clone()))
+    }
+
+    /// Downloads content from the given URL in place of the content sources
+    /// of its record.
+    ///
+    /// The URL is not rewritten by the content URL rewriter. Returns
+    /// [`ClientError::AllSourcesFailed`] if the URL responds with an error.
+    ///
+    /// See [`Client::download_content`].
+    pub async fn download_content_from(
+        &self,
+        url: &str,
+        digest: &AnyHash,
+        offset: u64,
+        identity: bool,
+    ) -> Result<(u64, Option<u64>, ContentStream), ClientError> {
+        self.try_download_from(url, digest, offset, identity)
+            .await?
+            .ok_or_else(|| ClientError::AllSourcesFailed(digest.clone()))
+    }
+
+    /// Downloads content from the given URL, starting at the given offset.
+    ///
+    /// Returns `None` if the URL responds with an error.
+    async fn try_download_from(
+        &self,
+        url: &str,
+        digest: &AnyHash,
+        offset: u64,
+        identity: bool,
+    ) -> Result<Option<(u64, Option<u64>, ContentStream)>, ClientError> {
+        tracing::debug!("downloading content `{digest}` from `{url}`");
+
+        let mut response = self.send_download(url, offset, identity).await?;
+        if offset > 0 && response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+            tracing::debug!("source `{url}` cannot resume at offset {offset}; restarting");
+            response = self.send_download(url, 0, identity).await?;
+        }
+
+        if !response.status().is_success() {
+            tracing::debug!(
+                "failed to download content `{digest}` from `{url}`: {status}",
+                status = response.status()
+            );
+            return Ok(None);
+        }
+
+        // A source that ignores the range serves the entire content
+        let start = match response.status() {
+            StatusCode::PARTIAL_CONTENT if content_range_start(&response) == Some(offset) => offset,
+            StatusCode::PARTIAL_CONTENT => {
+                tracing::debug!("source `{url}` served an unexpected range; restarting");
+                response = self.send_download(url, 0, identity).await?;
+                if !response.status().is_success() {
+                    return Ok(None);
+                }
+                0
             }
+            _ => 0,
+        };
 
-            return Ok(response.bytes_stream().map_err(|e| anyhow!(e)));
+        if offset > 0 {
+            tracing::debug!("resuming download of content `{digest}` at offset {start}");
         }
 
-        Err(ClientError::AllSourcesFailed(digest.clone()))
+        // The length of compressed content is not the length of the content
+        let compressed = lists_zstd(response.headers(), CONTENT_ENCODING);
+        let len = response
+            .content_length()
+            .filter(|_| !compressed)
+            .map(|len| start + len);
+        if let (Some(limit), Some(len)) = (self.max_content_bytes, len) {
+            if len > limit {
+                return Err(ClientError::ResponseTooLarge { limit });
+            }
+        }
+
+        let stream = with_stall_timeout(
+            response.bytes_stream().map_err(|e| anyhow!(e)),
+            self.transfer_timeout,
+        );
+        if !compressed {
+            return Ok(Some((
+                start,
+                len,
+                with_size_limit(stream, start, self.max_content_bytes),
+            )));
+        }
+
+        tracing::debug!("decompressing content `{digest}` from `{url}`");
+        let decoder = zstd::stream::write::Decoder::new(Vec::new()).map_err(anyhow::Error::from)?;
+        Ok(Some((
+            start,
+            len,
+            with_size_limit(
+                with_codec(stream, Codec::Decode(decoder)),
+                start,
+                self.max_content_bytes,
+            ),
+        )))
+    }
+
+    /// Sends a request to download content from the given offset.
+    ///
+    /// Compressed content is only accepted when downloading from the start
+    /// and identity encoding is not forced.
+    ///
+    /// Waiting for the response is bounded by the transfer timeout.
+    async fn send_download(
+        &self,
+        url: &str,
+        offset: u64,
+        identity: bool,
+    ) -> Result<Response, ClientError> {
+        let send = self.send(true, || {
+            let request = self.transfer_request(Method::GET, url);
+            if offset > 0 {
+                request.header(RANGE, format!("bytes={offset}-"))
+            } else if identity {
+                request.header(ACCEPT_ENCODING, "identity")
+            } else if self.compression {
+                request.header(ACCEPT_ENCODING, ZSTD)
+            } else {
+                request
+            }
+        });
+
+        match self.transfer_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, send)
+                .await
+                .map_err(|_| ClientError::TransferStalled { timeout })?,
+            None => send.await,
+        }
     }
 
     /// Proves the inclusion of the given package log heads in the registry.
@@ -253,15 +1430,51 @@ impl Client {
         checkpoint: &Checkpoint,
         leafs: &[LogLeaf],
     ) -> Result<(), ClientError> {
-        let url = self.url.join(paths::prove_inclusion());
-        tracing::debug!("proving checkpoint inclusion at `{url}`");
+        let response = self.inclusion_proof(request).await?;
+        Self::validate_inclusion_response(&response, checkpoint, leafs)
+    }
 
-        let response = into_result::<InclusionResponse, ProofError>(
-            self.client.post(url).json(&request).send().await?,
-        )
-        .await?;
+    /// Fetches the inclusion proofs of the given registry log leafs without
+    /// validating them.
+    pub async fn inclusion_proof(
+        &self,
+        request: InclusionRequest,
+    ) -> Result<InclusionResponse, ClientError> {
+        tracing::debug!("proving checkpoint inclusion");
 
-        Self::validate_inclusion_response(response, checkpoint, leafs)
+        self.send_proof_request(paths::prove_inclusion(), "inclusion proofs", &request)
+            .await
+    }
+
+    /// Sends a request to a proof endpoint of the registry.
+    ///
+    /// Returns [`ClientError::Unsupported`] if the registry does not serve
+    /// the endpoint: it responds with `405` or `501`, or with a `404` that
+    /// is not a proof error.
+    async fn send_proof_request<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        operation: &str,
+        request: &impl Serialize,
+    ) -> Result<T, ClientError> {
+        let response = self
+            .send_read(path, |url| self.request(Method::POST, url).json(request))
+            .await?;
+        let unsupported = || ClientError::Unsupported {
+            operation: operation.to_string(),
+        };
+
+        let limit = Some(self.max_response_bytes);
+        match response.status() {
+            StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED => Err(unsupported()),
+            StatusCode::NOT_FOUND => {
+                match into_limited_result::<T, ProofError>(response, limit).await {
+                    Err(ClientError::UnexpectedResponse { .. }) => Err(unsupported()),
+                    res => res,
+                }
+            }
+            _ => into_limited_result::<T, ProofError>(response, limit).await,
+        }
     }
 
     /// Proves consistency between two log roots.
@@ -271,11 +1484,9 @@ impl Client {
         from_log_root: Cow<'_, AnyHash>,
         to_log_root: Cow<'_, AnyHash>,
     ) -> Result<(), ClientError> {
-        let url = self.url.join(paths::prove_consistency());
-        let response = into_result::<ConsistencyResponse, ProofError>(
-            self.client.post(url).json(&request).send().await?,
-        )
-        .await?;
+        let response: ConsistencyResponse = self
+            .send_proof_request(paths::prove_consistency(), "consistency proofs", &request)
+            .await?;
 
         let proof = ProofBundle::<Sha256, LogLeaf>::decode(&response.proof).unwrap();
         let (log_data, consistencies, inclusions) = proof.unbundle();
@@ -320,15 +1531,116 @@ impl Client {
         url: &str,
         content: impl Into<Body>,
     ) -> Result<String, ClientError> {
+        self.resume_upload_content(url, 0, content).await
+    }
+
+    /// Gets the status of a content upload from the registry.
+    pub async fn upload_status(&self, url: &str) -> Result<UploadStatus, ClientError> {
+        // Upload URLs may be relative to the registry URL.
+        let url = self.url.join(url);
+
+        tracing::debug!("getting upload status at `{url}`");
+
+        let response = self.send(true, || self.request(Method::GET, &url)).await?;
+        into_result::<_, PackageError>(response).await
+    }
+
+    /// Determines if the registry already holds the content for the given
+    /// upload endpoint URL.
+    ///
+    /// Returns `false` if the content must be uploaded.
+    pub async fn content_exists(&self, url: &str) -> Result<bool, ClientError> {
         // Upload URLs may be relative to the registry URL.
         let url = self.url.join(url);
 
-        tracing::debug!("uploading content to `{url}`");
+        tracing::debug!("checking for existing content at `{url}`");
+
+        let response = self.send(true, || self.request(Method::HEAD, &url)).await?;
+        match response.status() {
+            StatusCode::OK => Ok(true),
+            StatusCode::NOT_FOUND => Ok(false),
+            status => Err(ClientError::UnexpectedResponse {
+                status,
+                message: format!("unexpected response when checking for content at `{url}`"),
+            }),
+        }
+    }
+
+    /// Uploads package content to the registry starting at the given offset.
+    ///
+    /// The content is expected to begin at `offset` bytes into the content
+    /// being uploaded.
+    pub async fn resume_upload_content(
+        &self,
+        url: &str,
+        offset: u64,
+        content: impl Into<Body>,
+    ) -> Result<String, ClientError> {
+        self.send_upload(url, offset, content, false).await
+    }
+
+    /// Uploads a stream of package content to the registry starting at the
+    /// given offset.
+    ///
+    /// The content is compressed when compression is enabled, the registry
+    /// advertised support for it, and the upload starts at the beginning of
+    /// the content; resumed uploads are sent uncompressed.
+    pub async fn resume_upload_stream(
+        &self,
+        url: &str,
+        offset: u64,
+        stream: impl Stream<Item = Result<Bytes>> + Send + Sync + 'static,
+    ) -> Result<String, ClientError> {
+        let stream: Pin<Box<dyn Stream<Item = Result<Bytes>> + Send + Sync>> = Box::pin(stream);
+        if offset > 0 || !self.compression || !self.accepts_zstd.load(Ordering::Relaxed) {
+            return self
+                .send_upload(url, offset, Body::wrap_stream(stream), false)
+                .await;
+        }
+
+        let encoder =
+            zstd::stream::write::Encoder::new(Vec::new(), 0).map_err(anyhow::Error::from)?;
+        let stream = with_codec(stream, Codec::Encode(encoder));
+        self.send_upload(url, offset, Body::wrap_stream(stream), true)
+            .await
+    }
+
+    /// Sends a content upload request, optionally of zstd-compressed content.
+    async fn send_upload(
+        &self,
+        url: &str,
+        offset: u64,
+        content: impl Into<Body>,
+        compressed: bool,
+    ) -> Result<String, ClientError> {
+        // Upload URLs may be relative to the registry URL.
+        let url = self.url.join(url);
+
+        tracing::debug!("uploading content to `{url}` at offset {offset}");
+
+        let mut request = self.transfer_request(Method::POST, url);
+        if offset > 0 {
+            request = request.query(&UploadContentQuery { offset });
+        }
+
+        if compressed {
+            request = request.header(CONTENT_ENCODING, ZSTD);
+        }
+
+        self.limit_rate().await;
+        self.metrics.record_request();
+        let response = request
+            .body(content)
+            .send()
+            .await
+            .map_err(|e| self.request_error(e))?;
+        if response.status() == StatusCode::UNAUTHORIZED {
+            return Err(ClientError::Unauthorized);
+        }
 
-        let response = self.client.post(url).body(content).send().await?;
         if !response.status().is_success() {
             return Err(ClientError::Package(
-                deserialize::<PackageError>(response).await?,
+                deserialize::<PackageError>(response, None).await?,
             ));
         }
 
@@ -347,44 +1659,69 @@ impl Client {
             .to_string())
     }
 
-    fn validate_inclusion_response(
-        response: InclusionResponse,
+    pub(crate) fn validate_inclusion_response(
+        response: &InclusionResponse,
         checkpoint: &Checkpoint,
         leafs: &[LogLeaf],
     ) -> Result<(), ClientError> {
-        let log_proof_bundle: LogProofBundle<Sha256, LogLeaf> =
-            LogProofBundle::decode(response.log.as_slice())?;
-        let (log_data, _, log_inclusions) = log_proof_bundle.unbundle();
-        for (leaf, proof) in leafs.iter().zip(log_inclusions.iter()) {
-            let found = proof.evaluate_value(&log_data, leaf)?;
-            let root = checkpoint.log_root.clone().try_into()?;
-            if found != root {
+        for (log_root, map_root) in Self::evaluate_inclusion_response(response, leafs)? {
+            if log_root != checkpoint.log_root {
                 return Err(ClientError::Proof(ProofError::IncorrectProof {
                     root: checkpoint.log_root.clone(),
-                    found: found.into(),
+                    found: log_root,
                 }));
             }
-        }
 
-        let map_proof_bundle: MapProofBundle<Sha256, LogId, MapLeaf> =
-            MapProofBundle::decode(response.map.as_slice())?;
-        let map_inclusions = map_proof_bundle.unbundle();
-        for (leaf, proof) in leafs.iter().zip(map_inclusions.iter()) {
-            let found = proof.evaluate(
-                &leaf.log_id,
-                &MapLeaf {
-                    record_id: leaf.record_id.clone(),
-                },
-            );
-            let root = checkpoint.map_root.clone().try_into()?;
-            if found != root {
+            if map_root != checkpoint.map_root {
                 return Err(ClientError::Proof(ProofError::IncorrectProof {
                     root: checkpoint.map_root.clone(),
-                    found: found.into(),
+                    found: map_root,
                 }));
             }
         }
 
         Ok(())
     }
+
+    /// Evaluates the proofs of an inclusion response for the given registry
+    /// log leafs, each mapped to its own record.
+    ///
+    /// Returns the log root and map root the proofs of each leaf evaluate to.
+    pub(crate) fn evaluate_inclusion_response(
+        response: &InclusionResponse,
+        leafs: &[LogLeaf],
+    ) -> Result<Vec<(AnyHash, AnyHash)>, ClientError> {
+        let log_proof_bundle: LogProofBundle<Sha256, LogLeaf> =
+            LogProofBundle::decode(response.log.as_slice())?;
+        let (log_data, _, log_inclusions) = log_proof_bundle.unbundle();
+        if log_inclusions.len() != leafs.len() {
+            return Err(ClientError::Proof(ProofError::BundleFailure(
+                "expected a log inclusion proof for every leaf".into(),
+            )));
+        }
+
+        let map_proof_bundle: MapProofBundle<Sha256, LogId, MapLeaf> =
+            MapProofBundle::decode(response.map.as_slice())?;
+        let map_inclusions = map_proof_bundle.unbundle();
+        if map_inclusions.len() != leafs.len() {
+            return Err(ClientError::Proof(ProofError::BundleFailure(
+                "expected a map inclusion proof for every leaf".into(),
+            )));
+        }
+
+        leafs
+            .iter()
+            .zip(log_inclusions.iter().zip(map_inclusions.iter()))
+            .map(|(leaf, (log_proof, map_proof))| {
+                let log_root = log_proof.evaluate_value(&log_data, leaf)?;
+                let map_root = map_proof.evaluate(
+                    &leaf.log_id,
+                    &MapLeaf {
+                        record_id: leaf.record_id.clone(),
+                    },
+                );
+                Ok((log_root.into(), map_root.into()))
+            })
+            .collect()
+    }
 }
@@ -0,0 +1,391 @@
+//! A module for a blocking Warg registry client.
+//!
+//! The blocking client wraps an asynchronous [`Client`] and drives each
+//! operation to completion on a runtime it owns, so that it can be used from
+//! code that is not asynchronous.
+
+use crate::{
+    storage::{ContentStorage, PackageHandle, PackageInfo, PublishInfo, RegistryStorage},
+    Client, ClientError, ClientResult, EnsureContentReport, FileSystemClient, PackageDownload,
+    RepairReport, SyncReport, SyncStatus,
+};
+use std::{future::Future, io::Read, time::Duration};
+use tokio::runtime::{Handle, Runtime};
+use warg_api::v1::package::PackageRecord;
+use warg_crypto::{
+    hash::AnyHash,
+    signing::{self, PublicKey},
+};
+use warg_protocol::{
+    operator, package,
+    registry::{PackageId, RecordId, TimestampedCheckpoint},
+    ProtoEnvelope, PublishedProtoEnvelope, Version, VersionReq,
+};
+
+/// A blocking Warg registry client that uses the local file system to store
+/// package logs and content.
+pub type FileSystemBlockingClient = BlockingClient<
+    crate::storage::FileSystemRegistryStorage,
+    crate::storage::FileSystemContentStorage,
+>;
+
+/// A client for a Warg registry whose operations block the calling thread.
+///
+/// Each operation is driven on a current-thread runtime owned by the client.
+/// Operations must not be called from within an asynchronous runtime; doing
+/// so returns [`ClientError::BlockingInAsyncContext`] rather than nesting
+/// runtimes.
+pub struct BlockingClient<R, C> {
+    client: Client<R, C>,
+    runtime: Option<Runtime>,
+}
+
+impl<R: RegistryStorage, C: ContentStorage> BlockingClient<R, C> {
+    /// Creates a new blocking client wrapping the given client.
+    ///
+    /// Returns [`ClientError::BlockingInAsyncContext`] if called from within
+    /// an asynchronous runtime.
+    pub fn new(client: Client<R, C>) -> ClientResult<Self> {
+        check_context()?;
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(anyhow::Error::from)?;
+
+        Ok(Self {
+            client,
+            runtime: Some(runtime),
+        })
+    }
+
+    /// Gets the wrapped asynchronous client.
+    pub fn inner(&self) -> &Client<R, C> {
+        &self.client
+    }
+
+    /// Drives the given future to completion on the client's runtime.
+    fn block_on<T>(&self, future: impl Future<Output = ClientResult<T>>) -> ClientResult<T> {
+        check_context()?;
+        self.runtime
+            .as_ref()
+            .expect("the runtime is only taken on drop")
+            .block_on(future)
+    }
+
+    /// Submits the publish information in client storage.
+    ///
+    /// See [`Client::publish`].
+    pub fn publish(&self, signing_key: &dyn signing::Signer) -> ClientResult<RecordId> {
+        self.block_on(self.client.publish(signing_key))
+    }
+
+    /// Submits the provided publish information.
+    ///
+    /// See [`Client::publish_with_info`].
+    pub fn publish_with_info(
+        &self,
+        signing_key: &dyn signing::Signer,
+        info: PublishInfo,
+    ) -> ClientResult<RecordId> {
+        self.block_on(self.client.publish_with_info(signing_key, info))
+    }
+
+    /// Submits a signed record.
+    ///
+    /// See [`Client::submit_record`].
+    pub fn submit_record(
+        &self,
+        id: &PackageId,
+        record: ProtoEnvelope<package::PackageRecord>,
+    ) -> ClientResult<RecordId> {
+        self.block_on(self.client.submit_record(id, record))
+    }
+
+    /// Stores the content read from the given reader, verifying it has the
+    /// given digest, and submits the provided publish information.
+    ///
+    /// The content is read into memory before it is stored.
+    ///
+    /// See [`Client::publish_with_content`].
+    pub fn publish_with_content(
+        &self,
+        signing_key: &dyn signing::Signer,
+        info: PublishInfo,
+        digest: &AnyHash,
+        mut reader: impl Read,
+    ) -> ClientResult<RecordId> {
+        let mut content = Vec::new();
+        reader
+            .read_to_end(&mut content)
+            .map_err(anyhow::Error::from)?;
+
+        self.block_on(self.client.publish_with_content(
+            signing_key,
+            info,
+            digest,
+            std::io::Cursor::new(content),
+        ))
+    }
+
+    /// Initializes a package, granting permissions to other keys in the
+    /// same record.
+    ///
+    /// See [`Client::init_package_with_grants`].
+    pub fn init_package_with_grants(
+        &self,
+        signing_key: &dyn signing::Signer,
+        id: &PackageId,
+        grants: impl IntoIterator<Item = (PublicKey, Vec<package::Permission>)>,
+    ) -> ClientResult<RecordId> {
+        self.block_on(
+            self.client
+                .init_package_with_grants(signing_key, id, grants),
+        )
+    }
+
+    /// Rotates the signing key of a package, authorizing a new key with the
+    /// permissions of the old key.
+    ///
+    /// See [`Client::rotate_signing_key`].
+    pub fn rotate_signing_key(
+        &self,
+        id: &PackageId,
+        old: &dyn signing::Signer,
+        new: &dyn signing::Signer,
+    ) -> ClientResult<RecordId> {
+        self.block_on(self.client.rotate_signing_key(id, old, new))
+    }
+
+    /// Waits for a submitted record to be published.
+    ///
+    /// See [`Client::wait_for_publish`].
+    pub fn wait_for_publish(
+        &self,
+        package: &PackageId,
+        record_id: &RecordId,
+        interval: Duration,
+    ) -> ClientResult<()> {
+        self.block_on(self.client.wait_for_publish(package, record_id, interval))
+    }
+
+    /// Updates every package log in client storage to the latest registry
+    /// checkpoint.
+    ///
+    /// See [`Client::update`].
+    pub fn update(&self) -> ClientResult<()> {
+        self.block_on(self.client.update())
+    }
+
+    /// Inserts or updates the logs of the specified packages in client
+    /// storage to the latest registry checkpoint.
+    ///
+    /// See [`Client::upsert`].
+    pub fn upsert(&self, packages: &[PackageId]) -> ClientResult<()> {
+        self.block_on(self.client.upsert(packages))
+    }
+
+    /// Synchronizes the logs of the specified packages in client storage with
+    /// the latest registry checkpoint.
+    ///
+    /// See [`Client::sync`].
+    pub fn sync(&self, ids: &[PackageId]) -> ClientResult<SyncReport> {
+        self.block_on(self.client.sync(ids))
+    }
+
+    /// Synchronizes the logs of the specified packages in client storage with
+    /// the latest registry checkpoint as a single transaction.
+    ///
+    /// See [`Client::sync_transactional`].
+    pub fn sync_transactional(&self, ids: &[PackageId]) -> ClientResult<SyncReport> {
+        self.block_on(self.client.sync_transactional(ids))
+    }
+
+    /// Gets the metadata of a package without transferring any content.
+    ///
+    /// See [`Client::package_metadata`].
+    pub fn package_metadata(&self, id: &PackageId) -> ClientResult<PackageInfo> {
+        self.block_on(self.client.package_metadata(id))
+    }
+
+    /// Gets the state of a package log as of the given registry checkpoint.
+    ///
+    /// See [`Client::package_info_at`].
+    pub fn package_info_at(
+        &self,
+        id: &PackageId,
+        checkpoint: &TimestampedCheckpoint,
+    ) -> ClientResult<PackageInfo> {
+        self.block_on(self.client.package_info_at(id, checkpoint))
+    }
+
+    /// Determines if the package log of a package in client storage is up to
+    /// date with the latest registry checkpoint, without updating it.
+    ///
+    /// See [`Client::sync_status`].
+    pub fn sync_status(&self, id: &PackageId) -> ClientResult<SyncStatus> {
+        self.block_on(self.client.sync_status(id))
+    }
+
+    /// Gets the metrics of the client in the Prometheus text exposition
+    /// format.
+    ///
+    /// See [`Client::metrics_text`].
+    pub fn metrics_text(&self) -> ClientResult<String> {
+        self.block_on(self.client.metrics_text())
+    }
+
+    /// Repairs the content released by the package logs in client storage.
+    ///
+    /// See [`Client::repair`].
+    pub fn repair(&self) -> ClientResult<RepairReport> {
+        self.block_on(self.client.repair())
+    }
+
+    /// Gets a handle to the package log of a package in client storage
+    /// without loading its releases.
+    ///
+    /// See [`Client::package_handle`].
+    pub fn package_handle(&self, id: &PackageId) -> ClientResult<PackageHandle> {
+        self.block_on(self.client.package_handle(id))
+    }
+
+    /// Fetches a single record of a package log from the registry.
+    ///
+    /// See [`Client::fetch_record`].
+    pub fn fetch_record(&self, id: &PackageId, record: &RecordId) -> ClientResult<PackageRecord> {
+        self.block_on(self.client.fetch_record(id, record))
+    }
+
+    /// Fetches the signed envelope of a published record of a package log.
+    ///
+    /// See [`Client::fetch_record_envelope`].
+    pub fn fetch_record_envelope(
+        &self,
+        id: &PackageId,
+        record: &RecordId,
+    ) -> ClientResult<PublishedProtoEnvelope<package::PackageRecord>> {
+        self.block_on(self.client.fetch_record_envelope(id, record))
+    }
+
+    /// Fetches and validates the operator log of the registry.
+    ///
+    /// See [`Client::fetch_operator_log`].
+    pub fn fetch_operator_log(
+        &self,
+    ) -> ClientResult<Vec<PublishedProtoEnvelope<operator::OperatorRecord>>> {
+        self.block_on(self.client.fetch_operator_log())
+    }
+
+    /// Fetches the records of a package log that were added between two
+    /// registry checkpoints.
+    ///
+    /// See [`Client::diff_checkpoints`].
+    pub fn diff_checkpoints(
+        &self,
+        id: &PackageId,
+        from: &TimestampedCheckpoint,
+        to: &TimestampedCheckpoint,
+    ) -> ClientResult<Vec<PublishedProtoEnvelope<package::PackageRecord>>> {
+        self.block_on(self.client.diff_checkpoints(id, from, to))
+    }
+
+    /// Downloads the latest version of a package that satisfies the given
+    /// version requirement.
+    ///
+    /// See [`Client::download`].
+    pub fn download(
+        &self,
+        id: &PackageId,
+        requirement: &VersionReq,
+    ) -> ClientResult<Option<PackageDownload>> {
+        self.block_on(self.client.download(id, requirement))
+    }
+
+    /// Downloads the specified version of a package.
+    ///
+    /// See [`Client::download_exact`].
+    pub fn download_exact(
+        &self,
+        package: &PackageId,
+        version: &Version,
+    ) -> ClientResult<PackageDownload> {
+        self.block_on(self.client.download_exact(package, version))
+    }
+
+    /// Downloads the latest versions of the given packages that satisfy the
+    /// given version requirements.
+    ///
+    /// See [`Client::download_many`].
+    pub fn download_many(
+        &self,
+        packages: &[(PackageId, VersionReq)],
+    ) -> ClientResult<Vec<Option<PackageDownload>>> {
+        self.block_on(
+            self.client
+                .download_many(packages.iter().map(|(id, req)| (id, req))),
+        )
+    }
+
+    /// Ensures the content of each of the given package versions is in
+    /// client storage, downloading only the content that is missing.
+    ///
+    /// See [`Client::ensure_content`].
+    pub fn ensure_content(
+        &self,
+        entries: &[(PackageId, Version, AnyHash)],
+    ) -> ClientResult<EnsureContentReport> {
+        self.block_on(self.client.ensure_content(entries))
+    }
+
+    /// Gets the digests of the content that would be downloaded for the
+    /// latest version of a package satisfying the given requirement.
+    ///
+    /// See [`Client::missing_content`].
+    pub fn missing_content(
+        &self,
+        id: &PackageId,
+        requirement: &VersionReq,
+    ) -> ClientResult<Vec<AnyHash>> {
+        self.block_on(self.client.missing_content(id, requirement))
+    }
+
+    /// Verifies the latest registry checkpoint against the log of a package
+    /// in client storage.
+    ///
+    /// See [`Client::verify_registry_checkpoint`].
+    pub fn verify_registry_checkpoint(&self, id: &PackageId) -> ClientResult<()> {
+        self.block_on(self.client.verify_registry_checkpoint(id))
+    }
+}
+
+impl FileSystemBlockingClient {
+    /// Creates a blocking client for the given registry URL and
+    /// configuration.
+    ///
+    /// See [`FileSystemClient::new_with_config`].
+    pub fn new_with_config(url: Option<&str>, config: &crate::Config) -> ClientResult<Self> {
+        Self::new(FileSystemClient::new_with_config(url, config)?)
+    }
+}
+
+impl<R, C> Drop for BlockingClient<R, C> {
+    fn drop(&mut self) {
+        // Dropping a runtime blocks, which is not allowed in an asynchronous
+        // context
+        if let Some(runtime) = self.runtime.take() {
+            if Handle::try_current().is_ok() {
+                runtime.shutdown_background();
+            }
+        }
+    }
+}
+
+/// Ensures the calling thread is not driving an asynchronous runtime.
+fn check_context() -> ClientResult<()> {
+    if Handle::try_current().is_ok() {
+        return Err(ClientError::BlockingInAsyncContext);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,769 @@
+//! Module for building Warg registry clients.
+
+use crate::{
+    api,
+    metrics::Metrics,
+    storage::{ContentStorage, RegistryStorage},
+    CancellationToken, Client, ClientError, ClientResult, NoProgress, NoRetry, ProgressHandler,
+    RegistryUrl, VerificationFailureHandler,
+};
+use reqwest::{header::AUTHORIZATION, Identity, NoProxy, Proxy};
+use std::{
+    collections::HashMap,
+    env, fs,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use url::Url;
+
+/// The duration for which a client remembers that a package does not exist.
+///
+/// See [`Client::package_exists`].
+pub const PACKAGE_NOT_FOUND_CACHE_DURATION: Duration = Duration::from_secs(30);
+
+/// The default maximum number of concurrent content downloads.
+pub const DEFAULT_MAX_CONCURRENT_DOWNLOADS: usize = 4;
+
+/// The default number of content blobs verified concurrently.
+pub const DEFAULT_VERIFY_CONCURRENCY: usize = 4;
+
+/// The default maximum number of times content the registry is still missing
+/// is uploaded again while waiting for a publish.
+pub const DEFAULT_MAX_CONTENT_RETRIES: u32 = 2;
+
+/// A builder for Warg registry clients.
+pub struct ClientBuilder<R, C> {
+    url: RegistryUrl,
+    mirrors: Vec<RegistryUrl>,
+    auth_token: Option<String>,
+    default_headers: HashMap<String, String>,
+    user_agent: Option<String>,
+    user_agent_suffix: Option<String>,
+    proxy: Option<String>,
+    no_proxy: Vec<String>,
+    http_client: Option<reqwest::Client>,
+    client_identity: Option<(PathBuf, PathBuf)>,
+    host_overrides: HashMap<String, SocketAddr>,
+    pool_max_idle_per_host: Option<usize>,
+    pool_idle_timeout: Option<Duration>,
+    http_version: Option<api::HttpVersion>,
+    registry: R,
+    content: C,
+    max_concurrent_downloads: usize,
+    verify_concurrency: usize,
+    offline: bool,
+    verify_proofs: bool,
+    allow_unverified: bool,
+    checkpoint_min_age: Option<Duration>,
+    max_retries: u32,
+    retry_base_delay: Duration,
+    max_requests_per_second: u32,
+    connect_timeout: Option<Duration>,
+    request_timeout: Option<Duration>,
+    content_transfer_timeout: Option<Duration>,
+    max_response_bytes: u64,
+    max_content_bytes: Option<u64>,
+    skip_existing_content: bool,
+    compress_content: bool,
+    retry_identity_encoding: bool,
+    max_content_retries: u32,
+    progress: Arc<dyn ProgressHandler>,
+    verification_failure: Arc<dyn VerificationFailureHandler>,
+    cancel: CancellationToken,
+    content_url_rewriter: Option<Arc<dyn Fn(Url) -> Url + Send + Sync>>,
+    routes: Vec<Route<R>>,
+}
+
+/// A registry that packages in a set of namespaces are routed to.
+struct Route<R> {
+    url: RegistryUrl,
+    registry: R,
+    auth_token: Option<String>,
+    namespaces: Vec<String>,
+}
+
+impl<R: RegistryStorage, C: ContentStorage> ClientBuilder<R, C> {
+    /// Creates a new client builder for the given URL, registry storage, and
+    /// content storage.
+    pub fn new(url: RegistryUrl, registry: R, content: C) -> Self {
+        Self {
+            url,
+            mirrors: Vec::new(),
+            auth_token: None,
+            default_headers: HashMap::new(),
+            user_agent: None,
+            user_agent_suffix: None,
+            proxy: None,
+            no_proxy: Vec::new(),
+            http_client: None,
+            client_identity: None,
+            host_overrides: HashMap::new(),
+            pool_max_idle_per_host: None,
+            pool_idle_timeout: None,
+            http_version: None,
+            registry,
+            content,
+            max_concurrent_downloads: DEFAULT_MAX_CONCURRENT_DOWNLOADS,
+            verify_concurrency: DEFAULT_VERIFY_CONCURRENCY,
+            offline: false,
+            verify_proofs: true,
+            allow_unverified: false,
+            checkpoint_min_age: None,
+            max_retries: api::DEFAULT_MAX_RETRIES,
+            retry_base_delay: api::DEFAULT_RETRY_BASE_DELAY,
+            max_requests_per_second: 0,
+            connect_timeout: None,
+            request_timeout: None,
+            content_transfer_timeout: None,
+            max_response_bytes: api::DEFAULT_MAX_RESPONSE_BYTES,
+            max_content_bytes: None,
+            skip_existing_content: true,
+            compress_content: false,
+            retry_identity_encoding: false,
+            max_content_retries: DEFAULT_MAX_CONTENT_RETRIES,
+            progress: Arc::new(NoProgress),
+            verification_failure: Arc::new(NoRetry),
+            cancel: CancellationToken::new(),
+            content_url_rewriter: None,
+            routes: Vec::new(),
+        }
+    }
+
+    /// Sets the maximum number of content downloads the client performs
+    /// concurrently.
+    ///
+    /// A value of zero is treated as one.
+    pub fn with_max_concurrent_downloads(mut self, max: usize) -> Self {
+        self.max_concurrent_downloads = max.max(1);
+        self
+    }
+
+    /// Sets the number of content blobs re-hashed concurrently when
+    /// verifying client storage.
+    ///
+    /// Hashing is CPU-bound, so a concurrency up to the number of available
+    /// cores speeds up verification. A value of zero is treated as one.
+    pub fn with_verify_concurrency(mut self, concurrency: usize) -> Self {
+        self.verify_concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Sets whether the client operates in offline mode.
+    ///
+    /// An offline client never makes requests to the registry; package logs
+    /// and content are read solely from client storage.
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Sets whether the client verifies inclusion and consistency proofs for
+    /// fetched records.
+    ///
+    /// Proofs are verified by default; disabling verification trusts the
+    /// transport to the registry.
+    pub fn with_verify_proofs(mut self, verify: bool) -> Self {
+        self.verify_proofs = verify;
+        self
+    }
+
+    /// Sets whether the client accepts package logs it cannot verify because
+    /// the registry does not serve inclusion or consistency proofs.
+    ///
+    /// Unverified package logs are rejected by default; when accepted, a
+    /// warning is logged and the logs are marked as unverified in client
+    /// storage.
+    pub fn with_allow_unverified(mut self, allow: bool) -> Self {
+        self.allow_unverified = allow;
+        self
+    }
+
+    /// Sets the minimum age of registry checkpoints the client accepts.
+    ///
+    /// A checkpoint timestamped more recently is rejected with
+    /// [`ClientError::CheckpointTooRecent`], so that the client only trusts
+    /// checkpoints that other clients have had time to witness. By default,
+    /// checkpoints of any age are accepted.
+    pub fn with_checkpoint_min_age(mut self, min_age: Duration) -> Self {
+        self.checkpoint_min_age = Some(min_age);
+        self
+    }
+
+    /// Sets the maximum number of times a failed request to the registry is
+    /// retried.
+    ///
+    /// Requests are retried with exponential backoff; see
+    /// [`ClientBuilder::with_retry_base_delay`].
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Sets the delay before the first retry of a failed request to the
+    /// registry.
+    ///
+    /// The delay doubles with each subsequent retry, unless the registry
+    /// responds with a `Retry-After` header.
+    pub fn with_retry_base_delay(mut self, delay: Duration) -> Self {
+        self.retry_base_delay = delay;
+        self
+    }
+
+    /// Sets the maximum number of requests per second the client sends to
+    /// each registry.
+    ///
+    /// The client delays requests to stay within the limit rather than being
+    /// throttled by the registry. By default, or with a limit of zero,
+    /// requests are not limited.
+    pub fn with_max_requests_per_second(mut self, max: u32) -> Self {
+        self.max_requests_per_second = max;
+        self
+    }
+
+    /// Sets the timeout for establishing a connection to the registry.
+    ///
+    /// By default, connecting does not time out.
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the timeout for a request to the registry to complete.
+    ///
+    /// Content uploads and downloads are not subject to the request timeout;
+    /// see [`ClientBuilder::with_content_transfer_timeout`]. By default,
+    /// requests do not time out.
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the timeout for a content upload or download to make progress.
+    ///
+    /// A transfer fails if no bytes are transferred within the timeout, so a
+    /// large transfer that makes steady progress is never interrupted. By
+    /// default, content transfers do not time out.
+    pub fn with_content_transfer_timeout(mut self, timeout: Duration) -> Self {
+        self.content_transfer_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the maximum size of a package log fetch or proof response from
+    /// the registry, in bytes.
+    ///
+    /// A larger response fails with [`ClientError::ResponseTooLarge`] as soon
+    /// as the limit is exceeded, rather than being read into memory. By
+    /// default, the limit is [`api::DEFAULT_MAX_RESPONSE_BYTES`].
+    pub fn with_max_response_bytes(mut self, max: u64) -> Self {
+        self.max_response_bytes = max;
+        self
+    }
+
+    /// Sets the maximum size of downloaded content, in bytes.
+    ///
+    /// Content is streamed to storage and is not subject to the maximum
+    /// response size. By default, the size of content is not limited.
+    pub fn with_max_content_bytes(mut self, max: u64) -> Self {
+        self.max_content_bytes = Some(max);
+        self
+    }
+
+    /// Sets the bearer token used to authenticate with the registry.
+    pub fn with_auth_token(mut self, token: impl Into<String>) -> Self {
+        self.auth_token = Some(token.into());
+        self
+    }
+
+    /// Sets the headers sent with every request the client makes.
+    ///
+    /// This is useful when the registry is behind a gateway that requires
+    /// additional headers. If an authentication token is also set, the token
+    /// takes precedence over an `Authorization` header.
+    pub fn with_default_headers(mut self, headers: HashMap<String, String>) -> Self {
+        self.default_headers = headers;
+        self
+    }
+
+    /// Sets the `User-Agent` header sent with every request the client makes.
+    ///
+    /// By default, the user agent is [`api::DEFAULT_USER_AGENT`].
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Sets a suffix appended to the `User-Agent` header, such as the name
+    /// and version of the application using the client.
+    pub fn with_user_agent_suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.user_agent_suffix = Some(suffix.into());
+        self
+    }
+
+    /// Sets the URL of the proxy used for all requests the client makes.
+    ///
+    /// HTTP, HTTPS, and SOCKS5 proxy URLs are supported, for example
+    /// `http://proxy.example.com:8080` or `socks5://127.0.0.1:1080`. If a
+    /// proxy is not set, the `HTTPS_PROXY` and `HTTP_PROXY` environment
+    /// variables are used for requests with the respective scheme.
+    pub fn with_proxy(mut self, url: impl Into<String>) -> Self {
+        self.proxy = Some(url.into());
+        self
+    }
+
+    /// Sets the hosts for which requests bypass the proxy.
+    ///
+    /// Hosts may be domain names, which also match their subdomains, IP
+    /// addresses, or IP ranges in CIDR notation. If no hosts are set, the
+    /// `NO_PROXY` environment variable is used.
+    pub fn with_no_proxy(mut self, hosts: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.no_proxy = hosts.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets the paths to the PEM-encoded certificate and private key the
+    /// client presents to registries requiring mutual TLS.
+    ///
+    /// The certificate file may contain a chain of certificates and the
+    /// private key must be in PKCS #8 format. The files are loaded when the
+    /// client is built.
+    pub fn with_client_identity(
+        mut self,
+        cert: impl Into<PathBuf>,
+        key: impl Into<PathBuf>,
+    ) -> Self {
+        self.client_identity = Some((cert.into(), key.into()));
+        self
+    }
+
+    /// Sets the addresses that connections to the given hosts are made to,
+    /// keyed by host name, instead of resolving the hosts.
+    ///
+    /// Requests keep the host of their URL, so the `Host` header and the TLS
+    /// server name are unchanged; this allows testing a staging registry under
+    /// the host name of the production registry without editing the system's
+    /// hosts file. The port of an address is ignored in favor of the port of
+    /// the URL.
+    pub fn with_host_overrides(mut self, overrides: HashMap<String, SocketAddr>) -> Self {
+        self.host_overrides = overrides;
+        self
+    }
+
+    /// Sets the maximum number of idle connections to each host that are
+    /// kept open for reuse by later requests.
+    ///
+    /// By default, up to [`api::DEFAULT_POOL_MAX_IDLE_PER_HOST`] connections
+    /// are kept open; zero opens a new connection for every request.
+    pub fn with_pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// Sets the time an idle connection is kept open for reuse before it is
+    /// closed.
+    ///
+    /// By default, idle connections are closed after
+    /// [`api::DEFAULT_POOL_IDLE_TIMEOUT`].
+    pub fn with_pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the HTTP version used for requests.
+    ///
+    /// By default, HTTP/2 is used when the server negotiates it over TLS, so
+    /// that concurrent content downloads share a single connection; use
+    /// [`api::HttpVersion::Http1`] for servers or proxies that mishandle
+    /// HTTP/2.
+    pub fn with_http_version(mut self, version: api::HttpVersion) -> Self {
+        self.http_version = Some(version);
+        self
+    }
+
+    /// Sets the HTTP client used for all requests the client makes.
+    ///
+    /// This is an escape hatch for settings the builder does not cover, such
+    /// as a custom root certificate store. The proxy, bypassed hosts, connect
+    /// timeout, client identity, host overrides, connection pool, and HTTP
+    /// version settings of the builder are ignored, as they are settings of
+    /// the HTTP client; all other settings still apply.
+    pub fn with_http_client(mut self, client: reqwest::Client) -> Self {
+        self.http_client = Some(client);
+        self
+    }
+
+    /// Sets the mirrors of the registry to fall back to for reads.
+    ///
+    /// Fetching package logs, proofs, and content falls back to each mirror
+    /// in order when the registry is unreachable or responds with a server
+    /// error. Data served by a mirror is verified as if it were served by the
+    /// registry; publishing never uses a mirror.
+    pub fn with_mirrors(mut self, mirrors: impl IntoIterator<Item = RegistryUrl>) -> Self {
+        self.mirrors = mirrors.into_iter().collect();
+        self
+    }
+
+    /// Sets whether the client checks if the registry already holds content
+    /// before uploading it during a publish.
+    ///
+    /// Existing content is skipped by default.
+    pub fn with_skip_existing_content(mut self, skip: bool) -> Self {
+        self.skip_existing_content = skip;
+        self
+    }
+
+    /// Sets whether content uploads and downloads are compressed with zstd.
+    ///
+    /// Not all registries support compressed transfers, so content is only
+    /// uploaded compressed once the registry advertises support for it.
+    /// Content is always stored uncompressed. By default, content is
+    /// transferred uncompressed.
+    pub fn with_content_compression(mut self, compress: bool) -> Self {
+        self.compress_content = compress;
+        self
+    }
+
+    /// Sets whether downloaded content that fails verification is downloaded
+    /// once more with identity encoding.
+    ///
+    /// Intermediaries that mishandle compressed transfers can mangle content
+    /// so it fails verification; the content is then requested with
+    /// `Accept-Encoding: identity` before the verification failure handler is
+    /// consulted. Only applies when content compression is enabled. By
+    /// default, content is not downloaded again.
+    pub fn with_identity_encoding_retry(mut self, retry: bool) -> Self {
+        self.retry_identity_encoding = retry;
+        self
+    }
+
+    /// Sets the maximum number of times content is uploaded again when the
+    /// registry still reports it missing while waiting for a publish.
+    ///
+    /// Once the retries are exhausted, waiting fails with
+    /// [`ClientError::PackageMissingContent`] listing the missing content.
+    pub fn with_max_content_retries(mut self, max: u32) -> Self {
+        self.max_content_retries = max;
+        self
+    }
+
+    /// Sets the handler that receives the progress of content uploads and
+    /// downloads.
+    ///
+    /// By default, progress is not reported.
+    pub fn with_progress_handler(mut self, handler: impl ProgressHandler + 'static) -> Self {
+        self.progress = Arc::new(handler);
+        self
+    }
+
+    /// Sets the handler that decides whether a download of content that
+    /// does not match its digest is retried.
+    ///
+    /// By default, the download fails with
+    /// [`ClientError::ContentDigestMismatch`].
+    pub fn with_verification_failure_handler(
+        mut self,
+        handler: impl VerificationFailureHandler + 'static,
+    ) -> Self {
+        self.verification_failure = Arc::new(handler);
+        self
+    }
+
+    /// Sets the token used to cancel the client's operations.
+    ///
+    /// Once the token is cancelled, content downloads and uploads and package
+    /// log updates stop at the next chunk or request and fail with
+    /// [`ClientError::Cancelled`]. Partial content of a cancelled download is
+    /// discarded, and package logs are only stored if an update completes.
+    pub fn with_cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.cancel = token;
+        self
+    }
+
+    /// Sets a function that rewrites the URL of every content source before
+    /// content is downloaded from it.
+    ///
+    /// This supports deployments where the registry returns content URLs on
+    /// an internal host that clients must instead reach through a public
+    /// CDN. Downloaded content is verified against its digest regardless of
+    /// where it was downloaded from.
+    ///
+    /// By default, content URLs are used as returned by the registry.
+    pub fn with_content_url_rewriter(
+        mut self,
+        rewriter: impl Fn(Url) -> Url + Send + Sync + 'static,
+    ) -> Self {
+        self.content_url_rewriter = Some(Arc::new(rewriter));
+        self
+    }
+
+    /// Routes packages in the given namespaces to another registry.
+    ///
+    /// Every operation on a package in one of the namespaces, including
+    /// publishing, is made against the given registry and uses the given
+    /// registry storage and authentication token; packages in other
+    /// namespaces use the client's registry. Content storage and all other
+    /// settings of the builder are shared with the routed registry, but
+    /// mirrors are not.
+    pub fn with_route(
+        mut self,
+        url: RegistryUrl,
+        registry: R,
+        auth_token: Option<String>,
+        namespaces: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.routes.push(Route {
+            url,
+            registry,
+            auth_token,
+            namespaces: namespaces.into_iter().map(Into::into).collect(),
+        });
+        self
+    }
+
+    /// Builds the client.
+    pub fn build(mut self) -> ClientResult<Client<R, C>> {
+        if self.auth_token.is_some()
+            && self
+                .default_headers
+                .keys()
+                .any(|name| AUTHORIZATION.as_str().eq_ignore_ascii_case(name))
+        {
+            tracing::warn!(
+                "the default `Authorization` header is overridden by the authentication token"
+            );
+        }
+
+        let proxies = if self.http_client.is_some() {
+            if self.proxy.is_some()
+                || !self.no_proxy.is_empty()
+                || self.connect_timeout.is_some()
+                || self.client_identity.is_some()
+                || !self.host_overrides.is_empty()
+                || self.pool_max_idle_per_host.is_some()
+                || self.pool_idle_timeout.is_some()
+                || self.http_version.is_some()
+            {
+                tracing::warn!(
+                    "the proxy, connect timeout, client identity, host override, connection pool, and HTTP version settings are ignored with a custom HTTP client"
+                );
+            }
+
+            Vec::new()
+        } else {
+            self.proxies()?
+        };
+        let identity = self
+            .client_identity
+            .as_ref()
+            .filter(|_| self.http_client.is_none())
+            .map(|(cert, key)| load_identity(cert, key))
+            .transpose()?;
+
+        // Routed registries share the metrics of the client
+        let metrics = Arc::new(Metrics::default());
+        let api = self
+            .api(
+                &self.url,
+                self.mirrors.clone(),
+                self.auth_token.clone(),
+                &proxies,
+                identity.as_ref(),
+            )?
+            .with_metrics(metrics.clone());
+        let routes = std::mem::take(&mut self.routes)
+            .into_iter()
+            .map(|route| {
+                let api = self
+                    .api(
+                        &route.url,
+                        Vec::new(),
+                        route.auth_token,
+                        &proxies,
+                        identity.as_ref(),
+                    )?
+                    .with_metrics(metrics.clone());
+                Ok((api, route.registry, route.namespaces))
+            })
+            .collect::<ClientResult<Vec<_>>>()?;
+
+        let content = Arc::new(self.content);
+        let client = |registry, api| Client {
+            registry,
+            content: content.clone(),
+            api,
+            max_concurrent_downloads: self.max_concurrent_downloads,
+            verify_concurrency: self.verify_concurrency,
+            offline: self.offline,
+            verify_proofs: self.verify_proofs,
+            allow_unverified: self.allow_unverified,
+            checkpoint_min_age: self.checkpoint_min_age,
+            skip_existing_content: self.skip_existing_content,
+            retry_identity_encoding: self.retry_identity_encoding,
+            max_content_retries: self.max_content_retries,
+            progress: self.progress.clone(),
+            verification_failure: self.verification_failure.clone(),
+            metrics: metrics.clone(),
+            cancel: self.cancel.clone(),
+            routes: Vec::new(),
+            namespaces: HashMap::new(),
+            missing_packages: Default::default(),
+        };
+
+        let mut namespaces = HashMap::new();
+        let mut clients = Vec::with_capacity(routes.len());
+        for (api, registry, route_namespaces) in routes {
+            namespaces.extend(route_namespaces.into_iter().map(|ns| (ns, clients.len())));
+            clients.push(client(registry, api));
+        }
+
+        Ok(Client {
+            routes: clients,
+            namespaces,
+            ..client(self.registry, api)
+        })
+    }
+
+    /// Creates the API client for the given registry URL.
+    fn api(
+        &self,
+        url: &RegistryUrl,
+        mirrors: Vec<RegistryUrl>,
+        auth_token: Option<String>,
+        proxies: &[Proxy],
+        identity: Option<&Identity>,
+    ) -> ClientResult<api::Client> {
+        let mut api = api::Client::from_registry_url(url)?
+            .with_mirrors(mirrors)
+            .with_max_retries(self.max_retries)
+            .with_retry_base_delay(self.retry_base_delay)
+            .with_max_requests_per_second(self.max_requests_per_second)
+            .with_max_response_bytes(self.max_response_bytes)
+            .with_compression(self.compress_content);
+        if let Some(rewriter) = &self.content_url_rewriter {
+            api = api.with_content_url_rewriter(rewriter.clone());
+        }
+        if !self.default_headers.is_empty() {
+            api = api.with_default_headers(self.default_headers.clone())?;
+        }
+
+        let user_agent = self
+            .user_agent
+            .as_deref()
+            .unwrap_or(api::DEFAULT_USER_AGENT);
+        match &self.user_agent_suffix {
+            Some(suffix) => api = api.with_user_agent(&format!("{user_agent} {suffix}"))?,
+            None if self.user_agent.is_some() => api = api.with_user_agent(user_agent)?,
+            None => {}
+        }
+
+        if let Some(token) = auth_token {
+            api = api.with_auth_token(token);
+        }
+
+        if let Some(client) = &self.http_client {
+            api = api.with_http_client(client.clone());
+        } else {
+            if !proxies.is_empty() {
+                api = api.with_proxies(proxies.to_vec())?;
+            }
+
+            if let Some(timeout) = self.connect_timeout {
+                api = api.with_connect_timeout(timeout)?;
+            }
+
+            if let Some(identity) = identity {
+                api = api.with_identity(identity.clone())?;
+            }
+
+            if !self.host_overrides.is_empty() {
+                api = api.with_host_overrides(self.host_overrides.clone())?;
+            }
+
+            if let Some(max) = self.pool_max_idle_per_host {
+                api = api.with_pool_max_idle_per_host(max)?;
+            }
+
+            if let Some(timeout) = self.pool_idle_timeout {
+                api = api.with_pool_idle_timeout(timeout)?;
+            }
+
+            if let Some(version) = self.http_version {
+                api = api.with_http_version(version)?;
+            }
+        }
+
+        if let Some(timeout) = self.request_timeout {
+            api = api.with_request_timeout(timeout);
+        }
+
+        if let Some(timeout) = self.content_transfer_timeout {
+            api = api.with_transfer_timeout(timeout);
+        }
+
+        if let Some(max) = self.max_content_bytes {
+            api = api.with_max_content_bytes(max);
+        }
+
+        Ok(api)
+    }
+
+    /// Resolves the proxies to use from the builder and the environment.
+    ///
+    /// The proxy and bypassed hosts set on the builder take precedence over
+    /// the environment.
+    fn proxies(&self) -> ClientResult<Vec<Proxy>> {
+        fn var(name: &str) -> Option<String> {
+            env::var(name)
+                .or_else(|_| env::var(name.to_lowercase()))
+                .ok()
+                .filter(|value| !value.is_empty())
+        }
+
+        let no_proxy = if self.no_proxy.is_empty() {
+            NoProxy::from_env()
+        } else {
+            NoProxy::from_string(&self.no_proxy.join(","))
+        };
+
+        let proxy = |url: String, new: fn(String) -> reqwest::Result<Proxy>| {
+            new(url.clone())
+                .map(|proxy| proxy.no_proxy(no_proxy.clone()))
+                .map_err(|e| ClientError::InvalidProxyUrl {
+                    url,
+                    message: e.to_string(),
+                })
+        };
+
+        match &self.proxy {
+            Some(url) => Ok(vec![proxy(url.clone(), Proxy::all)?]),
+            None => [
+                var("HTTPS_PROXY").map(|url| proxy(url, Proxy::https)),
+                var("HTTP_PROXY").map(|url| proxy(url, Proxy::http)),
+            ]
+            .into_iter()
+            .flatten()
+            .collect(),
+        }
+    }
+}
+
+/// Loads the TLS client identity from the given PEM-encoded certificate and
+/// private key files.
+fn load_identity(cert: &Path, key: &Path) -> ClientResult<Identity> {
+    let invalid = |message: String| ClientError::InvalidClientIdentity {
+        cert: cert.to_path_buf(),
+        key: key.to_path_buf(),
+        message,
+    };
+    let read = |path: &Path| {
+        fs::read(path).map_err(|e| {
+            invalid(format!(
+                "failed to read `{path}`: {e}",
+                path = path.display()
+            ))
+        })
+    };
+
+    Identity::from_pkcs8_pem(&read(cert)?, &read(key)?).map_err(|e| invalid(e.to_string()))
+}
@@ -0,0 +1,162 @@
+//! A module for portable package log bundles.
+//!
+//! A bundle is a single file with the following layout:
+//!
+//! * the magic bytes `WARGPKG\0`;
+//! * the format version as a little-endian `u32`;
+//! * the length of the manifest as a little-endian `u64`;
+//! * the JSON-encoded manifest;
+//! * the content blobs, in manifest order.
+
+use crate::ClientError;
+use anyhow::{Context, Result};
+use bytes::Bytes;
+use futures_util::{Stream, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use std::{io::SeekFrom, path::Path, pin::Pin};
+use tokio::{
+    fs::File,
+    io::{AsyncReadExt, AsyncSeekExt},
+};
+use tokio_util::io::ReaderStream;
+use warg_api::v1::proof::InclusionResponse;
+use warg_crypto::hash::AnyHash;
+use warg_protocol::{
+    registry::{PackageId, TimestampedCheckpoint},
+    PublishedProtoEnvelopeBody, SerdeEnvelope,
+};
+
+/// The magic bytes at the start of every bundle.
+const MAGIC: &[u8; 8] = b"WARGPKG\0";
+
+/// The current version of the bundle format.
+///
+/// Version 2 added the operator log and the inclusion proof of the log
+/// heads to the manifest.
+pub const BUNDLE_FORMAT_VERSION: u32 = 2;
+
+/// The length of the bundle header preceding the manifest.
+const HEADER_LEN: usize = MAGIC.len() + 4 + 8;
+
+/// Represents the manifest of a package bundle.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Manifest {
+    /// The id of the bundled package.
+    pub id: PackageId,
+    /// The checkpoint the bundled package log was exported at.
+    pub checkpoint: SerdeEnvelope<TimestampedCheckpoint>,
+    /// The records of the operator log, in order.
+    ///
+    /// The operator log authorizes the key that signed the checkpoint.
+    pub operator: Vec<PublishedProtoEnvelopeBody>,
+    /// The records of the package log, in order.
+    pub records: Vec<PublishedProtoEnvelopeBody>,
+    /// The proof that the heads of the operator and package logs, in that
+    /// order, are included in the checkpoint.
+    pub inclusion: InclusionResponse,
+    /// The bundled content, in the order the blobs follow the manifest.
+    pub contents: Vec<ManifestContent>,
+}
+
+/// Represents a content blob in a package bundle.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ManifestContent {
+    /// The digest of the content.
+    pub digest: AnyHash,
+    /// The size of the content, in bytes.
+    pub size: u64,
+}
+
+/// Encodes the header and manifest of a bundle.
+///
+/// The content blobs are written after the encoded manifest, in manifest
+/// order.
+pub(crate) fn encode_manifest(manifest: &Manifest) -> Result<Vec<u8>, ClientError> {
+    let manifest = serde_json::to_vec(manifest).map_err(anyhow::Error::from)?;
+
+    let mut bundle = Vec::with_capacity(HEADER_LEN + manifest.len());
+    bundle.extend_from_slice(MAGIC);
+    bundle.extend_from_slice(&BUNDLE_FORMAT_VERSION.to_le_bytes());
+    bundle.extend_from_slice(&(manifest.len() as u64).to_le_bytes());
+    bundle.extend_from_slice(&manifest);
+    Ok(bundle)
+}
+
+/// Reads the header and manifest of a bundle.
+///
+/// Returns the manifest and the offset of the first content blob. The
+/// length of the bundle is checked against the content sizes in the
+/// manifest, but the content blobs are neither read nor verified against
+/// their digests.
+pub(crate) async fn read_manifest(path: &Path) -> Result<(Manifest, u64), ClientError> {
+    let invalid = |reason: &str| ClientError::InvalidBundle {
+        reason: reason.to_string(),
+    };
+    let read_error = || format!("failed to read bundle `{path}`", path = path.display());
+
+    let mut file = File::open(path).await.with_context(read_error)?;
+    let len = file.metadata().await.with_context(read_error)?.len();
+
+    let mut header = [0; HEADER_LEN];
+    if len < HEADER_LEN as u64 {
+        return Err(invalid("the file is not a package bundle"));
+    }
+    file.read_exact(&mut header)
+        .await
+        .with_context(read_error)?;
+    if &header[..MAGIC.len()] != MAGIC {
+        return Err(invalid("the file is not a package bundle"));
+    }
+
+    let version = u32::from_le_bytes(header[MAGIC.len()..MAGIC.len() + 4].try_into().unwrap());
+    if version != BUNDLE_FORMAT_VERSION {
+        return Err(ClientError::InvalidBundle {
+            reason: format!("unsupported bundle format version {version}"),
+        });
+    }
+
+    let manifest_len = u64::from_le_bytes(header[MAGIC.len() + 4..].try_into().unwrap());
+    let offset = (HEADER_LEN as u64)
+        .checked_add(manifest_len)
+        .filter(|offset| *offset <= len)
+        .ok_or_else(|| invalid("the bundle manifest is truncated"))?;
+    let mut manifest = vec![0; manifest_len as usize];
+    file.read_exact(&mut manifest)
+        .await
+        .with_context(read_error)?;
+    let manifest: Manifest =
+        serde_json::from_slice(&manifest).map_err(|e| ClientError::InvalidBundle {
+            reason: format!("the bundle manifest is invalid: {e}"),
+        })?;
+
+    let end = manifest
+        .contents
+        .iter()
+        .try_fold(offset, |end, content| end.checked_add(content.size))
+        .filter(|end| *end <= len)
+        .ok_or_else(|| invalid("the bundle content is truncated"))?;
+    if end != len {
+        return Err(invalid("the bundle has trailing data"));
+    }
+
+    Ok((manifest, offset))
+}
+
+/// Streams a content blob of a bundle at the given offset.
+pub(crate) async fn read_blob(
+    path: &Path,
+    offset: u64,
+    size: u64,
+) -> Result<Pin<Box<dyn Stream<Item = Result<Bytes>> + Send + Sync>>, ClientError> {
+    let read_error = || format!("failed to read bundle `{path}`", path = path.display());
+    let mut file = File::open(path).await.with_context(read_error)?;
+    file.seek(SeekFrom::Start(offset))
+        .await
+        .with_context(read_error)?;
+
+    Ok(Box::pin(
+        ReaderStream::new(file.take(size)).map_err(anyhow::Error::from),
+    ))
+}
@@ -1,20 +1,33 @@
 //! Module for client configuration.
 
-use crate::{ClientError, RegistryUrl};
+use crate::{
+    api::HttpVersion,
+    storage::{ContentStorage, FileSystemContentStorage, RegistryStorage},
+    Client, ClientBuilder, ClientError, RegistryUrl,
+};
 use anyhow::{anyhow, Context, Result};
+use indexmap::IndexMap;
 use normpath::PathExt;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::{BTreeMap, HashMap},
     env::current_dir,
+    fmt,
     fs::{self, File},
+    net::SocketAddr,
     path::{Component, Path, PathBuf},
+    str::FromStr,
+    time::Duration,
 };
 
 static CACHE_DIR: Lazy<Option<PathBuf>> = Lazy::new(dirs::cache_dir);
 static CONFIG_DIR: Lazy<Option<PathBuf>> = Lazy::new(dirs::config_dir);
 static CONFIG_FILE_NAME: &str = "warg-config.json";
 
+/// The prefix of the environment variables read by [`Config::from_env`].
+const ENV_PREFIX: &str = "WARG_";
+
 fn find_warg_config(cwd: &Path) -> Option<PathBuf> {
     let mut current = Some(cwd);
 
@@ -69,6 +82,60 @@ pub struct StoragePaths {
     pub content_dir: PathBuf,
 }
 
+/// The name of the profile implied by the top-level configuration.
+pub const DEFAULT_PROFILE_NAME: &str = "default";
+
+/// Represents a named registry profile in the client configuration.
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    /// The Warg registry server URL of the profile.
+    pub url: String,
+
+    /// The name of the signing key to use with the profile's registry.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_name: Option<String>,
+
+    /// The path to the signing key file to use with the profile's registry.
+    ///
+    /// This path is expected to be relative to the configuration file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_file: Option<PathBuf>,
+
+    /// The authentication token to use with the profile's registry.
+    ///
+    /// A token of the form `${NAME}` is read from the environment variable
+    /// `NAME` so that the token need not be stored in the configuration.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_token: Option<String>,
+}
+
+impl Profile {
+    /// Resolves the authentication token of the profile.
+    ///
+    /// A token referencing an environment variable is read from the
+    /// environment; an error is returned if the variable is not set.
+    pub fn resolve_auth_token(&self) -> Result<Option<String>, ClientError> {
+        let Some(token) = &self.auth_token else {
+            return Ok(None);
+        };
+
+        match token
+            .strip_prefix("${")
+            .and_then(|name| name.strip_suffix('}'))
+        {
+            Some(name) => {
+                std::env::var(name)
+                    .map(Some)
+                    .map_err(|_| ClientError::AuthTokenVariableNotSet {
+                        name: name.to_string(),
+                    })
+            }
+            None => Ok(Some(token.clone())),
+        }
+    }
+}
+
 /// Represents the Warg client configuration.
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -77,6 +144,26 @@ pub struct Config {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub default_url: Option<String>,
 
+    /// The authentication token to use with the default registry.
+    ///
+    /// A token of the form `${NAME}` is read from the environment variable
+    /// `NAME` so that the token need not be stored in the configuration.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_token: Option<String>,
+
+    /// The name of the profile to use when one is not specified.
+    ///
+    /// If `None`, the `default` profile is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_profile: Option<String>,
+
+    /// The named registry profiles.
+    ///
+    /// If a `default` profile is not present, the `default` profile is
+    /// implied by the `default_url` of the configuration.
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub profiles: IndexMap<String, Profile>,
+
     /// The path to the top-level directory where per-registry information is stored.
     ///
     /// This path is expected to be relative to the configuration file.
@@ -94,6 +181,182 @@ pub struct Config {
     /// `$CACHE_DIR` is the platform-specific cache directory.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub content_dir: Option<PathBuf>,
+
+    /// The path to the directory where temporary files and partial downloads
+    /// are stored.
+    ///
+    /// This path is expected to be relative to the configuration file.
+    ///
+    /// Completed downloads are moved from this directory into the content
+    /// directory, so both should be on the same file system.
+    ///
+    /// If `None`, a subdirectory of the content directory is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temp_dir: Option<PathBuf>,
+
+    /// The maximum total size, in bytes, of the content in the content
+    /// directory.
+    ///
+    /// When the limit is exceeded, the least recently accessed content is
+    /// evicted. If `None`, the size of the content directory is unlimited.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_cache_bytes: Option<u64>,
+
+    /// The maximum number of content downloads to perform concurrently.
+    ///
+    /// If `None`, the default of 4 concurrent downloads is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrent_downloads: Option<usize>,
+
+    /// The number of content blobs to re-hash concurrently when verifying
+    /// client storage.
+    ///
+    /// If `None`, the default of 4 concurrent blobs is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verify_concurrency: Option<usize>,
+
+    /// The maximum number of requests per second to send to each registry.
+    ///
+    /// If `None`, requests are not limited.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_requests_per_second: Option<u32>,
+
+    /// The maximum size of a package log fetch or proof response, in bytes.
+    ///
+    /// If `None`, the default of 64 MiB is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_response_bytes: Option<u64>,
+
+    /// The maximum size of downloaded content, in bytes.
+    ///
+    /// If `None`, the size of content is not limited.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_content_bytes: Option<u64>,
+
+    /// Whether the client operates in offline mode.
+    ///
+    /// An offline client reads package logs and content solely from client
+    /// storage and never makes requests to the registry.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub offline: bool,
+
+    /// Whether to verify inclusion and consistency proofs for fetched records.
+    ///
+    /// If `None`, proofs are verified.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verify_proofs: Option<bool>,
+
+    /// Whether to accept package logs that cannot be verified because the
+    /// registry does not serve inclusion or consistency proofs.
+    ///
+    /// Unverified package logs are rejected by default.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub allow_unverified: bool,
+
+    /// The minimum age, in seconds, of registry checkpoints the client
+    /// accepts.
+    ///
+    /// Checkpoints timestamped more recently are rejected to mitigate
+    /// split-view attacks, in which a registry presents a log to a single
+    /// client that other clients have not witnessed.
+    ///
+    /// If `None`, checkpoints of any age are accepted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checkpoint_min_age: Option<u64>,
+
+    /// Whether to skip uploading content the registry already holds.
+    ///
+    /// If `None`, existing content is skipped.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skip_existing_content: Option<bool>,
+
+    /// Whether to compress content transfers with zstd.
+    ///
+    /// Compression is only used with registries that support it.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub compress_content: bool,
+
+    /// Whether to download content that fails verification once more with
+    /// identity encoding when compression is enabled.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub retry_identity_encoding: bool,
+
+    /// The URLs of mirrors of the registry to fall back to for reads.
+    ///
+    /// Mirrors are tried in order when the registry is unreachable.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mirrors: Vec<String>,
+
+    /// The URLs of the registries that packages are routed to, keyed by the
+    /// namespace of the packages.
+    ///
+    /// Packages in a namespace that is not mapped use the default registry.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub namespace_map: HashMap<String, String>,
+
+    /// The URL of the proxy to use for all requests, such as
+    /// `http://proxy.example.com:8080` or `socks5://127.0.0.1:1080`.
+    ///
+    /// If `None`, the `HTTPS_PROXY` and `HTTP_PROXY` environment variables
+    /// are used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+
+    /// The hosts for which requests bypass the proxy.
+    ///
+    /// If empty, the `NO_PROXY` environment variable is used.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub no_proxy: Vec<String>,
+
+    /// The `User-Agent` header to send with every request.
+    ///
+    /// If `None`, the name and version of the client library are sent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+
+    /// The path to the PEM-encoded TLS client certificate for registries
+    /// requiring mutual TLS.
+    ///
+    /// This path is expected to be relative to the configuration file and
+    /// requires `client_key` to also be set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_cert: Option<PathBuf>,
+
+    /// The path to the PEM-encoded private key of the TLS client certificate.
+    ///
+    /// This path is expected to be relative to the configuration file and
+    /// requires `client_cert` to also be set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_key: Option<PathBuf>,
+
+    /// The addresses to connect to instead of resolving the given hosts,
+    /// keyed by host name, such as `{"registry.example.com": "10.0.0.5:443"}`.
+    ///
+    /// Requests keep the host name, so the `Host` header and the TLS server
+    /// name are unchanged. The port of an address is ignored.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub host_overrides: HashMap<String, SocketAddr>,
+
+    /// The maximum number of idle connections to each host that are kept
+    /// open for reuse.
+    ///
+    /// If `None`, the default of 32 connections is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pool_max_idle_per_host: Option<usize>,
+
+    /// The time an idle connection is kept open for reuse, in seconds.
+    ///
+    /// If `None`, the default of 300 seconds is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pool_idle_timeout: Option<u64>,
+
+    /// The HTTP version used for requests: `auto`, `http1`, or `http2`.
+    ///
+    /// If `None`, HTTP/2 is used when negotiated over TLS; `http1` forces
+    /// HTTP/1.1 for compatibility with servers or proxies that mishandle
+    /// HTTP/2.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_version: Option<HttpVersion>,
 }
 
 impl Config {
@@ -114,6 +377,12 @@ impl Config {
         if let Some(parent) = path.parent() {
             config.registries_dir = config.registries_dir.map(|p| parent.join(p));
             config.content_dir = config.content_dir.map(|p| parent.join(p));
+            config.temp_dir = config.temp_dir.map(|p| parent.join(p));
+            config.client_cert = config.client_cert.map(|p| parent.join(p));
+            config.client_key = config.client_key.map(|p| parent.join(p));
+            for profile in config.profiles.values_mut() {
+                profile.key_file = profile.key_file.take().map(|p| parent.join(p));
+            }
         }
 
         Ok(config)
@@ -153,18 +422,32 @@ impl Config {
 
         assert!(parent.is_absolute());
 
+        let relative = |p: &PathBuf| {
+            let p = normalize_path(parent.join(p).as_path());
+            assert!(p.is_absolute());
+            pathdiff::diff_paths(&p, &parent).unwrap()
+        };
+
         let config = Config {
-            default_url: self.default_url.clone(),
-            registries_dir: self.registries_dir.as_ref().map(|p| {
-                let p = normalize_path(parent.join(p).as_path());
-                assert!(p.is_absolute());
-                pathdiff::diff_paths(&p, &parent).unwrap()
-            }),
-            content_dir: self.content_dir.as_ref().map(|p| {
-                let p = normalize_path(parent.join(p).as_path());
-                assert!(p.is_absolute());
-                pathdiff::diff_paths(&p, &parent).unwrap()
-            }),
+            registries_dir: self.registries_dir.as_ref().map(relative),
+            content_dir: self.content_dir.as_ref().map(relative),
+            temp_dir: self.temp_dir.as_ref().map(relative),
+            client_cert: self.client_cert.as_ref().map(relative),
+            client_key: self.client_key.as_ref().map(relative),
+            profiles: self
+                .profiles
+                .iter()
+                .map(|(name, profile)| {
+                    (
+                        name.clone(),
+                        Profile {
+                            key_file: profile.key_file.as_ref().map(relative),
+                            ..profile.clone()
+                        },
+                    )
+                })
+                .collect(),
+            ..self.clone()
         };
 
         serde_json::to_writer_pretty(
@@ -209,6 +492,191 @@ impl Config {
             .ok_or_else(|| anyhow!("failed to determine operating system configuration directory"))
     }
 
+    /// Loads the client configuration from the default configuration file
+    /// and the environment.
+    ///
+    /// Settings are taken, in order of precedence, from:
+    ///
+    /// * the environment (see [`Config::from_env`])
+    /// * the default configuration file (see [`Config::from_default_file`])
+    /// * the defaults
+    ///
+    /// Values passed explicitly when creating a client, such as a registry
+    /// URL, take precedence over the configuration.
+    pub fn load() -> Result<Self> {
+        Ok(Self::from_default_file()?
+            .unwrap_or_default()
+            .merge(Self::from_env()?))
+    }
+
+    /// Creates a client configuration from environment variables.
+    ///
+    /// The following variables are read; a variable that is not set or is
+    /// empty leaves the setting unset:
+    ///
+    /// * `WARG_DEFAULT_URL` - the default registry URL
+    /// * `WARG_DEFAULT_PROFILE` - the default profile name
+    /// * `WARG_AUTH_TOKEN` - the authentication token of the default registry
+    /// * `WARG_HOME` - a directory containing the `registries` and `content`
+    ///   directories
+    /// * `WARG_REGISTRIES_DIR`, `WARG_CONTENT_DIR` and `WARG_TEMP_DIR` - the
+    ///   storage directories, taking precedence over `WARG_HOME`
+    /// * `WARG_MAX_CACHE_BYTES`, `WARG_MAX_CONCURRENT_DOWNLOADS`,
+    ///   `WARG_MAX_REQUESTS_PER_SECOND`, `WARG_MAX_RESPONSE_BYTES`,
+    ///   `WARG_MAX_CONTENT_BYTES` and `WARG_VERIFY_CONCURRENCY` - the client
+    ///   limits
+    /// * `WARG_OFFLINE`, `WARG_VERIFY_PROOFS`, `WARG_ALLOW_UNVERIFIED`,
+    ///   `WARG_SKIP_EXISTING_CONTENT`, `WARG_COMPRESS_CONTENT` and
+    ///   `WARG_RETRY_IDENTITY_ENCODING` - `true` or `false`
+    /// * `WARG_MIRRORS` - a comma-separated list of mirror URLs
+    /// * `WARG_PROXY` and `WARG_USER_AGENT`
+    ///
+    /// An error is returned if a variable has an invalid value.
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// Creates a client configuration from variables looked up by name.
+    fn from_vars(lookup: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        fn parse<T>(var: Option<(String, String)>) -> Result<Option<T>>
+        where
+            T: FromStr,
+            T::Err: fmt::Display,
+        {
+            var.map(|(name, value)| {
+                value.parse().map_err(|e| {
+                    anyhow!("invalid value `{value}` for environment variable `{name}`: {e}")
+                })
+            })
+            .transpose()
+        }
+
+        let var = |name: &str| {
+            let name = format!("{ENV_PREFIX}{name}");
+            lookup(&name)
+                .filter(|value| !value.is_empty())
+                .map(|value| (name, value))
+        };
+        let flag = |name: &str| {
+            var(name)
+                .map(|(name, value)| match value.as_str() {
+                    "true" | "1" => Ok(true),
+                    "false" | "0" => Ok(false),
+                    _ => Err(anyhow!(
+                        "invalid value `{value}` for environment variable `{name}`: expected `true` or `false`"
+                    )),
+                })
+                .transpose()
+        };
+        let url = |(name, value): (String, String)| {
+            RegistryUrl::new(&value)
+                .map(|url| url.to_string())
+                .map_err(|e| {
+                    anyhow!("invalid value `{value}` for environment variable `{name}`: {e}")
+                })
+        };
+
+        let home = var("HOME").map(|(_, value)| PathBuf::from(value));
+        let dir = |name: &str, subdir: &str| {
+            var(name)
+                .map(|(_, value)| PathBuf::from(value))
+                .or_else(|| home.as_ref().map(|home| home.join(subdir)))
+        };
+
+        Ok(Self {
+            default_url: var("DEFAULT_URL").map(url).transpose()?,
+            auth_token: var("AUTH_TOKEN").map(|(_, value)| value),
+            default_profile: var("DEFAULT_PROFILE").map(|(_, value)| value),
+            registries_dir: dir("REGISTRIES_DIR", "registries"),
+            content_dir: dir("CONTENT_DIR", "content"),
+            temp_dir: var("TEMP_DIR").map(|(_, value)| PathBuf::from(value)),
+            max_cache_bytes: parse(var("MAX_CACHE_BYTES"))?,
+            max_concurrent_downloads: parse(var("MAX_CONCURRENT_DOWNLOADS"))?,
+            verify_concurrency: parse(var("VERIFY_CONCURRENCY"))?,
+            max_requests_per_second: parse(var("MAX_REQUESTS_PER_SECOND"))?,
+            max_response_bytes: parse(var("MAX_RESPONSE_BYTES"))?,
+            max_content_bytes: parse(var("MAX_CONTENT_BYTES"))?,
+            offline: flag("OFFLINE")?.unwrap_or_default(),
+            verify_proofs: flag("VERIFY_PROOFS")?,
+            allow_unverified: flag("ALLOW_UNVERIFIED")?.unwrap_or_default(),
+            skip_existing_content: flag("SKIP_EXISTING_CONTENT")?,
+            compress_content: flag("COMPRESS_CONTENT")?.unwrap_or_default(),
+            retry_identity_encoding: flag("RETRY_IDENTITY_ENCODING")?.unwrap_or_default(),
+            mirrors: var("MIRRORS")
+                .map(|(name, value)| {
+                    value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|mirror| !mirror.is_empty())
+                        .map(|mirror| url((name.clone(), mirror.to_string())))
+                        .collect::<Result<_>>()
+                })
+                .transpose()?
+                .unwrap_or_default(),
+            proxy: var("PROXY").map(|(_, value)| value),
+            user_agent: var("USER_AGENT").map(|(_, value)| value),
+            ..Default::default()
+        })
+    }
+
+    /// Merges the given configuration into this configuration.
+    ///
+    /// Settings of the given configuration take precedence; settings it
+    /// leaves unset keep the value of this configuration. Profiles,
+    /// namespace mappings and host overrides are combined, with those of the
+    /// given configuration replacing any of the same name.
+    pub fn merge(mut self, other: Self) -> Self {
+        self.profiles.extend(other.profiles);
+        self.namespace_map.extend(other.namespace_map);
+        self.host_overrides.extend(other.host_overrides);
+
+        Self {
+            default_url: other.default_url.or(self.default_url),
+            auth_token: other.auth_token.or(self.auth_token),
+            default_profile: other.default_profile.or(self.default_profile),
+            profiles: self.profiles,
+            registries_dir: other.registries_dir.or(self.registries_dir),
+            content_dir: other.content_dir.or(self.content_dir),
+            temp_dir: other.temp_dir.or(self.temp_dir),
+            max_cache_bytes: other.max_cache_bytes.or(self.max_cache_bytes),
+            max_concurrent_downloads: other
+                .max_concurrent_downloads
+                .or(self.max_concurrent_downloads),
+            verify_concurrency: other.verify_concurrency.or(self.verify_concurrency),
+            max_requests_per_second: other
+                .max_requests_per_second
+                .or(self.max_requests_per_second),
+            max_response_bytes: other.max_response_bytes.or(self.max_response_bytes),
+            max_content_bytes: other.max_content_bytes.or(self.max_content_bytes),
+            offline: other.offline || self.offline,
+            verify_proofs: other.verify_proofs.or(self.verify_proofs),
+            allow_unverified: other.allow_unverified || self.allow_unverified,
+            checkpoint_min_age: other.checkpoint_min_age.or(self.checkpoint_min_age),
+            skip_existing_content: other.skip_existing_content.or(self.skip_existing_content),
+            compress_content: other.compress_content || self.compress_content,
+            retry_identity_encoding: other.retry_identity_encoding || self.retry_identity_encoding,
+            mirrors: if other.mirrors.is_empty() {
+                self.mirrors
+            } else {
+                other.mirrors
+            },
+            namespace_map: self.namespace_map,
+            proxy: other.proxy.or(self.proxy),
+            no_proxy: if other.no_proxy.is_empty() {
+                self.no_proxy
+            } else {
+                other.no_proxy
+            },
+            user_agent: other.user_agent.or(self.user_agent),
+            client_cert: other.client_cert.or(self.client_cert),
+            client_key: other.client_key.or(self.client_key),
+            host_overrides: self.host_overrides,
+            pool_max_idle_per_host: other.pool_max_idle_per_host.or(self.pool_max_idle_per_host),
+            pool_idle_timeout: other.pool_idle_timeout.or(self.pool_idle_timeout),
+            http_version: other.http_version.or(self.http_version),
+        }
+    }
+
     /// Gets the path to the directory where per-registry packages are stored.
     pub fn registries_dir(&self) -> Result<PathBuf> {
         self.registries_dir
@@ -237,14 +705,221 @@ impl Config {
             })
     }
 
+    /// Gets the registry profile with the given name.
+    ///
+    /// Returns `None` if the profile is not defined in the configuration.
+    ///
+    /// Note that the implied `default` profile is not returned; use
+    /// [`Config::resolve_profile`] to resolve it.
+    pub fn profile(&self, name: &str) -> Option<&Profile> {
+        self.profiles.get(name)
+    }
+
+    /// Resolves the registry profile with the given name.
+    ///
+    /// If the name is `None`, the configuration's default profile is used.
+    ///
+    /// If the `default` profile is not defined, it is implied by the
+    /// `default_url` of the configuration.
+    ///
+    /// Returns an error if the profile does not exist.
+    pub fn resolve_profile(&self, name: Option<&str>) -> Result<Profile, ClientError> {
+        let name = name
+            .or(self.default_profile.as_deref())
+            .unwrap_or(DEFAULT_PROFILE_NAME);
+
+        if let Some(profile) = self.profile(name) {
+            return Ok(profile.clone());
+        }
+
+        if name == DEFAULT_PROFILE_NAME {
+            return Ok(Profile {
+                url: self.default_url.clone().ok_or(ClientError::NoDefaultUrl)?,
+                auth_token: self.auth_token.clone(),
+                ..Default::default()
+            });
+        }
+
+        Err(ClientError::ProfileDoesNotExist {
+            name: name.to_string(),
+        })
+    }
+
+    /// Resolves the authentication token to use with the given registry.
+    ///
+    /// The token of the first profile for the registry is used; if no
+    /// profile is for the registry, the registry has no token.
+    pub fn resolve_auth_token(&self, url: &RegistryUrl) -> Result<Option<String>, ClientError> {
+        let url = url.to_string();
+        let implied = self
+            .default_url
+            .as_ref()
+            .filter(|_| !self.profiles.contains_key(DEFAULT_PROFILE_NAME))
+            .map(|default_url| Profile {
+                url: default_url.clone(),
+                auth_token: self.auth_token.clone(),
+                ..Default::default()
+            });
+
+        for profile in self.profiles.values().chain(implied.as_ref()) {
+            if RegistryUrl::new(&profile.url).map(|u| u.to_string()).ok() == Some(url.clone()) {
+                return profile.resolve_auth_token();
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Applies the configuration to the given client builder and builds
+    /// the client.
+    ///
+    /// The given authentication token, if any, is used with the registry.
+    pub(crate) fn apply<R: RegistryStorage, C: ContentStorage>(
+        &self,
+        mut builder: ClientBuilder<R, C>,
+        auth_token: Option<String>,
+    ) -> Result<Client<R, C>, ClientError> {
+        if let Some(token) = auth_token {
+            builder = builder.with_auth_token(token);
+        }
+
+        if let Some(max) = self.max_concurrent_downloads {
+            builder = builder.with_max_concurrent_downloads(max);
+        }
+
+        if let Some(concurrency) = self.verify_concurrency {
+            builder = builder.with_verify_concurrency(concurrency);
+        }
+
+        if let Some(max) = self.max_requests_per_second {
+            builder = builder.with_max_requests_per_second(max);
+        }
+
+        if let Some(max) = self.max_response_bytes {
+            builder = builder.with_max_response_bytes(max);
+        }
+
+        if let Some(max) = self.max_content_bytes {
+            builder = builder.with_max_content_bytes(max);
+        }
+
+        if let Some(verify) = self.verify_proofs {
+            builder = builder.with_verify_proofs(verify);
+        }
+
+        if let Some(secs) = self.checkpoint_min_age {
+            builder = builder.with_checkpoint_min_age(Duration::from_secs(secs));
+        }
+
+        if let Some(skip) = self.skip_existing_content {
+            builder = builder.with_skip_existing_content(skip);
+        }
+
+        if let Some(proxy) = &self.proxy {
+            builder = builder.with_proxy(proxy);
+        }
+
+        if !self.no_proxy.is_empty() {
+            builder = builder.with_no_proxy(&self.no_proxy);
+        }
+
+        if let Some(user_agent) = &self.user_agent {
+            builder = builder.with_user_agent(user_agent);
+        }
+
+        if !self.host_overrides.is_empty() {
+            builder = builder.with_host_overrides(self.host_overrides.clone());
+        }
+
+        if let Some(max) = self.pool_max_idle_per_host {
+            builder = builder.with_pool_max_idle_per_host(max);
+        }
+
+        if let Some(secs) = self.pool_idle_timeout {
+            builder = builder.with_pool_idle_timeout(Duration::from_secs(secs));
+        }
+
+        if let Some(version) = self.http_version {
+            builder = builder.with_http_version(version);
+        }
+
+        match (&self.client_cert, &self.client_key) {
+            (Some(cert), Some(key)) => builder = builder.with_client_identity(cert, key),
+            (None, None) => {}
+            _ => {
+                return Err(anyhow!(
+                    "both a TLS client certificate and key must be configured for mutual TLS"
+                )
+                .into())
+            }
+        }
+
+        if !self.mirrors.is_empty() {
+            builder = builder.with_mirrors(
+                self.mirrors
+                    .iter()
+                    .map(RegistryUrl::new)
+                    .collect::<Result<Vec<_>>>()?,
+            );
+        }
+
+        builder
+            .with_offline(self.offline)
+            .with_allow_unverified(self.allow_unverified)
+            .with_content_compression(self.compress_content)
+            .with_identity_encoding_retry(self.retry_identity_encoding)
+            .build()
+    }
+
+    /// Gets the registries that namespaces are routed to by the namespace
+    /// map, each with its namespaces.
+    ///
+    /// Namespaces mapped to the given registry are not routed.
+    pub(crate) fn namespace_routes(
+        &self,
+        url: &RegistryUrl,
+    ) -> Result<Vec<(RegistryUrl, Vec<String>)>, ClientError> {
+        let mut routes = BTreeMap::<String, (RegistryUrl, Vec<String>)>::new();
+        for (namespace, route) in &self.namespace_map {
+            let route = RegistryUrl::new(route)?;
+            if route.to_string() == url.to_string() {
+                continue;
+            }
+
+            routes
+                .entry(route.to_string())
+                .or_insert_with(|| (route, Vec::new()))
+                .1
+                .push(namespace.clone());
+        }
+
+        Ok(routes.into_values().collect())
+    }
+
+    /// Applies the configuration to the given file system content storage.
+    pub(crate) fn apply_content_storage(
+        &self,
+        content: FileSystemContentStorage,
+    ) -> Result<FileSystemContentStorage> {
+        let content = match &self.temp_dir {
+            Some(dir) => content.with_temp_dir(dir),
+            None => content,
+        };
+
+        match self.max_cache_bytes {
+            Some(max) => content.with_max_cache_bytes(max),
+            None => Ok(content),
+        }
+    }
+
     pub(crate) fn storage_paths_for_url(
         &self,
         url: Option<&str>,
     ) -> Result<StoragePaths, ClientError> {
-        let registry_url = RegistryUrl::new(
-            url.or(self.default_url.as_deref())
-                .ok_or(ClientError::NoDefaultUrl)?,
-        )?;
+        let registry_url = match url {
+            Some(url) => RegistryUrl::new(url)?,
+            None => RegistryUrl::new(self.resolve_profile(None)?.url)?,
+        };
 
         let label = registry_url.safe_label();
         let registries_dir = self.registries_dir()?.join(label);
@@ -256,3 +931,327 @@ impl Config {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_implied_default_profile() {
+        let config = Config {
+            default_url: Some("https://warg.io".to_string()),
+            ..Default::default()
+        };
+
+        assert!(config.profile(DEFAULT_PROFILE_NAME).is_none());
+        assert_eq!(config.resolve_profile(None).unwrap().url, "https://warg.io");
+        assert_eq!(
+            config
+                .resolve_profile(Some(DEFAULT_PROFILE_NAME))
+                .unwrap()
+                .url,
+            "https://warg.io"
+        );
+        assert!(matches!(
+            Config::default().resolve_profile(None),
+            Err(ClientError::NoDefaultUrl)
+        ));
+    }
+
+    #[test]
+    fn resolve_named_profiles() {
+        let config: Config = serde_json::from_str(
+            r#"{
+                "defaultUrl": "https://warg.io",
+                "defaultProfile": "staging",
+                "profiles": {
+                    "staging": { "url": "https://staging.warg.io", "keyName": "staging" },
+                    "local": { "url": "http://localhost:8090", "authToken": "secret" }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let staging = config.resolve_profile(None).unwrap();
+        assert_eq!(staging.url, "https://staging.warg.io");
+        assert_eq!(staging.key_name.as_deref(), Some("staging"));
+
+        let local = config.resolve_profile(Some("local")).unwrap();
+        assert_eq!(local.url, "http://localhost:8090");
+        assert_eq!(local.auth_token.as_deref(), Some("secret"));
+
+        assert_eq!(
+            config
+                .resolve_profile(Some(DEFAULT_PROFILE_NAME))
+                .unwrap()
+                .url,
+            "https://warg.io"
+        );
+
+        match config.resolve_profile(Some("missing")) {
+            Err(ClientError::ProfileDoesNotExist { name }) => assert_eq!(name, "missing"),
+            res => panic!("expected a missing profile error; got {res:?}"),
+        }
+    }
+
+    #[test]
+    fn resolve_auth_tokens() {
+        std::env::set_var("WARG_CONFIG_TEST_TOKEN", "from-env");
+
+        let config = Config {
+            default_url: Some("https://warg.io".to_string()),
+            auth_token: Some("default-secret".to_string()),
+            profiles: [
+                (
+                    "env".to_string(),
+                    Profile {
+                        url: "https://env.warg.io".to_string(),
+                        auth_token: Some("${WARG_CONFIG_TEST_TOKEN}".to_string()),
+                        ..Default::default()
+                    },
+                ),
+                (
+                    "unset".to_string(),
+                    Profile {
+                        url: "https://unset.warg.io".to_string(),
+                        auth_token: Some("${WARG_CONFIG_TEST_UNSET}".to_string()),
+                        ..Default::default()
+                    },
+                ),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+
+        let token = |url: &str| config.resolve_auth_token(&RegistryUrl::new(url).unwrap());
+        assert_eq!(token("warg.io").unwrap().as_deref(), Some("default-secret"));
+        assert_eq!(
+            token("https://env.warg.io/").unwrap().as_deref(),
+            Some("from-env")
+        );
+        assert_eq!(token("https://other.warg.io").unwrap(), None);
+        match token("https://unset.warg.io") {
+            Err(ClientError::AuthTokenVariableNotSet { name }) => {
+                assert_eq!(name, "WARG_CONFIG_TEST_UNSET")
+            }
+            res => panic!("expected an unset variable error; got {res:?}"),
+        }
+
+        assert_eq!(
+            config
+                .resolve_profile(None)
+                .unwrap()
+                .resolve_auth_token()
+                .unwrap()
+                .as_deref(),
+            Some("default-secret")
+        );
+    }
+
+    #[test]
+    fn apply_proxy_configuration() {
+        let config: Config = serde_json::from_str(
+            r#"{
+                "proxy": "socks5://127.0.0.1:1080",
+                "noProxy": ["localhost", "10.0.0.0/8"]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(config.proxy.as_deref(), Some("socks5://127.0.0.1:1080"));
+        assert_eq!(config.no_proxy, ["localhost", "10.0.0.0/8"]);
+
+        let dir = std::env::temp_dir().join(format!("warg-proxy-test-{}", std::process::id()));
+        let build = |config: &Config| {
+            config.apply(
+                Client::builder(
+                    "https://warg.io",
+                    crate::storage::FileSystemRegistryStorage::lock(dir.join("registries"))
+                        .unwrap(),
+                    FileSystemContentStorage::lock(dir.join("content")).unwrap(),
+                )
+                .unwrap(),
+                None,
+            )
+        };
+
+        build(&config).unwrap();
+
+        let invalid = Config {
+            proxy: Some("http://proxy example".to_string()),
+            ..Default::default()
+        };
+        match build(&invalid) {
+            Err(ClientError::InvalidProxyUrl { url, .. }) => {
+                assert_eq!(url, "http://proxy example")
+            }
+            res => panic!(
+                "expected an invalid proxy error; got {res:?}",
+                res = res.err()
+            ),
+        }
+
+        // The environment is only used when the configuration has no proxy
+        std::env::set_var("HTTPS_PROXY", "http://env proxy");
+        let from_env = build(&Config::default());
+        let from_config = build(&config);
+        std::env::remove_var("HTTPS_PROXY");
+        fs::remove_dir_all(&dir).unwrap();
+
+        match from_env {
+            Err(ClientError::InvalidProxyUrl { url, .. }) => assert_eq!(url, "http://env proxy"),
+            res => panic!(
+                "expected an invalid proxy error; got {res:?}",
+                res = res.err()
+            ),
+        }
+        from_config.unwrap();
+    }
+
+    #[test]
+    fn resolve_namespace_routes() {
+        let config: Config = serde_json::from_str(
+            r#"{
+                "defaultUrl": "https://warg.io",
+                "namespaceMap": {
+                    "my-org": "https://my-org.example.com",
+                    "my-team": "https://my-org.example.com/",
+                    "vendor": "https://vendor.example.com",
+                    "wasi": "https://warg.io"
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let routes = config
+            .namespace_routes(&RegistryUrl::new("https://warg.io").unwrap())
+            .unwrap()
+            .into_iter()
+            .map(|(url, mut namespaces)| {
+                namespaces.sort();
+                (url.to_string(), namespaces)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            routes,
+            [
+                (
+                    "https://my-org.example.com/".to_string(),
+                    vec!["my-org".to_string(), "my-team".to_string()]
+                ),
+                (
+                    "https://vendor.example.com/".to_string(),
+                    vec!["vendor".to_string()]
+                ),
+            ]
+        );
+    }
+
+    fn vars<'a>(vars: &'a [(&str, &str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| {
+            vars.iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    #[test]
+    fn env_overrides_file() {
+        let file: Config = serde_json::from_str(
+            r#"{
+                "defaultUrl": "https://warg.io",
+                "registriesDir": "/file/registries",
+                "contentDir": "/file/content",
+                "maxConcurrentDownloads": 2,
+                "verifyProofs": true,
+                "userAgent": "file"
+            }"#,
+        )
+        .unwrap();
+
+        let env = Config::from_vars(vars(&[
+            ("WARG_DEFAULT_URL", "https://env.example.com"),
+            ("WARG_HOME", "/env"),
+            ("WARG_CONTENT_DIR", "/env-content"),
+            ("WARG_MAX_CONCURRENT_DOWNLOADS", "8"),
+            ("WARG_VERIFY_PROOFS", "false"),
+            ("WARG_OFFLINE", "1"),
+            (
+                "WARG_MIRRORS",
+                "https://a.example.com, https://b.example.com",
+            ),
+            ("WARG_USER_AGENT", ""),
+        ]))
+        .unwrap();
+
+        let config = file.merge(env);
+        assert_eq!(
+            config.default_url.as_deref(),
+            Some("https://env.example.com/")
+        );
+        assert_eq!(
+            config.registries_dir.as_deref(),
+            Some(Path::new("/env/registries"))
+        );
+        assert_eq!(
+            config.content_dir.as_deref(),
+            Some(Path::new("/env-content"))
+        );
+        assert_eq!(config.max_concurrent_downloads, Some(8));
+        assert_eq!(config.verify_proofs, Some(false));
+        assert!(config.offline);
+        assert_eq!(
+            config.mirrors,
+            ["https://a.example.com/", "https://b.example.com/"]
+        );
+
+        // Empty or unset variables keep the file settings
+        assert_eq!(config.user_agent.as_deref(), Some("file"));
+        assert_eq!(config.max_cache_bytes, None);
+    }
+
+    #[test]
+    fn env_rejects_invalid_values() {
+        for (name, value, message) in [
+            (
+                "WARG_MAX_CACHE_BYTES",
+                "lots",
+                "invalid value `lots` for environment variable `WARG_MAX_CACHE_BYTES`",
+            ),
+            (
+                "WARG_OFFLINE",
+                "yes",
+                "invalid value `yes` for environment variable `WARG_OFFLINE`: expected `true` or `false`",
+            ),
+            (
+                "WARG_DEFAULT_URL",
+                "ftp://warg.io",
+                "invalid value `ftp://warg.io` for environment variable `WARG_DEFAULT_URL`",
+            ),
+            (
+                "WARG_MIRRORS",
+                "https://a.example.com,not a url",
+                "invalid value `not a url` for environment variable `WARG_MIRRORS`",
+            ),
+        ] {
+            let e = Config::from_vars(vars(&[(name, value)])).unwrap_err();
+            assert!(
+                e.to_string().starts_with(message),
+                "unexpected error for `{name}`: {e}"
+            );
+        }
+    }
+
+    #[test]
+    fn read_config_from_env() {
+        std::env::set_var("WARG_DEFAULT_PROFILE", "env-profile");
+        std::env::set_var("WARG_MAX_REQUESTS_PER_SECOND", "5");
+        let config = Config::from_env();
+        std::env::remove_var("WARG_DEFAULT_PROFILE");
+        std::env::remove_var("WARG_MAX_REQUESTS_PER_SECOND");
+
+        let config = config.unwrap();
+        assert_eq!(config.default_profile.as_deref(), Some("env-profile"));
+        assert_eq!(config.max_requests_per_second, Some(5));
+    }
+}
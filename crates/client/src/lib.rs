@@ -2,57 +2,121 @@
 
 #![deny(missing_docs)]
 
-use crate::storage::PackageInfo;
+use crate::{metrics::Metrics, storage::PackageInfo};
 use anyhow::{anyhow, Context, Result};
-use reqwest::{Body, IntoUrl};
-use std::{borrow::Cow, collections::HashMap, path::PathBuf, time::Duration};
+use bytes::Bytes;
+use futures_util::{Stream, StreamExt, TryStreamExt};
+use reqwest::IntoUrl;
+use serde::{Deserialize, Serialize};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant, SystemTime},
+};
 use storage::{
-    ContentStorage, FileSystemContentStorage, FileSystemRegistryStorage, PublishInfo,
-    RegistryStorage,
+    ContentStorage, FileSystemContentStorage, FileSystemRegistryStorage, GcStats, LogVerifyError,
+    OperatorInfo, PackageHandle, PublishEntry, PublishInfo, RegistryStorage, StorageReport,
+    StorageUsage, UploadInfo,
 };
 use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncWriteExt};
+use tokio_util::io::ReaderStream;
+use tracing::{field, Instrument};
 use warg_api::v1::{
     fetch::{FetchError, FetchLogsRequest, FetchLogsResponse},
     package::{
-        MissingContent, PackageError, PackageRecord, PackageRecordState, PublishRecordRequest,
-        UploadEndpoint,
+        ListPackagesQuery, MissingContent, PackageError, PackageRecord, PackageRecordState,
+        PublishRecordRequest, UploadEndpoint,
     },
     proof::{ConsistencyRequest, InclusionRequest},
 };
 use warg_crypto::{
     hash::{AnyHash, Hash, Sha256},
-    signing,
+    signing::{self, KeyID, PublicKey},
 };
 use warg_protocol::{
     operator, package,
-    registry::{LogId, LogLeaf, PackageId, RecordId, TimestampedCheckpoint},
-    PublishedProtoEnvelope, SerdeEnvelope, Version, VersionReq,
+    registry::{
+        Checkpoint, InvalidPackageIdError, LogId, LogLeaf, PackageId, RecordId, RegistryIndex,
+        RegistryLen, TimestampedCheckpoint,
+    },
+    ProtoEnvelope, PublishedProtoEnvelope, PublishedProtoEnvelopeBody, SerdeEnvelope, Version,
+    VersionReq,
 };
 
 pub mod api;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+mod builder;
+mod bundle;
 mod config;
 pub mod lock;
+mod metrics;
+mod progress;
+mod proof;
+mod record;
 mod registry_url;
+mod signer;
 pub mod storage;
+mod verification;
+pub use self::builder::*;
+pub use self::bundle::BUNDLE_FORMAT_VERSION;
 pub use self::config::*;
+pub use self::progress::*;
+pub use self::proof::InclusionProof;
+pub use self::record::PackageRecordBuilder;
 pub use self::registry_url::RegistryUrl;
+pub use self::signer::CommandSigner;
+pub use self::verification::*;
+pub use tokio_util::sync::CancellationToken;
 
 /// A client for a Warg registry.
 pub struct Client<R, C> {
     registry: R,
-    content: C,
+    content: Arc<C>,
     api: api::Client,
+    max_concurrent_downloads: usize,
+    verify_concurrency: usize,
+    offline: bool,
+    verify_proofs: bool,
+    allow_unverified: bool,
+    checkpoint_min_age: Option<Duration>,
+    skip_existing_content: bool,
+    retry_identity_encoding: bool,
+    max_content_retries: u32,
+    progress: Arc<dyn ProgressHandler>,
+    verification_failure: Arc<dyn VerificationFailureHandler>,
+    metrics: Arc<Metrics>,
+    cancel: CancellationToken,
+    routes: Vec<Client<R, C>>,
+    namespaces: HashMap<String, usize>,
+    missing_packages: Mutex<HashMap<PackageId, Instant>>,
 }
 
 impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
     /// Creates a new client for the given URL, registry storage, and
     /// content storage.
     pub fn new(url: impl IntoUrl, registry: R, content: C) -> ClientResult<Self> {
-        Ok(Self {
+        Self::builder(url, registry, content)?.build()
+    }
+
+    /// Creates a new client builder for the given URL, registry storage, and
+    /// content storage.
+    pub fn builder(
+        url: impl IntoUrl,
+        registry: R,
+        content: C,
+    ) -> ClientResult<ClientBuilder<R, C>> {
+        Ok(ClientBuilder::new(
+            RegistryUrl::new(url)?,
             registry,
             content,
-            api: api::Client::new(url)?,
-        })
+        ))
     }
 
     /// Gets the URL of the client.
@@ -70,6 +134,63 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
         &self.content
     }
 
+    /// Determines if the client is in offline mode.
+    pub fn offline(&self) -> bool {
+        self.offline
+    }
+
+    /// Gets the API client used to make requests to the registry.
+    ///
+    /// Returns an error if the client is in offline mode.
+    fn api(&self) -> ClientResult<&api::Client> {
+        if self.offline {
+            return Err(ClientError::Offline);
+        }
+
+        Ok(&self.api)
+    }
+
+    /// Gets the client for the registry the given package is routed to.
+    ///
+    /// Packages in a namespace without a route use this client.
+    fn routed(&self, id: &PackageId) -> &Self {
+        self.route(id.namespace())
+    }
+
+    /// Gets the client for the registry the given namespace is routed to.
+    fn route(&self, namespace: &str) -> &Self {
+        self.namespaces
+            .get(namespace)
+            .map_or(self, |index| &self.routes[*index])
+    }
+
+    /// Gets this client followed by the clients of every routed registry.
+    fn clients(&self) -> impl Iterator<Item = &Self> {
+        std::iter::once(self).chain(&self.routes)
+    }
+
+    /// Groups the given items by the client of the registry the package of
+    /// each item is routed to, preserving the order of the items.
+    fn group_by_route<T>(
+        &self,
+        items: impl IntoIterator<Item = T>,
+        id: impl Fn(&T) -> &PackageId,
+    ) -> Vec<(&Self, Vec<T>)> {
+        let mut groups: Vec<(Option<usize>, Vec<T>)> = Vec::new();
+        for item in items {
+            let route = self.namespaces.get(id(&item).namespace()).copied();
+            match groups.iter_mut().find(|(r, _)| *r == route) {
+                Some((_, group)) => group.push(item),
+                None => groups.push((route, vec![item])),
+            }
+        }
+
+        groups
+            .into_iter()
+            .map(|(route, items)| (route.map_or(self, |index| &self.routes[index]), items))
+            .collect()
+    }
+
     /// Submits the publish information in client storage.
     ///
     /// If there's no publishing information in client storage, an error is returned.
@@ -77,7 +198,10 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
     /// Returns the identifier of the record that was published.
     ///
     /// Use `wait_for_publish` to wait for the record to transition to the `published` state.
-    pub async fn publish(&self, signing_key: &signing::PrivateKey) -> ClientResult<RecordId> {
+    pub async fn publish(&self, signing_key: &dyn signing::Signer) -> ClientResult<RecordId> {
+        // Fail before the pending publish is cleared from storage
+        self.api()?;
+
         let info = self
             .registry
             .load_publish()
@@ -89,8 +213,39 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
         res
     }
 
+    /// Cancels the publish of the given package in client storage.
+    ///
+    /// The publish information and the state of any interrupted uploads of
+    /// its content are discarded; the content itself is kept until collected
+    /// by [`Client::gc`]. Records already submitted to the registry are not
+    /// affected.
+    ///
+    /// Returns [`ClientError::NotPublishing`] if the package is not being
+    /// published.
+    pub async fn cancel_publish(&self, id: &PackageId) -> ClientResult<()> {
+        let info = match self.registry.load_publish().await? {
+            Some(info) if info.id == *id => info,
+            _ => return Err(ClientError::NotPublishing),
+        };
+
+        for entry in &info.entries {
+            if let PublishEntry::Release { content, .. } = entry {
+                if self.content.load_upload(content).await?.is_some() {
+                    self.content.store_upload(content, None).await?;
+                }
+            }
+        }
+
+        self.registry.store_publish(None).await?;
+        tracing::info!("cancelled the publish of package `{id}`");
+        Ok(())
+    }
+
     /// Submits the provided publish information.
     ///
+    /// The record is signed with the given signer, such as a
+    /// [`signing::PrivateKey`] or a [`CommandSigner`].
+    ///
     /// Any publish information in client storage is ignored.
     ///
     /// Returns the identifier of the record that was published.
@@ -98,62 +253,282 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
     /// Use `wait_for_publish` to wait for the record to transition to the `published` state.
     pub async fn publish_with_info(
         &self,
-        signing_key: &signing::PrivateKey,
-        mut info: PublishInfo,
+        signing_key: &dyn signing::Signer,
+        info: PublishInfo,
     ) -> ClientResult<RecordId> {
-        if info.entries.is_empty() {
-            return Err(ClientError::NothingToPublish {
-                id: info.id.clone(),
-            });
-        }
+        self.routed(&info.id)
+            .publish_record(signing_key, info, &mut HashSet::new())
+            .await
+    }
 
-        let initializing = info.initializing();
+    /// Stores the content read from the given reader and submits the
+    /// provided publish information.
+    ///
+    /// The content is hashed as it is stored. If it does not match the given
+    /// digest, nothing is stored and [`ClientError::ContentDigestMismatch`]
+    /// is returned before the record is submitted.
+    ///
+    /// Returns the identifier of the record that was published.
+    pub async fn publish_with_content(
+        &self,
+        signing_key: &dyn signing::Signer,
+        info: PublishInfo,
+        digest: &AnyHash,
+        reader: impl AsyncRead + Send + Sync + 'static,
+    ) -> ClientResult<RecordId> {
+        self.store_verified_content(digest, reader).await?;
+        self.publish_with_info(signing_key, info).await
+    }
 
-        tracing::info!(
-            "publishing {new}package `{id}`",
-            id = info.id,
-            new = if initializing { "new " } else { "" }
+    /// Stores the content read from the given reader in content storage,
+    /// failing if it does not match the given digest.
+    async fn store_verified_content(
+        &self,
+        digest: &AnyHash,
+        reader: impl AsyncRead + Send + Sync + 'static,
+    ) -> ClientResult<()> {
+        let expected = digest.clone();
+        let stream = futures_util::stream::try_unfold(
+            (
+                ReaderStream::new(Box::pin(reader)),
+                Some(digest.algorithm().hasher()),
+            ),
+            move |(mut stream, hasher)| {
+                let expected = expected.clone();
+                async move {
+                    let Some(mut hasher) = hasher else {
+                        return Ok(None);
+                    };
+
+                    match stream.try_next().await? {
+                        Some(bytes) => {
+                            hasher.update(&bytes);
+                            Ok(Some((bytes, (stream, Some(hasher)))))
+                        }
+                        None => {
+                            let actual = hasher.finalize();
+                            if actual != expected {
+                                // Fail the stream so the storage discards the content
+                                return Err(anyhow!(ClientError::ContentDigestMismatch {
+                                    expected,
+                                    actual,
+                                }));
+                            }
+
+                            Ok(None)
+                        }
+                    }
+                }
+            },
         );
-        tracing::debug!("entries: {:?}", info.entries);
 
-        let mut package = self
+        self.content
+            .store_content(Box::pin(stream), Some(digest))
+            .await
+            .map_err(|e| match e.downcast::<ClientError>() {
+                Ok(e) => e,
+                Err(e) => ClientError::Other(e),
+            })?;
+
+        Ok(())
+    }
+
+    /// Submits the provided publish information and waits for the record to
+    /// be published, checking every `interval`.
+    ///
+    /// Returns a receipt linking the published record to the checkpoint it
+    /// was included under; the inclusion of the record is verified.
+    pub async fn publish_with_receipt(
+        &self,
+        signing_key: &dyn signing::Signer,
+        info: PublishInfo,
+        interval: Duration,
+    ) -> ClientResult<PublishReceipt> {
+        let id = info.id.clone();
+        let (versions, content) = info
+            .entries
+            .iter()
+            .filter_map(|entry| match entry {
+                PublishEntry::Release { version, content } => {
+                    Some((version.clone(), content.clone()))
+                }
+                _ => None,
+            })
+            .unzip();
+
+        let expected_head = info.expected_head.clone();
+        let record_id = self.publish_with_info(signing_key, info).await?;
+        let client = self.routed(&id);
+        let registry_index = match client
+            .wait_for_registry_index(&id, &record_id, interval)
+            .await
+        {
+            Ok(registry_index) => registry_index,
+            // The package log may have advanced after the expected head was checked
+            Err(e @ ClientError::PublishRejected { .. }) => match &expected_head {
+                Some(expected) => {
+                    let mut package = client
+                        .registry
+                        .load_package(&id)
+                        .await?
+                        .unwrap_or_else(|| PackageInfo::new(id.clone()));
+                    client.check_head(&mut package, expected).await?;
+                    return Err(e);
+                }
+                None => return Err(e),
+            },
+            Err(e) => return Err(e),
+        };
+
+        self.upsert([&id]).await?;
+        let checkpoint = client
             .registry
-            .load_package(&info.id)
+            .load_package(&id)
             .await?
-            .unwrap_or_else(|| PackageInfo::new(info.id.clone()));
+            .and_then(|package| package.checkpoint)
+            .filter(|checkpoint| checkpoint.log_length > registry_index)
+            .ok_or_else(|| {
+                anyhow!("record `{record_id}` is not included in the latest checkpoint")
+            })?;
 
-        // If we're not initializing the package and a head was not explicitly specified,
-        // updated to the latest checkpoint to get the latest known head.
-        if !initializing && info.head.is_none() {
-            self.update_checkpoint(&self.api.latest_checkpoint().await?, [&mut package])
-                .await?;
+        Ok(PublishReceipt {
+            id,
+            record_id,
+            registry_index,
+            versions,
+            content,
+            checkpoint,
+        })
+    }
 
-            info.head = package.state.head().as_ref().map(|h| h.digest.clone());
-        }
+    /// Submits the provided publish information for each record in order.
+    ///
+    /// Content referenced by multiple records is uploaded at most once to
+    /// each registry the records are routed to. Each
+    /// record is waited on until published, checking every `interval`,
+    /// before the next record is submitted.
+    ///
+    /// As published records cannot be rolled back, publishing stops at the
+    /// first record that fails and the returned batch describes which
+    /// records were published and which were not.
+    pub async fn publish_batch(
+        &self,
+        signing_key: &dyn signing::Signer,
+        entries: Vec<PublishInfo>,
+        interval: Duration,
+    ) -> PublishBatch {
+        let mut batch = PublishBatch::default();
+        let mut uploaded = HashMap::<String, HashSet<AnyHash>>::new();
+        let mut entries = entries.into_iter();
+        for info in entries.by_ref() {
+            let id = info.id.clone();
+            let client = self.routed(&id);
+            let res = match client
+                .publish_record(
+                    signing_key,
+                    info.clone(),
+                    uploaded.entry(client.url().to_string()).or_default(),
+                )
+                .await
+            {
+                Ok(record_id) => client
+                    .wait_for_registry_index(&id, &record_id, interval)
+                    .await
+                    .map(|_| record_id),
+                Err(e) => Err(e),
+            };
 
-        match (initializing, info.head.is_some()) {
-            (true, true) => return Err(ClientError::CannotInitializePackage { id: package.id }),
-            (false, false) => return Err(ClientError::MustInitializePackage { id: package.id }),
-            _ => (),
+            match res {
+                Ok(record_id) => batch.published.push((id, record_id)),
+                Err(e) => {
+                    batch.failed = Some((info, e));
+                    break;
+                }
+            }
         }
 
-        let record = info.finalize(signing_key)?;
-        let log_id = LogId::package_log::<Sha256>(&package.id);
+        batch.remaining = entries.collect();
+        batch
+    }
+
+    /// Publishes a record from the given publish information.
+    ///
+    /// Content with a digest in `uploaded` is not uploaded again; digests
+    /// of uploaded content are added to the set.
+    #[tracing::instrument(name = "publish", skip_all, fields(id = %info.id, record_id = field::Empty))]
+    async fn publish_record(
+        &self,
+        signing_key: &dyn signing::Signer,
+        info: PublishInfo,
+        uploaded: &mut HashSet<AnyHash>,
+    ) -> ClientResult<RecordId> {
+        tracing::info!(
+            "publishing {new}package `{id}`",
+            id = info.id,
+            new = if info.initializing() { "new " } else { "" }
+        );
+        tracing::debug!("entries: {:?}", info.entries);
+
+        let (package, record) = self.prepare_publish(signing_key, info).await?;
+        let record_id = self
+            .submit_record_with(&package.id, record, uploaded)
+            .await?;
+        tracing::Span::current().record("record_id", field::display(&record_id));
+        Ok(record_id)
+    }
+
+    /// Submits a signed record to the registry, such as one built with
+    /// [`PackageRecordBuilder`].
+    ///
+    /// The record is submitted as-is: it is not validated against the package
+    /// log in client storage, and its previous record must be the head of the
+    /// package log in the registry. Content released by the record that the
+    /// registry is missing is uploaded from content storage.
+    ///
+    /// Returns the identifier of the record that was submitted.
+    ///
+    /// Use `wait_for_publish` to wait for the record to transition to the `published` state.
+    #[tracing::instrument(name = "submit", skip_all, fields(%id))]
+    pub async fn submit_record(
+        &self,
+        id: &PackageId,
+        record: ProtoEnvelope<package::PackageRecord>,
+    ) -> ClientResult<RecordId> {
+        self.routed(id)
+            .submit_record_with(id, record, &mut HashSet::new())
+            .await
+    }
+
+    /// Submits a signed record to the registry, uploading any content the
+    /// registry is missing.
+    ///
+    /// Content with a digest in `uploaded` is not uploaded again; digests
+    /// of uploaded content are added to the set.
+    async fn submit_record_with(
+        &self,
+        id: &PackageId,
+        record: ProtoEnvelope<package::PackageRecord>,
+        uploaded: &mut HashSet<AnyHash>,
+    ) -> ClientResult<RecordId> {
+        let log_id = LogId::package_log::<Sha256>(id);
+        let record_id = RecordId::package_record::<Sha256>(&record);
         let record = self
-            .api
+            .api()?
             .publish_package_record(
                 &log_id,
                 PublishRecordRequest {
-                    id: Cow::Borrowed(&package.id),
+                    id: Cow::Borrowed(id),
                     record: Cow::Owned(record.into()),
                     content_sources: Default::default(),
+                    // The record identifier is stable across retries of the request
+                    idempotency_key: Some(record_id.to_string()),
                 },
             )
             .await
             .map_err(|e| {
-                ClientError::translate_log_not_found(e, |id| {
-                    if id == &log_id {
-                        Some(package.id.clone())
+                ClientError::translate_log_not_found(e, |other| {
+                    if other == &log_id {
+                        Some(id.clone())
                     } else {
                         None
                     }
@@ -167,133 +542,2456 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
                 continue;
             };
 
-            self.api
-                .upload_content(
-                    url,
-                    Body::wrap_stream(self.content.load_content(digest).await?.ok_or_else(
-                        || ClientError::ContentNotFound {
-                            digest: digest.clone(),
-                        },
-                    )?),
-                )
+            if uploaded.contains(digest) {
+                tracing::debug!("content `{digest}` was already uploaded");
+                continue;
+            }
+
+            if self.skip_existing_content {
+                match self.api()?.content_exists(url).await {
+                    Ok(true) => {
+                        tracing::info!("registry already has content `{digest}`; skipping upload");
+                        continue;
+                    }
+                    Ok(false) => {}
+                    Err(e) => {
+                        tracing::debug!("failed to check for existing content `{digest}`: {e}")
+                    }
+                }
+            }
+
+            self.upload_content(url, digest)
                 .await
                 .map_err(|e| match e {
-                    api::ClientError::Package(PackageError::Rejection(reason)) => {
-                        ClientError::PublishRejected {
-                            id: package.id.clone(),
-                            record_id: record.id.clone(),
-                            reason,
-                        }
-                    }
-                    _ => e.into(),
+                    ClientError::Api(api::ClientError::Package(PackageError::Rejection(
+                        reason,
+                    ))) => ClientError::PublishRejected {
+                        id: id.clone(),
+                        record_id: record.id.clone(),
+                        reason,
+                    },
+                    _ => e,
                 })?;
+
+            uploaded.insert(digest.clone());
         }
 
         Ok(record.id)
     }
 
-    /// Waits for a package record to transition to the `published` state.
+    /// Initializes a package, granting permissions to other keys in the
+    /// same record.
     ///
-    /// The `interval` is the amount of time to wait between checks.
+    /// The signing key becomes the initial key of the package; the granted
+    /// keys may publish to the package as soon as the record is published,
+    /// so that a package can be maintained by a team from the start.
     ///
-    /// Returns an error if the package record was rejected.
-    pub async fn wait_for_publish(
+    /// Returns [`ClientError::CannotInitializePackage`] if the package
+    /// already exists.
+    ///
+    /// Returns the identifier of the record that was published.
+    pub async fn init_package_with_grants(
         &self,
-        package: &PackageId,
-        record_id: &RecordId,
-        interval: Duration,
-    ) -> ClientResult<()> {
-        let log_id = LogId::package_log::<Sha256>(package);
-        let mut current = self.get_package_record(package, &log_id, record_id).await?;
-
-        loop {
-            match current.state {
-                PackageRecordState::Sourcing { .. } => {
-                    return Err(ClientError::PackageMissingContent);
-                }
-                PackageRecordState::Published { .. } => {
-                    return Ok(());
-                }
-                PackageRecordState::Rejected { reason } => {
-                    return Err(ClientError::PublishRejected {
-                        id: package.clone(),
-                        record_id: record_id.clone(),
-                        reason,
-                    });
-                }
-                PackageRecordState::Processing => {
-                    tokio::time::sleep(interval).await;
-                    current = self.get_package_record(package, &log_id, record_id).await?;
-                }
-            }
+        signing_key: &dyn signing::Signer,
+        id: &PackageId,
+        grants: impl IntoIterator<Item = (PublicKey, Vec<package::Permission>)>,
+    ) -> ClientResult<RecordId> {
+        let client = self.routed(id);
+        if client.package_exists(id).await? {
+            return Err(ClientError::CannotInitializePackage { id: id.clone() });
         }
-    }
 
-    /// Updates every package log in client storage to the latest registry checkpoint.
-    pub async fn update(&self) -> ClientResult<()> {
-        tracing::info!("updating all packages to latest checkpoint");
+        let entries = std::iter::once(PublishEntry::Init)
+            .chain(
+                grants
+                    .into_iter()
+                    .map(|(key, permissions)| PublishEntry::Grant { key, permissions }),
+            )
+            .collect();
 
-        let mut updating = self.registry.load_packages().await?;
-        self.update_checkpoint(&self.api.latest_checkpoint().await?, &mut updating)
+        let record_id = self
+            .publish_with_info(
+                signing_key,
+                PublishInfo {
+                    id: id.clone(),
+                    head: None,
+                    expected_head: None,
+                    entries,
+                },
+            )
             .await?;
 
-        Ok(())
+        // The package may have been remembered as missing by the check above
+        client.missing_packages.lock().unwrap().remove(id);
+        Ok(record_id)
     }
 
-    /// Inserts or updates the logs of the specified packages in client storage to
-    /// the latest registry checkpoint.
-    pub async fn upsert<'a, I>(&self, packages: I) -> Result<(), ClientError>
-    where
-        I: IntoIterator<Item = &'a PackageId>,
-        I::IntoIter: ExactSizeIterator,
-    {
-        tracing::info!("updating specific packages to latest checkpoint");
+    /// Rotates the signing key of a package, authorizing a new key with the
+    /// permissions of the old key.
+    ///
+    /// The package log is updated to ensure the old key currently holds
+    /// permissions in the package; the published record, signed by the old
+    /// key, grants those permissions to the new key and revokes them from the
+    /// old key. Subsequent records must be signed by the new key.
+    ///
+    /// Returns [`ClientError::PackageValidationFailed`] if the old key holds
+    /// no permissions in the package.
+    ///
+    /// Returns the identifier of the record that was published.
+    pub async fn rotate_signing_key(
+        &self,
+        id: &PackageId,
+        old: &dyn signing::Signer,
+        new: &dyn signing::Signer,
+    ) -> ClientResult<RecordId> {
+        let client = self.routed(id);
+        let mut package = client
+            .registry
+            .load_package(id)
+            .await?
+            .unwrap_or_else(|| PackageInfo::new(id.clone()));
+        client
+            .update_checkpoint(&client.api()?.latest_checkpoint().await?, [&mut package])
+            .await?;
 
-        let packages = packages.into_iter();
-        let mut updating = Vec::with_capacity(packages.len());
-        for package in packages {
-            updating.push(
-                self.registry
-                    .load_package(package)
-                    .await?
-                    .unwrap_or_else(|| PackageInfo::new(package.clone())),
-            );
-        }
+        let head = package
+            .state
+            .head()
+            .as_ref()
+            .map(|h| h.digest.clone())
+            .ok_or_else(|| ClientError::PackageDoesNotExist { id: id.clone() })?;
 
-        self.update_checkpoint(&self.api.latest_checkpoint().await?, &mut updating)
-            .await?;
+        let old_key_id = old.public_key().fingerprint();
+        let permissions: Vec<_> = package.state.key_permissions(&old_key_id).collect();
+        if permissions.is_empty() {
+            let inner = match package.state.public_key(&old_key_id) {
+                Some(_) => package::ValidationError::UnauthorizedAction {
+                    key_id: old_key_id,
+                    needed_permission: package::Permission::Release,
+                },
+                None => package::ValidationError::KeyIDNotRecognized { key_id: old_key_id },
+            };
+            return Err(ClientError::PackageValidationFailed {
+                id: id.clone(),
+                inner,
+            });
+        }
 
-        Ok(())
+        client
+            .publish_with_info(
+                old,
+                PublishInfo {
+                    id: id.clone(),
+                    head: Some(head),
+                    expected_head: None,
+                    entries: vec![
+                        PublishEntry::Grant {
+                            key: new.public_key(),
+                            permissions: permissions.clone(),
+                        },
+                        PublishEntry::Revoke {
+                            key_id: old_key_id,
+                            permissions,
+                        },
+                    ],
+                },
+            )
+            .await
     }
 
-    /// Downloads the latest version of a package into client storage that
-    /// satisfies the given version requirement.
+    /// Yanks a released version of a package.
     ///
-    /// If the requested package log is not present in client storage, it
-    /// will be fetched from the registry first.
+    /// A yanked version remains in the package log but is no longer
+    /// considered when resolving version requirements.
     ///
-    /// An error is returned if the package does not exist.
+    /// Returns the identifier of the record that was published.
+    pub async fn yank_version(
+        &self,
+        signing_key: &dyn signing::Signer,
+        id: &PackageId,
+        version: &Version,
+    ) -> ClientResult<RecordId> {
+        self.publish_with_info(
+            signing_key,
+            PublishInfo {
+                id: id.clone(),
+                head: None,
+                expected_head: None,
+                entries: vec![PublishEntry::Yank {
+                    version: version.clone(),
+                }],
+            },
+        )
+        .await
+    }
+
+    /// Deprecates a released version of a package.
     ///
-    /// If a version satisfying the requirement does not exist, `None` is
-    /// returned.
+    /// Unlike a yanked version, a deprecated version is still considered
+    /// when resolving version requirements; the deprecation message and the
+    /// version that supersedes it, if any, are recorded in the package log
+    /// so that consumers may warn about the deprecation.
     ///
-    /// Returns the path within client storage of the package contents for
-    /// the resolved version.
-    pub async fn download(
+    /// Returns the identifier of the record that was published.
+    pub async fn deprecate_version(
         &self,
+        signing_key: &dyn signing::Signer,
         id: &PackageId,
-        requirement: &VersionReq,
-    ) -> Result<Option<PackageDownload>, ClientError> {
-        tracing::info!("downloading package `{id}` with requirement `{requirement}`");
-        let info = self.fetch_package(id).await?;
-        let log_id = LogId::package_log::<Sha256>(&info.id);
-
-        match info.state.find_latest_release(requirement) {
-            Some(release) => {
-                let digest = release
-                    .content()
-                    .context("invalid state: not yanked but missing content")?
-                    .clone();
+        version: &Version,
+        message: impl Into<String>,
+        superseded_by: Option<Version>,
+    ) -> ClientResult<RecordId> {
+        self.publish_with_info(
+            signing_key,
+            PublishInfo {
+                id: id.clone(),
+                head: None,
+                expected_head: None,
+                entries: vec![PublishEntry::Deprecate {
+                    version: version.clone(),
+                    message: message.into(),
+                    superseded_by,
+                }],
+            },
+        )
+        .await
+    }
+
+    /// Unyanks a previously yanked version of a package.
+    ///
+    /// Returns the identifier of the record that was published.
+    pub async fn unyank_version(
+        &self,
+        signing_key: &dyn signing::Signer,
+        id: &PackageId,
+        version: &Version,
+    ) -> ClientResult<RecordId> {
+        self.publish_with_info(
+            signing_key,
+            PublishInfo {
+                id: id.clone(),
+                head: None,
+                expected_head: None,
+                entries: vec![PublishEntry::Unyank {
+                    version: version.clone(),
+                }],
+            },
+        )
+        .await
+    }
+
+    /// Validates the provided publish information without publishing it.
+    ///
+    /// This performs the same checks as `publish_with_info` prior to
+    /// submitting the record to the registry, and additionally ensures that
+    /// all released content is present in content storage and that the
+    /// signed record is valid for the current package log.
+    ///
+    /// The package log may be updated from the registry to determine the
+    /// current head of the log, but no record is published.
+    pub async fn validate_publish(
+        &self,
+        signing_key: &dyn signing::Signer,
+        info: &PublishInfo,
+    ) -> ClientResult<()> {
+        tracing::info!("validating publish of package `{id}`", id = info.id);
+
+        for entry in &info.entries {
+            if let PublishEntry::Release { content, .. } = entry {
+                if !self.content.contains_content(content).await? {
+                    return Err(ClientError::ContentNotFound {
+                        digest: content.clone(),
+                    });
+                }
+            }
+        }
+
+        let (mut package, record) = self
+            .routed(&info.id)
+            .prepare_publish(signing_key, info.clone())
+            .await?;
+        package
+            .state
+            .validate(&record)
+            .map_err(|inner| ClientError::PackageValidationFailed {
+                id: package.id.clone(),
+                inner,
+            })
+    }
+
+    /// Prepares a record to be published from the given publish information.
+    ///
+    /// Returns the current package information and the signed record.
+    async fn prepare_publish(
+        &self,
+        signing_key: &dyn signing::Signer,
+        mut info: PublishInfo,
+    ) -> ClientResult<(PackageInfo, ProtoEnvelope<package::PackageRecord>)> {
+        if info.entries.is_empty() {
+            return Err(ClientError::NothingToPublish {
+                id: info.id.clone(),
+            });
+        }
+
+        let initializing = info.initializing();
+
+        let mut package = self
+            .registry
+            .load_package(&info.id)
+            .await?
+            .unwrap_or_else(|| PackageInfo::new(info.id.clone()));
+
+        // If a head is expected, the package log must not have advanced past it;
+        // otherwise, if we're not initializing the package and a head was not
+        // explicitly specified, updated to the latest checkpoint to get the latest known head.
+        if let Some(expected) = &info.expected_head {
+            self.check_head(&mut package, expected).await?;
+            info.head = Some(expected.clone());
+        } else if !initializing && info.head.is_none() {
+            self.update_checkpoint(&self.api()?.latest_checkpoint().await?, [&mut package])
+                .await?;
+
+            info.head = package.state.head().as_ref().map(|h| h.digest.clone());
+        }
+
+        match (initializing, info.head.is_some()) {
+            (true, true) => return Err(ClientError::CannotInitializePackage { id: package.id }),
+            (false, false) => return Err(ClientError::MustInitializePackage { id: package.id }),
+            _ => (),
+        }
+
+        let record = tracing::debug_span!("sign_record", id = %package.id)
+            .in_scope(|| info.finalize(signing_key))?;
+        Ok((package, record))
+    }
+
+    /// Updates the given package log to the latest checkpoint, returning
+    /// [`ClientError::PublishConflict`] if its head is not the expected head.
+    async fn check_head(&self, package: &mut PackageInfo, expected: &RecordId) -> ClientResult<()> {
+        self.update_checkpoint(&self.api()?.latest_checkpoint().await?, [&mut *package])
+            .await?;
+
+        let actual = package.state.head().as_ref().map(|h| h.digest.clone());
+        if actual.as_ref() != Some(expected) {
+            return Err(ClientError::PublishConflict {
+                id: package.id.clone(),
+                expected: expected.clone(),
+                actual,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Uploads content to the given upload endpoint URL.
+    ///
+    /// If a previous upload of the content was interrupted, the upload is
+    /// resumed from the number of bytes the registry has already received.
+    #[tracing::instrument(level = "debug", skip_all, fields(%digest, bytes = field::Empty))]
+    async fn upload_content(&self, url: &str, digest: &AnyHash) -> ClientResult<()> {
+        let mut offset = 0;
+        if self.content.load_upload(digest).await?.is_some() {
+            let status = self.api()?.upload_status(url).await?;
+            if status.received > 0 {
+                // Ensure the registry received the same bytes as what is stored locally
+                let prefix = self.hash_content_prefix(digest, status.received).await?;
+                if status.digest.as_ref() != Some(&prefix) {
+                    self.content.store_upload(digest, None).await?;
+                    return Err(ClientError::ContentNotFound {
+                        digest: digest.clone(),
+                    });
+                }
+
+                tracing::info!(
+                    "resuming upload of content `{digest}` at offset {offset}",
+                    offset = status.received
+                );
+                offset = status.received;
+            }
+        }
+
+        let mut info = UploadInfo {
+            digest: digest.clone(),
+            offset,
+        };
+        self.content.store_upload(digest, Some(&info)).await?;
+
+        let total = self
+            .content
+            .content_location(digest)
+            .and_then(|path| std::fs::metadata(path).ok())
+            .map(|metadata| metadata.len());
+        let sent = Arc::new(AtomicU64::new(offset));
+        let stream = self
+            .content
+            .resume_upload(digest, offset)
+            .await?
+            .ok_or_else(|| ClientError::ContentNotFound {
+                digest: digest.clone(),
+            })?
+            .inspect_ok({
+                let sent = sent.clone();
+                let progress = self.progress.clone();
+                move |bytes| {
+                    let len = bytes.len() as u64;
+                    progress.on_progress(sent.fetch_add(len, Ordering::Relaxed) + len, total);
+                }
+            });
+
+        let api = self.api()?;
+        let transfer = api.watch_transfer(&sent, api.resume_upload_stream(url, offset, stream));
+        let result = tokio::select! {
+            biased;
+            _ = self.cancel.cancelled() => Err(ClientError::Cancelled),
+            result = transfer => result.map_err(ClientError::from),
+        };
+
+        self.metrics
+            .record_upload(sent.load(Ordering::Relaxed) - offset);
+        match result {
+            Ok(_) => {
+                self.content.store_upload(digest, None).await?;
+                tracing::Span::current().record("bytes", sent.load(Ordering::Relaxed) - offset);
+                Ok(())
+            }
+            Err(e) => {
+                // Record how much was sent so a later publish may resume the upload
+                info.offset = sent.load(Ordering::Relaxed);
+                self.content.store_upload(digest, Some(&info)).await?;
+                Err(e)
+            }
+        }
+    }
+
+    async fn hash_content_prefix(&self, digest: &AnyHash, len: u64) -> ClientResult<AnyHash> {
+        let mut stream = self.content.load_content(digest).await?.ok_or_else(|| {
+            ClientError::ContentNotFound {
+                digest: digest.clone(),
+            }
+        })?;
+
+        let mut hasher = digest.algorithm().hasher();
+        let mut remaining = len;
+        while remaining > 0 {
+            let Some(bytes) = stream.try_next().await? else {
+                break;
+            };
+
+            let n = bytes.len().min(remaining as usize);
+            hasher.update(&bytes[..n]);
+            remaining -= n as u64;
+        }
+
+        Ok(hasher.finalize())
+    }
+
+    /// Returns [`ClientError::Cancelled`] if the client's cancellation token
+    /// was cancelled.
+    fn check_cancelled(&self) -> ClientResult<()> {
+        if self.cancel.is_cancelled() {
+            return Err(ClientError::Cancelled);
+        }
+
+        Ok(())
+    }
+
+    /// Waits for a package record to transition to the `published` state.
+    ///
+    /// The `interval` is the amount of time to wait between checks.
+    ///
+    /// Returns an error if the package record was rejected.
+    pub async fn wait_for_publish(
+        &self,
+        package: &PackageId,
+        record_id: &RecordId,
+        interval: Duration,
+    ) -> ClientResult<()> {
+        self.routed(package)
+            .wait_for_registry_index(package, record_id, interval)
+            .await?;
+        Ok(())
+    }
+
+    /// Waits for a package record to transition to the `published` state,
+    /// returning the index of the record in the registry log.
+    ///
+    /// Content the registry is still missing is uploaded again, up to the
+    /// client's maximum number of content retries.
+    async fn wait_for_registry_index(
+        &self,
+        package: &PackageId,
+        record_id: &RecordId,
+        interval: Duration,
+    ) -> ClientResult<RegistryIndex> {
+        let log_id = LogId::package_log::<Sha256>(package);
+        let mut current = self.get_package_record(package, &log_id, record_id).await?;
+        let mut retries = 0;
+
+        loop {
+            match current.state {
+                PackageRecordState::Sourcing { missing_content } => {
+                    let mut digests = missing_content.keys().cloned().collect::<Vec<_>>();
+                    digests.sort();
+                    let missing = || ClientError::PackageMissingContent {
+                        id: package.clone(),
+                        record_id: record_id.clone(),
+                        digests: digests.clone(),
+                    };
+
+                    if retries >= self.max_content_retries {
+                        return Err(missing());
+                    }
+
+                    retries += 1;
+                    tracing::warn!(
+                        "registry is still missing {count} content blob(s) for record `{record_id}`; \
+                         uploading again (retry {retries} of {max})",
+                        count = digests.len(),
+                        max = self.max_content_retries
+                    );
+                    for (digest, MissingContent { upload }) in &missing_content {
+                        // Retrying is futile if the registry cannot accept the content
+                        let Some(UploadEndpoint::HttpPost { url }) = upload.first() else {
+                            return Err(missing());
+                        };
+
+                        self.upload_content(url, digest).await?;
+                    }
+
+                    current = self.get_package_record(package, &log_id, record_id).await?;
+                }
+                PackageRecordState::Published { registry_index, .. } => {
+                    return Ok(registry_index);
+                }
+                PackageRecordState::Rejected { reason } => {
+                    return Err(ClientError::PublishRejected {
+                        id: package.clone(),
+                        record_id: record_id.clone(),
+                        reason,
+                    });
+                }
+                PackageRecordState::Processing => {
+                    tokio::time::sleep(interval).await;
+                    current = self.get_package_record(package, &log_id, record_id).await?;
+                }
+            }
+        }
+    }
+
+    /// Updates every package log in client storage to the latest registry checkpoint.
+    ///
+    /// Package logs of routed registries are updated to the latest checkpoint
+    /// of their registry.
+    ///
+    /// In offline mode, the package logs in client storage are left as-is.
+    pub async fn update(&self) -> ClientResult<()> {
+        if self.offline {
+            tracing::info!("client is offline; package logs will not be updated");
+            return Ok(());
+        }
+
+        tracing::info!("updating all packages to latest checkpoint");
+
+        for client in self.clients() {
+            let mut updating = client.registry.load_packages().await?;
+            client
+                .update_checkpoint(&client.api()?.latest_checkpoint().await?, &mut updating)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Pins the public key of the registry operator.
+    ///
+    /// The key is pinned for the client's registry and not for any routed
+    /// registry.
+    ///
+    /// Checkpoints from the registry are only accepted if they are signed by
+    /// the pinned key. The key is pinned on first use of the registry, so
+    /// this is required to accept checkpoints after the registry changes its
+    /// key. If the key is `None`, the pinned key is removed and the key that
+    /// signs the next checkpoint is trusted on first use.
+    pub async fn trust_registry_key(&self, key: Option<&PublicKey>) -> ClientResult<()> {
+        match key {
+            Some(key) => tracing::info!(
+                "pinning registry key `{key_id}`",
+                key_id = key.fingerprint()
+            ),
+            None => tracing::info!("removing the pinned registry key"),
+        }
+
+        self.registry.store_registry_key(key).await?;
+        Ok(())
+    }
+
+    /// Inserts or updates the logs of the specified packages in client storage to
+    /// the latest registry checkpoint.
+    ///
+    /// Only package logs are fetched; content is never downloaded.
+    ///
+    /// In offline mode, an error is returned if any of the package logs is not
+    /// present in client storage; existing package logs are left as-is.
+    pub async fn upsert<'a, I>(&self, packages: I) -> Result<(), ClientError>
+    where
+        I: IntoIterator<Item = &'a PackageId>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        tracing::info!("updating specific packages to latest checkpoint");
+
+        self.upsert_packages(packages).await?;
+        Ok(())
+    }
+
+    /// Synchronizes the logs of the specified packages in client storage with
+    /// the latest registry checkpoint.
+    ///
+    /// New records since the last checkpoint are fetched and verified; a
+    /// package that is already at the latest checkpoint only costs a probe
+    /// of the checkpoint. Only package logs are fetched; content is never
+    /// downloaded.
+    ///
+    /// Returns the number of new records each package gained. In offline mode,
+    /// an error is returned if any of the package logs is not present in
+    /// client storage.
+    pub async fn sync(&self, ids: &[PackageId]) -> ClientResult<SyncReport> {
+        tracing::info!("synchronizing {count} package(s)", count = ids.len());
+
+        let mut new_records = self.upsert_packages(ids).await?;
+        Ok(SyncReport {
+            packages: ids
+                .iter()
+                .map(|id| (id.clone(), new_records.remove(id).unwrap_or_default()))
+                .collect(),
+        })
+    }
+
+    /// Synchronizes the logs of the specified packages in client storage with
+    /// the latest registry checkpoint as a single transaction.
+    ///
+    /// Unlike [`Client::sync`], the logs of every package are fetched and
+    /// verified, across every routed registry, before any is stored; if any
+    /// package fails, no package log in client storage is changed. Should
+    /// storing the verified logs fail, the package logs already stored are
+    /// restored. Verified checkpoints and operator logs are kept.
+    pub async fn sync_transactional(&self, ids: &[PackageId]) -> ClientResult<SyncReport> {
+        tracing::info!(
+            "synchronizing {count} package(s) transactionally",
+            count = ids.len()
+        );
+
+        let mut staged = Vec::new();
+        for (client, packages) in self.group_by_route(ids, |id| id) {
+            if let Some(update) = client.stage_routed(packages).await? {
+                staged.push((client, update));
+            }
+        }
+
+        let mut previous = Vec::new();
+        for (client, update) in &staged {
+            for package in &update.packages {
+                previous.push((
+                    *client,
+                    package.id.clone(),
+                    client.registry.load_package(&package.id).await?,
+                ));
+            }
+        }
+
+        let mut new_records = HashMap::new();
+        for (client, update) in staged {
+            if let Err(e) = client.commit_staged(&update).await {
+                tracing::warn!("failed to store synchronized package logs; rolling back: {e}");
+                for (client, id, info) in &previous {
+                    match info {
+                        Some(info) => client.registry.store_package(info).await?,
+                        None => client.registry.remove_package(id).await?,
+                    }
+                }
+
+                return Err(e);
+            }
+
+            new_records.extend(update.new_records);
+        }
+
+        Ok(SyncReport {
+            packages: ids
+                .iter()
+                .map(|id| (id.clone(), new_records.remove(id).unwrap_or_default()))
+                .collect(),
+        })
+    }
+
+    /// Prefetches the specified packages into client storage.
+    ///
+    /// The logs of the packages are synchronized with the latest registry
+    /// checkpoint and, if `include_content` is set, the content of the latest
+    /// version of each package that has not been yanked is downloaded.
+    /// Content is downloaded concurrently, up to the client's maximum number
+    /// of concurrent downloads.
+    ///
+    /// A failure for one package does not prevent the others from being
+    /// prefetched; the outcome of each package is reported separately.
+    pub async fn prefetch(&self, ids: &[PackageId], include_content: bool) -> PrefetchReport {
+        let mut seen = HashSet::new();
+        let ids = ids.iter().filter(|id| seen.insert(*id)).collect::<Vec<_>>();
+        tracing::info!("prefetching {count} package(s)", count = ids.len());
+
+        // The logs are synchronized together; should that fail, each package
+        // is synchronized on its own so that the failure is attributed to it
+        let mut synced = match self.upsert_packages(ids.iter().copied()).await {
+            Ok(_) => HashMap::new(),
+            Err(e) => {
+                tracing::debug!("failed to synchronize the packages together: {e}");
+                futures_util::stream::iter(&ids)
+                    .map(|id| async move { (*id, self.upsert_packages([*id]).await) })
+                    .buffer_unordered(self.max_concurrent_downloads)
+                    .collect::<HashMap<_, _>>()
+                    .await
+            }
+        };
+
+        let packages = futures_util::stream::iter(ids.into_iter().map(|id| {
+            let synced = synced.remove(id).unwrap_or_else(|| Ok(HashMap::new()));
+            async move {
+                let result = match synced {
+                    Err(e) => Err(e),
+                    Ok(_) if include_content => self.download(id, &VersionReq::STAR).await,
+                    Ok(_) => Ok(None),
+                };
+                (id.clone(), result)
+            }
+        }))
+        .buffered(self.max_concurrent_downloads)
+        .collect()
+        .await;
+
+        PrefetchReport { packages }
+    }
+
+    /// Upserts the given packages, returning the number of new records each
+    /// updated package gained.
+    async fn upsert_packages<'a, I>(&self, packages: I) -> ClientResult<HashMap<PackageId, usize>>
+    where
+        I: IntoIterator<Item = &'a PackageId>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let mut new_records = HashMap::new();
+        for (client, packages) in self.group_by_route(packages, |id| *id) {
+            new_records.extend(client.upsert_routed(packages).await?);
+        }
+
+        Ok(new_records)
+    }
+
+    /// Upserts the given packages, all of which are routed to this client's
+    /// registry.
+    async fn upsert_routed(
+        &self,
+        packages: Vec<&PackageId>,
+    ) -> ClientResult<HashMap<PackageId, usize>> {
+        match self.stage_routed(packages).await? {
+            Some(staged) => {
+                self.commit_staged(&staged).await?;
+                Ok(staged.new_records)
+            }
+            None => Ok(HashMap::new()),
+        }
+    }
+
+    /// Fetches and verifies the logs of the given packages, all of which are
+    /// routed to this client's registry, without storing them.
+    ///
+    /// Returns `None` in offline mode, as nothing is fetched.
+    async fn stage_routed(&self, packages: Vec<&PackageId>) -> ClientResult<Option<StagedUpdate>> {
+        let mut updating = Vec::with_capacity(packages.len());
+        for package in packages {
+            match self.registry.load_package(package).await? {
+                Some(info) => updating.push(info),
+                None if self.offline => {
+                    return Err(ClientError::OfflineDataMissing {
+                        id: package.to_string(),
+                    })
+                }
+                None => updating.push(PackageInfo::new(package.clone())),
+            }
+        }
+
+        if self.offline {
+            return Ok(None);
+        }
+
+        self.stage_checkpoint(&self.api()?.latest_checkpoint().await?, &mut updating)
+            .await
+            .map(Some)
+    }
+
+    /// Gets the metadata of a package without transferring any content.
+    ///
+    /// The package log is updated to the latest registry checkpoint first, as
+    /// with [`Client::upsert`]. The returned information holds every release
+    /// of the package with its version, content digest, and yank and
+    /// deprecation state; content is only transferred by an explicit
+    /// download, such as with [`Client::download`].
+    ///
+    /// In offline mode, the package log in client storage is returned as-is.
+    pub async fn package_metadata(&self, id: &PackageId) -> ClientResult<PackageInfo> {
+        self.upsert([id]).await?;
+        self.routed(id)
+            .registry
+            .load_package(id)
+            .await?
+            .ok_or_else(|| ClientError::PackageDoesNotExist { id: id.clone() })
+    }
+
+    /// Determines if the package log of a package in client storage is up to
+    /// date with the latest registry checkpoint, without updating it.
+    ///
+    /// The latest checkpoint is requested from the registry, followed by the
+    /// package's records after its head in client storage; the checkpoint's
+    /// signature is verified against the pinned registry key, if any, but it
+    /// is neither checked for consistency nor stored, and the records are
+    /// neither validated nor stored.
+    ///
+    /// Returns [`ClientError::PackageDoesNotExist`] if the package log is not
+    /// in client storage.
+    pub async fn sync_status(&self, id: &PackageId) -> ClientResult<SyncStatus> {
+        let client = self.routed(id);
+        let info = client
+            .registry
+            .load_package(id)
+            .await?
+            .ok_or_else(|| ClientError::PackageDoesNotExist { id: id.clone() })?;
+        let Some(head) = info.state.head() else {
+            return Err(ClientError::PackageDoesNotExist { id: id.clone() });
+        };
+
+        let ts_checkpoint = client.api()?.latest_checkpoint().await?;
+        if let Some(key) = client.registry.load_registry_key().await? {
+            proof::verify_checkpoint_signature(&ts_checkpoint, &key)?;
+        }
+
+        // The stored head must be included in the latest checkpoint
+        let log_length = ts_checkpoint.as_ref().checkpoint.log_length;
+        if info
+            .head_registry_index
+            .map_or(false, |index| index >= log_length)
+        {
+            return Ok(SyncStatus::LocalAhead);
+        }
+
+        let records = client.package_log_records(id, log_length, Some(head.digest.clone()));
+        futures_util::pin_mut!(records);
+
+        let mut records_behind = 0;
+        while records.try_next().await?.is_some() {
+            records_behind += 1;
+        }
+
+        Ok(match records_behind {
+            0 => SyncStatus::UpToDate,
+            records_behind => SyncStatus::Behind { records_behind },
+        })
+    }
+
+    /// Gets a handle to the package log of the given package in client
+    /// storage without loading its releases.
+    ///
+    /// This suits packages with many releases, whose releases can then be
+    /// read a page at a time with [`Client::package_releases`]. Nothing is
+    /// fetched from the registry.
+    pub async fn package_handle(&self, id: &PackageId) -> ClientResult<PackageHandle> {
+        self.routed(id)
+            .registry
+            .load_package_handle(id)
+            .await?
+            .ok_or_else(|| ClientError::PackageDoesNotExist { id: id.clone() })
+    }
+
+    /// Gets the releases of the package log of the given handle, in log
+    /// order, reading them from client storage in pages of the given size.
+    ///
+    /// See [`PackageHandle::releases`].
+    pub fn package_releases<'a>(
+        &'a self,
+        handle: &'a PackageHandle,
+        page_size: usize,
+    ) -> impl Stream<Item = ClientResult<package::Release>> + Send + 'a {
+        handle
+            .releases(&self.routed(&handle.id).registry, page_size)
+            .map_err(ClientError::from)
+    }
+
+    /// Deletes content from content storage that is not referenced by any
+    /// package log in registry storage.
+    ///
+    /// Content referenced by a package log of a routed registry or by a
+    /// pending publish is retained.
+    ///
+    /// Returns statistics about the content that was reclaimed.
+    pub async fn gc(&self) -> ClientResult<GcStats> {
+        tracing::info!("collecting unreachable content");
+
+        let mut reachable = HashSet::new();
+        for client in self.clients() {
+            for package in client.registry.load_packages().await? {
+                reachable.extend(
+                    package
+                        .state
+                        .releases()
+                        .filter_map(|r| r.content().cloned()),
+                );
+            }
+        }
+
+        if let Some(publish) = self.registry.load_publish().await? {
+            reachable.extend(publish.entries.into_iter().filter_map(|e| match e {
+                PublishEntry::Release { content, .. } => Some(content),
+                _ => None,
+            }));
+        }
+
+        let stats = self.content.gc(&reachable).await?;
+
+        tracing::info!(
+            "deleted {blobs} content blob(s) totaling {bytes} byte(s)",
+            blobs = stats.blobs,
+            bytes = stats.bytes
+        );
+
+        Ok(stats)
+    }
+
+    /// Gets the disk usage of client storage.
+    ///
+    /// The usage of the registry storage of every routed registry is
+    /// included. Only file metadata is read to compute the usage; no stored
+    /// logs or content are loaded.
+    pub async fn storage_usage(&self) -> ClientResult<StorageUsage> {
+        let cache = self.content.cache_stats().await?;
+        let mut usage = StorageUsage {
+            blobs: cache.entries,
+            content_bytes: cache.bytes,
+            partial_bytes: self.content.partial_bytes().await?,
+            ..Default::default()
+        };
+
+        for client in self.clients() {
+            let stats = client.registry.registry_stats().await?;
+            usage.packages += stats.packages;
+            usage.registry_bytes += stats.bytes;
+        }
+
+        Ok(usage)
+    }
+
+    /// Gets the metrics of the client in the Prometheus text exposition
+    /// format.
+    ///
+    /// The metrics count the requests sent, the content bytes transferred
+    /// and the content downloads served from storage since the client was
+    /// built, including those of routed registries, and report the current
+    /// usage of client storage as computed by [`Client::storage_usage`].
+    pub async fn metrics_text(&self) -> ClientResult<String> {
+        let usage = self.storage_usage().await?;
+        Ok(self.metrics.render(&usage))
+    }
+
+    /// Verifies the integrity of client storage.
+    ///
+    /// All stored content is re-hashed and compared against its digest, and
+    /// every stored log is checked for consistency with the stored registry
+    /// checkpoint, including the package logs of routed registries.
+    ///
+    /// Problems are reported rather than repaired; content that failed
+    /// verification may be repaired with [`Client::repair`].
+    pub async fn verify_storage(&self) -> ClientResult<StorageReport> {
+        tracing::info!("verifying client storage");
+
+        let registry_log_length = self
+            .registry
+            .load_checkpoint()
+            .await?
+            .map(|ts| ts.as_ref().checkpoint.log_length);
+
+        let mut report = StorageReport {
+            content: self.content.verify_all(self.verify_concurrency).await?,
+            ..Default::default()
+        };
+
+        if let Some(operator) = self.registry.load_operator().await? {
+            report.operator =
+                Self::verify_log(operator.head_registry_index, None, registry_log_length);
+        }
+
+        for (index, client) in self.clients().enumerate() {
+            let registry_log_length = if index == 0 {
+                registry_log_length
+            } else {
+                client
+                    .registry
+                    .load_checkpoint()
+                    .await?
+                    .map(|ts| ts.as_ref().checkpoint.log_length)
+            };
+
+            for package in client.registry.load_packages().await? {
+                if let Some(e) = Self::verify_log(
+                    package.head_registry_index,
+                    package.checkpoint.as_ref(),
+                    registry_log_length,
+                ) {
+                    report.packages.push((package.id, e));
+                }
+            }
+        }
+
+        for (digest, e) in &report.content {
+            tracing::warn!("content `{digest}` failed verification: {e}");
+        }
+
+        Ok(report)
+    }
+
+    /// Repairs the content released by the package logs in client storage.
+    ///
+    /// All stored content is verified as with [`Client::verify_storage`].
+    /// Released content that failed verification is deleted and downloaded
+    /// again, as is released content missing from content storage, including
+    /// the content of releases of routed registries. Downloaded content is
+    /// verified against its digest; content that is intact is not
+    /// downloaded.
+    ///
+    /// A failure to download content is reported rather than returned so
+    /// that the remaining content is still repaired.
+    pub async fn repair(&self) -> ClientResult<RepairReport> {
+        tracing::info!("repairing client storage");
+
+        // Content may have been deleted by means other than the storage
+        self.content.refresh_index().await?;
+        let corrupt = self
+            .content
+            .verify_all(self.verify_concurrency)
+            .await?
+            .into_iter()
+            .map(|(digest, _)| digest)
+            .collect::<HashSet<_>>();
+
+        let mut report = RepairReport::default();
+        let mut seen = HashSet::new();
+        for client in self.clients() {
+            for package in client.registry.load_packages().await? {
+                let log_id = LogId::package_log::<Sha256>(&package.id);
+                for release in package.state.releases() {
+                    let Some(digest) = release.content() else {
+                        continue;
+                    };
+
+                    if !seen.insert(digest.clone()) {
+                        continue;
+                    }
+
+                    if corrupt.contains(digest) {
+                        tracing::info!("deleting corrupt content `{digest}`");
+                        self.content.remove_content(digest).await?;
+                    } else if self.content.contains_content(digest).await? {
+                        continue;
+                    }
+
+                    match client
+                        .download_content(&log_id, &release.record_id, digest)
+                        .await
+                    {
+                        Ok(_) => report.repaired.push(digest.clone()),
+                        Err(e) => {
+                            tracing::warn!("failed to repair content `{digest}`: {e}");
+                            report.failed.push((digest.clone(), e));
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Lists the packages in client storage with their known versions.
+    ///
+    /// Only client storage is read; nothing is fetched from the registry, so
+    /// this works in offline mode. Packages of routed registries are
+    /// included, and packages are listed in order of their ids.
+    pub async fn local_inventory(&self) -> ClientResult<Vec<LocalPackage>> {
+        let mut inventory = Vec::new();
+        for client in self.clients() {
+            let checkpoint = client
+                .registry
+                .load_checkpoint()
+                .await?
+                .map(|ts| ts.as_ref().checkpoint.clone());
+
+            for package in client.registry.load_packages().await? {
+                let mut content = HashSet::new();
+                for digest in package.state.releases().filter_map(|r| r.content()) {
+                    if self.content.contains_content(digest).await? {
+                        content.insert(digest);
+                    }
+                }
+
+                inventory.push(LocalPackage {
+                    versions: package
+                        .state
+                        .releases()
+                        .map(|r| r.version.clone())
+                        .collect(),
+                    content: content.len(),
+                    up_to_date: checkpoint.is_some() && package.checkpoint == checkpoint,
+                    id: package.id,
+                });
+            }
+        }
+
+        inventory.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(inventory)
+    }
+
+    /// Reports the content referenced by more than one package in client
+    /// storage.
+    ///
+    /// Only client storage is consulted, so this works in offline mode.
+    /// Packages of routed registries are included.
+    ///
+    /// Shared content is stored once; the bytes saved count each additional
+    /// reference to shared content that has a location in content storage.
+    pub async fn content_sharing_report(&self) -> ClientResult<ContentSharingReport> {
+        let mut references = BTreeMap::<AnyHash, BTreeSet<PackageId>>::new();
+        for client in self.clients() {
+            for package in client.registry.load_packages().await? {
+                for digest in package.state.releases().filter_map(|r| r.content()) {
+                    references
+                        .entry(digest.clone())
+                        .or_default()
+                        .insert(package.id.clone());
+                }
+            }
+        }
+
+        let mut report = ContentSharingReport::default();
+        for (digest, ids) in references {
+            if ids.len() < 2 {
+                continue;
+            }
+
+            let size = self
+                .content
+                .content_location(&digest)
+                .and_then(|path| std::fs::metadata(path).ok())
+                .map_or(0, |metadata| metadata.len());
+            report.bytes_saved += size * (ids.len() as u64 - 1);
+            report.shared.push((digest, ids.into_iter().collect()));
+        }
+
+        Ok(report)
+    }
+
+    fn verify_log(
+        head_registry_index: Option<RegistryIndex>,
+        checkpoint: Option<&Checkpoint>,
+        registry_log_length: Option<RegistryLen>,
+    ) -> Option<LogVerifyError> {
+        let index = head_registry_index?;
+        let Some(registry_log_length) = registry_log_length else {
+            return Some(LogVerifyError::MissingCheckpoint);
+        };
+
+        let log_length = checkpoint
+            .map(|c| c.log_length)
+            .unwrap_or(registry_log_length);
+        if log_length > registry_log_length {
+            return Some(LogVerifyError::CheckpointAhead {
+                log_length,
+                registry_log_length,
+            });
+        }
+
+        if index >= log_length {
+            return Some(LogVerifyError::HeadBeyondCheckpoint { index, log_length });
+        }
+
+        None
+    }
+
+    /// Exports a package log to a portable bundle file.
+    ///
+    /// The package is first updated to the latest registry checkpoint; the
+    /// bundle contains every record of the operator and package logs, the
+    /// checkpoint with the proof that the log heads are included in it, and
+    /// the content of every release that has not been yanked.
+    ///
+    /// Content is streamed from content storage into the bundle file.
+    ///
+    /// See [`Client::import_package`] to import the bundle.
+    pub async fn export_package(&self, id: &PackageId, out: &Path) -> ClientResult<()> {
+        tracing::info!("exporting package `{id}` to `{out}`", out = out.display());
+
+        self.upsert([id]).await?;
+        let client = self.routed(id);
+        let info = client
+            .registry
+            .load_package(id)
+            .await?
+            .ok_or_else(|| ClientError::PackageDoesNotExist { id: id.clone() })?;
+        let checkpoint = client
+            .registry
+            .load_checkpoint()
+            .await?
+            .ok_or_else(|| anyhow!("no checkpoint is stored for the registry"))?;
+
+        let log_id = LogId::package_log::<Sha256>(id);
+        let log_length = checkpoint.as_ref().checkpoint.log_length;
+        let batches = client.fetch_logs_stream(FetchLogsRequest {
+            log_length,
+            operator: None,
+            limit: None,
+            packages: Cow::Owned(HashMap::new()),
+        });
+        futures_util::pin_mut!(batches);
+
+        let mut operator = Vec::new();
+        while let Some(batch) = batches.try_next().await? {
+            operator.extend(batch.operator);
+        }
+
+        let records: Vec<_> = client
+            .package_log_records(id, log_length, None)
+            .try_collect()
+            .await?;
+
+        let mut leafs = Vec::with_capacity(2);
+        if let Some(last) = operator.last() {
+            let last: PublishedProtoEnvelope<operator::OperatorRecord> = last.clone().try_into()?;
+            leafs.push(last.registry_index);
+        }
+        if let Some(last) = records.last() {
+            let last: PublishedProtoEnvelope<package::PackageRecord> = last.clone().try_into()?;
+            leafs.push(last.registry_index);
+        }
+        let inclusion = client
+            .api()?
+            .inclusion_proof(InclusionRequest { log_length, leafs })
+            .await?;
+
+        let mut contents = Vec::new();
+        for release in info.state.releases() {
+            let Some(digest) = release.content() else {
+                continue;
+            };
+
+            client
+                .download_content(&log_id, &release.record_id, digest)
+                .await?;
+            let size = self
+                .content
+                .load_content(digest)
+                .await?
+                .ok_or_else(|| ClientError::ContentNotFound {
+                    digest: digest.clone(),
+                })?
+                .try_fold(
+                    0,
+                    |size, bytes| async move { Ok(size + bytes.len() as u64) },
+                )
+                .await?;
+            contents.push(bundle::ManifestContent {
+                digest: digest.clone(),
+                size,
+            });
+        }
+
+        let manifest = bundle::Manifest {
+            id: id.clone(),
+            checkpoint,
+            operator,
+            records,
+            inclusion,
+            contents,
+        };
+
+        let write_error = || format!("failed to write bundle `{out}`", out = out.display());
+        let mut file = tokio::fs::File::create(out)
+            .await
+            .with_context(write_error)?;
+        file.write_all(&bundle::encode_manifest(&manifest)?)
+            .await
+            .with_context(write_error)?;
+        for content in &manifest.contents {
+            let mut stream = self
+                .content
+                .load_content(&content.digest)
+                .await?
+                .ok_or_else(|| ClientError::ContentNotFound {
+                    digest: content.digest.clone(),
+                })?;
+
+            let mut written = 0;
+            while let Some(bytes) = stream.try_next().await? {
+                file.write_all(&bytes).await.with_context(write_error)?;
+                written += bytes.len() as u64;
+            }
+
+            if written != content.size {
+                return Err(anyhow!(
+                    "content `{digest}` changed while it was exported",
+                    digest = content.digest
+                )
+                .into());
+            }
+        }
+        file.flush().await.with_context(write_error)?;
+
+        Ok(())
+    }
+
+    /// Imports a package log from a bundle file created by
+    /// [`Client::export_package`].
+    ///
+    /// Before anything is stored, every record of the operator and package
+    /// logs is validated, including its signature; the checkpoint's
+    /// signature is verified; the heads of both logs are proven to be
+    /// included in the checkpoint; and every content blob is verified
+    /// against its digest.
+    ///
+    /// The checkpoint is trusted only if it is signed by the pinned registry
+    /// key or by a key authorized by the operator log in client storage; the
+    /// operator log of the bundle is used instead when it extends the stored
+    /// one. Returns [`ClientError::UntrustedCheckpointKey`] if the client has
+    /// neither a pinned registry key nor a stored operator log, or if the
+    /// operator log of the bundle is a fork of the stored one.
+    ///
+    /// Returns [`ClientError::PackageValidationFailed`] if a package record
+    /// or content blob of the bundle has been tampered with.
+    ///
+    /// A package log in storage is only replaced by a package log that
+    /// extends it as of a checkpoint no older than its own; otherwise
+    /// [`ClientError::CheckpointRollback`] or
+    /// [`ClientError::LogForkDetected`] is returned. The package log is
+    /// stored with the registry the package is routed to.
+    ///
+    /// Returns the identifier of the imported package.
+    pub async fn import_package(&self, bundle: &Path) -> ClientResult<PackageId> {
+        tracing::info!(
+            "importing package bundle `{bundle}`",
+            bundle = bundle.display()
+        );
+
+        let (manifest, offset) = bundle::read_manifest(bundle).await?;
+        let client = self.routed(&manifest.id);
+        let stored_operator = client
+            .registry
+            .load_operator()
+            .await?
+            .filter(|operator| operator.state.head().is_some());
+
+        // Track whether the operator log of the bundle includes the head of
+        // the stored operator log as it is validated
+        let mut operator = OperatorInfo::default();
+        let mut extends_stored = stored_operator.is_none();
+        for record in manifest.operator {
+            let record: PublishedProtoEnvelope<operator::OperatorRecord> = record.try_into()?;
+            operator
+                .state
+                .validate(&record.envelope)
+                .map_err(|inner| ClientError::OperatorValidationFailed { inner })?;
+            operator.head_registry_index = Some(record.registry_index);
+            extends_stored |= stored_operator
+                .as_ref()
+                .and_then(|o| o.state.head().as_ref())
+                == operator.state.head().as_ref();
+        }
+
+        let mut info = PackageInfo::new(manifest.id.clone());
+        let mut record_ids = HashSet::new();
+        for record in manifest.records {
+            let record: PublishedProtoEnvelope<package::PackageRecord> = record.try_into()?;
+            info.state.validate(&record.envelope).map_err(|inner| {
+                ClientError::PackageValidationFailed {
+                    id: manifest.id.clone(),
+                    inner,
+                }
+            })?;
+            info.head_registry_index = Some(record.registry_index);
+            record_ids.insert(RecordId::package_record::<Sha256>(&record.envelope));
+        }
+
+        let Some(head) = info.state.head() else {
+            return Err(ClientError::PackageLogEmpty { id: manifest.id });
+        };
+
+        // Trust is anchored in client storage: the checkpoint must be signed
+        // by the pinned registry key, if any, and by a key the stored
+        // operator log, or the bundle's operator log extending it, authorizes
+        let key = client.registry.load_registry_key().await?;
+        if let Some(key) = &key {
+            proof::verify_checkpoint_signature(&manifest.checkpoint, key)?;
+        }
+
+        match &stored_operator {
+            Some(stored) if !extends_stored => {
+                // An operator log no longer than the stored one may be a
+                // prefix of it, which the stored operator log vouches for
+                if operator.head_registry_index >= stored.head_registry_index {
+                    return Err(ClientError::UntrustedCheckpointKey {
+                        key_id: manifest.checkpoint.key_id().clone(),
+                    });
+                }
+
+                Self::authorized_checkpoint_key(&manifest.checkpoint, &stored.state)?;
+            }
+            None if key.is_none() => {
+                return Err(ClientError::UntrustedCheckpointKey {
+                    key_id: manifest.checkpoint.key_id().clone(),
+                });
+            }
+            _ => {
+                Self::authorized_checkpoint_key(&manifest.checkpoint, &operator.state)?;
+            }
+        }
+
+        let checkpoint = &manifest.checkpoint.as_ref().checkpoint;
+        let mut leafs = Vec::with_capacity(2);
+        if let Some(head) = operator.state.head() {
+            leafs.push(LogLeaf {
+                log_id: LogId::operator_log::<Sha256>(),
+                record_id: head.digest.clone(),
+            });
+        }
+        leafs.push(LogLeaf {
+            log_id: LogId::package_log::<Sha256>(&info.id),
+            record_id: head.digest.clone(),
+        });
+        api::Client::validate_inclusion_response(&manifest.inclusion, checkpoint, &leafs).map_err(
+            |inner| ClientError::InclusionProofFailed {
+                id: Hash::<Sha256>::of(checkpoint).into(),
+                inner,
+            },
+        )?;
+
+        // The imported package log must not roll back or fork the stored one
+        if let Some(stored) = client.registry.load_package(&info.id).await? {
+            if let (Some(stored_head), Some(pinned)) = (stored.state.head(), &stored.checkpoint) {
+                if checkpoint.log_length < pinned.log_length {
+                    return Err(ClientError::CheckpointRollback {
+                        pinned: pinned.log_length,
+                        found: checkpoint.log_length,
+                    });
+                }
+
+                if !record_ids.contains(&stored_head.digest) {
+                    return Err(ClientError::LogForkDetected {
+                        id: client.url().to_string(),
+                        pinned: Hash::<Sha256>::of(pinned).into(),
+                        presented: Hash::<Sha256>::of(checkpoint).into(),
+                    });
+                }
+            }
+        }
+
+        let mut blobs = Vec::with_capacity(manifest.contents.len());
+        let mut next = offset;
+        for content in &manifest.contents {
+            blobs.push((content, next));
+            next += content.size;
+        }
+
+        for &(content, offset) in &blobs {
+            let mut hasher = content.digest.algorithm().hasher();
+            let mut stream = bundle::read_blob(bundle, offset, content.size).await?;
+            while let Some(bytes) = stream.try_next().await? {
+                hasher.update(&bytes);
+            }
+
+            if hasher.finalize() != content.digest {
+                return Err(ClientError::PackageValidationFailed {
+                    id: manifest.id,
+                    inner: package::ValidationError::ContentDigestMismatch {
+                        digest: content.digest.clone(),
+                    },
+                });
+            }
+        }
+
+        for release in info.state.releases() {
+            if let Some(digest) = release.content() {
+                if !manifest.contents.iter().any(|c| &c.digest == digest) {
+                    return Err(ClientError::InvalidBundle {
+                        reason: format!("content `{digest}` is missing from the bundle"),
+                    });
+                }
+            }
+        }
+
+        for (content, offset) in blobs {
+            self.content
+                .store_content(
+                    bundle::read_blob(bundle, offset, content.size).await?,
+                    Some(&content.digest),
+                )
+                .await?;
+        }
+
+        info.checkpoint = Some(checkpoint.clone());
+        info.checkpoint_timestamp = Some(manifest.checkpoint.as_ref().timestamp);
+        client.registry.store_package(&info).await?;
+
+        Ok(manifest.id)
+    }
+
+    /// Fetches log records from the registry as a stream of batches.
+    ///
+    /// The given request is the starting point of the fetch; subsequent
+    /// requests are made with the last record of each log seen in the
+    /// previous batch until the registry reports there are no more records.
+    ///
+    /// Each batch is yielded as it is received, so a consumer may process and
+    /// discard batches without holding the entire log in memory. Dropping the
+    /// stream stops any further requests.
+    ///
+    /// Records are not validated or stored in client storage.
+    pub fn fetch_logs_stream(
+        &self,
+        request: FetchLogsRequest<'static>,
+    ) -> impl Stream<Item = ClientResult<FetchLogsResponse>> + '_ {
+        futures_util::stream::try_unfold(Some(request), move |request| async move {
+            let Some(mut request) = request else {
+                return Ok(None);
+            };
+
+            let response = self
+                .api()?
+                .fetch_logs(FetchLogsRequest {
+                    log_length: request.log_length,
+                    limit: request.limit,
+                    operator: request.operator.as_deref().map(Cow::Borrowed),
+                    packages: Cow::Borrowed(&request.packages),
+                })
+                .await?;
+
+            if !response.more {
+                return Ok(Some((response, None)));
+            }
+
+            // Advance the cursor to the last record of each log in the batch
+            if let Some(last) = response.operator.last() {
+                let record: PublishedProtoEnvelope<operator::OperatorRecord> =
+                    last.clone().try_into()?;
+                request.operator = Some(Cow::Owned(RecordId::operator_record::<Sha256>(
+                    &record.envelope,
+                )));
+            }
+
+            for (log_id, records) in &response.packages {
+                if let Some(last) = records.last() {
+                    let record: PublishedProtoEnvelope<package::PackageRecord> =
+                        last.clone().try_into()?;
+                    request.packages.to_mut().insert(
+                        log_id.clone(),
+                        Some(RecordId::package_record::<Sha256>(&record.envelope)),
+                    );
+                }
+            }
+
+            Ok(Some((response, Some(request))))
+        })
+    }
+
+    /// Fetches the records of a package log first included in a registry
+    /// checkpoint timestamped at or after the given time.
+    ///
+    /// The registry is asked for the latest checkpoint timestamped before the
+    /// given time; the records returned are those after that checkpoint's
+    /// log length, fetched as of the latest registry checkpoint. The
+    /// timestamps of the records themselves are set by their publishers and
+    /// are not considered.
+    ///
+    /// Records are not validated or stored in client storage.
+    ///
+    /// Returns [`ClientError::PackageDoesNotExist`] if the package does not
+    /// exist.
+    pub async fn fetch_logs_since(
+        &self,
+        id: &PackageId,
+        since: SystemTime,
+    ) -> ClientResult<LogsSince> {
+        let client = self.routed(id);
+        let checkpoint = client.api()?.latest_checkpoint().await?;
+
+        // Checkpoint timestamps are in whole seconds, so a checkpoint is
+        // before `since` exactly when it is before `since` rounded up
+        let timestamp = since
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_secs() + u64::from(d.subsec_nanos() > 0))
+            .unwrap_or_default();
+        let included = client
+            .api()?
+            .checkpoint_before(timestamp)
+            .await?
+            .map(|c| c.as_ref().checkpoint.log_length)
+            .unwrap_or_default();
+
+        let bodies =
+            client.package_log_records(id, checkpoint.as_ref().checkpoint.log_length, None);
+        futures_util::pin_mut!(bodies);
+
+        let mut records = Vec::new();
+        while let Some(body) = bodies.try_next().await? {
+            let published: PublishedProtoEnvelope<package::PackageRecord> = body.try_into()?;
+            if published.registry_index >= included {
+                records.push(published);
+            }
+        }
+
+        tracing::debug!(
+            "fetched {count} record(s) of package `{id}` since the given time",
+            count = records.len()
+        );
+
+        Ok(LogsSince {
+            records,
+            checkpoint,
+        })
+    }
+
+    /// Fetches the records of a package log that were added between two
+    /// registry checkpoints.
+    ///
+    /// The log of `to` is first proven to be consistent with the log of
+    /// `from`; the returned records are those included in `to` but not in
+    /// `from`, in log order.
+    ///
+    /// The package log is validated up to `to` and its head is proven
+    /// included in `to`. If the package log in client storage ends before
+    /// `from`, only the records after it are fetched; otherwise the log is
+    /// fetched from its start. Nothing is stored in client storage.
+    ///
+    /// Returns [`ClientError::CheckpointRollback`] if `to` is older than
+    /// `from`, [`ClientError::LogForkDetected`] if the checkpoints are not
+    /// consistent, [`ClientError::PackageValidationFailed`] if the package
+    /// log is invalid and [`ClientError::InclusionProofFailed`] if its head
+    /// is not included in `to`.
+    pub async fn diff_checkpoints(
+        &self,
+        id: &PackageId,
+        from: &TimestampedCheckpoint,
+        to: &TimestampedCheckpoint,
+    ) -> ClientResult<Vec<PublishedProtoEnvelope<package::PackageRecord>>> {
+        let client = self.routed(id);
+        let (from, to) = (&from.checkpoint, &to.checkpoint);
+        if to.log_length < from.log_length {
+            return Err(ClientError::CheckpointRollback {
+                pinned: from.log_length,
+                found: to.log_length,
+            });
+        }
+
+        client.prove_checkpoints_consistent(from, to).await?;
+
+        // Start from the stored package log if it ends before `from`, as its
+        // records are then all included in `from`
+        let mut info = match client.registry.load_package(id).await? {
+            Some(info)
+                if info
+                    .head_registry_index
+                    .map_or(false, |index| index < from.log_length) =>
+            {
+                info
+            }
+            _ => PackageInfo::new(id.clone()),
+        };
+
+        let since = info.state.head().as_ref().map(|head| head.digest.clone());
+        let bodies = client.package_log_records(id, to.log_length, since);
+        futures_util::pin_mut!(bodies);
+
+        let mut records = Vec::new();
+        while let Some(body) = bodies.try_next().await? {
+            let published: PublishedProtoEnvelope<package::PackageRecord> = body.try_into()?;
+            info.state.validate(&published.envelope).map_err(|inner| {
+                ClientError::PackageValidationFailed {
+                    id: id.clone(),
+                    inner,
+                }
+            })?;
+            info.head_registry_index = Some(published.registry_index);
+            if published.registry_index >= from.log_length {
+                records.push(published);
+            }
+        }
+
+        if info.state.head().is_none() {
+            return Err(ClientError::PackageDoesNotExist { id: id.clone() });
+        }
+
+        match client
+            .verify_inclusion(
+                to,
+                &OperatorInfo::default(),
+                &HashMap::from([(LogId::package_log::<Sha256>(id), &mut info)]),
+            )
+            .await
+        {
+            Err(ClientError::InclusionProofFailed {
+                inner: api::ClientError::Unsupported { operation },
+                ..
+            }) => return Err(ClientError::ProofServiceUnavailable { operation }),
+            res => res?,
+        }
+
+        tracing::debug!(
+            "fetched {count} record(s) of package `{id}` added between the given checkpoints",
+            count = records.len()
+        );
+
+        Ok(records)
+    }
+
+    /// Proves that the log of checkpoint `to` is consistent with the log of
+    /// the older checkpoint `from`.
+    ///
+    /// Returns [`ClientError::LogForkDetected`] if the checkpoints are not
+    /// consistent and [`ClientError::ProofServiceUnavailable`] if the
+    /// registry does not serve consistency proofs.
+    async fn prove_checkpoints_consistent(
+        &self,
+        from: &Checkpoint,
+        to: &Checkpoint,
+    ) -> ClientResult<()> {
+        if from == to {
+            return Ok(());
+        }
+
+        let fork = || ClientError::LogForkDetected {
+            id: self.url().to_string(),
+            pinned: Hash::<Sha256>::of(from).into(),
+            presented: Hash::<Sha256>::of(to).into(),
+        };
+
+        // Checkpoints of the same length must be identical
+        if from.log_length == to.log_length {
+            return Err(fork());
+        }
+
+        self.api()?
+            .prove_log_consistency(
+                ConsistencyRequest {
+                    from: from.log_length,
+                    to: to.log_length,
+                },
+                Cow::Borrowed(&from.log_root),
+                Cow::Borrowed(&to.log_root),
+            )
+            .await
+            .map_err(|e| match e {
+                api::ClientError::IncorrectConsistencyProof { .. }
+                | api::ClientError::ConsistencyProof(_) => fork(),
+                e => proof_service_error(e),
+            })
+    }
+
+    /// Gets the state of a package log as of the given registry checkpoint,
+    /// such as a checkpoint recorded by a lockfile.
+    ///
+    /// Only the records included in the checkpoint are validated, so
+    /// versions released after the checkpoint are absent. Unless proof
+    /// verification is disabled, the checkpoint is proven consistent with
+    /// the last-seen checkpoint in client storage, or with the latest
+    /// registry checkpoint if none was seen, and the head of the package log
+    /// is proven included in the checkpoint.
+    ///
+    /// The package log is not stored in client storage.
+    ///
+    /// Returns [`ClientError::PackageDoesNotExist`] if the package has no
+    /// records in the checkpoint and [`ClientError::LogForkDetected`] if the
+    /// checkpoint is not consistent with the last-seen checkpoint.
+    pub async fn package_info_at(
+        &self,
+        id: &PackageId,
+        ts_checkpoint: &TimestampedCheckpoint,
+    ) -> ClientResult<PackageInfo> {
+        let client = self.routed(id);
+        let checkpoint = &ts_checkpoint.checkpoint;
+        let checkpoint_id: AnyHash = Hash::<Sha256>::of(checkpoint).into();
+        let mut verified = client.verify_proofs;
+
+        if client.verify_proofs {
+            let anchor = match client.registry.load_checkpoint().await? {
+                Some(pinned) => pinned,
+                None => {
+                    let latest = client.api()?.latest_checkpoint().await?;
+                    if let Some(key) = client.registry.load_registry_key().await? {
+                        proof::verify_checkpoint_signature(&latest, &key)?;
+                    }
+
+                    latest
+                }
+            };
+
+            let anchor = &anchor.as_ref().checkpoint;
+            let res = if checkpoint.log_length <= anchor.log_length {
+                client
+                    .prove_checkpoints_consistent(checkpoint, anchor)
+                    .await
+            } else {
+                client
+                    .prove_checkpoints_consistent(anchor, checkpoint)
+                    .await
+            };
+
+            match res {
+                Err(ClientError::ProofServiceUnavailable { operation }) => {
+                    client.skip_unavailable_proof(&checkpoint_id, operation)?;
+                    verified = false;
+                }
+                res => res?,
+            }
+        }
+
+        let log_id = LogId::package_log::<Sha256>(id);
+        let mut info = PackageInfo::new(id.clone());
+        let bodies = client.package_log_records(id, checkpoint.log_length, None);
+        futures_util::pin_mut!(bodies);
+
+        while let Some(body) = bodies.try_next().await? {
+            let record: PublishedProtoEnvelope<package::PackageRecord> = body.try_into()?;
+            info.state.validate(&record.envelope).map_err(|inner| {
+                ClientError::PackageValidationFailed {
+                    id: id.clone(),
+                    inner,
+                }
+            })?;
+            info.head_registry_index = Some(record.registry_index);
+        }
+
+        if info.state.head().is_none() {
+            return Err(ClientError::PackageDoesNotExist { id: id.clone() });
+        }
+
+        if verified {
+            match client
+                .verify_inclusion(
+                    checkpoint,
+                    &OperatorInfo::default(),
+                    &HashMap::from([(log_id, &mut info)]),
+                )
+                .await
+            {
+                Err(ClientError::InclusionProofFailed {
+                    inner: api::ClientError::Unsupported { operation },
+                    ..
+                }) => {
+                    client.skip_unavailable_proof(&checkpoint_id, operation)?;
+                    verified = false;
+                }
+                res => res?,
+            }
+        }
+
+        info.checkpoint = Some(checkpoint.clone());
+        info.checkpoint_timestamp = Some(ts_checkpoint.timestamp);
+        info.unverified = !verified;
+        Ok(info)
+    }
+
+    /// Fetches a proof that a version of a package is included in the
+    /// registry log.
+    ///
+    /// The package log is first updated to the latest registry checkpoint;
+    /// the proof is for the latest checkpoint and is verified before it is
+    /// returned.
+    ///
+    /// Returns [`ClientError::PackageVersionDoesNotExist`] if the version was
+    /// never released.
+    pub async fn inclusion_proof(
+        &self,
+        id: &PackageId,
+        version: &Version,
+    ) -> ClientResult<InclusionProof> {
+        let client = self.routed(id);
+        client.upsert([id]).await?;
+
+        let ts_checkpoint = client.api()?.latest_checkpoint().await?;
+        let key = match client.registry.load_registry_key().await? {
+            Some(key) => key,
+            None => {
+                let operator = client.registry.load_operator().await?.unwrap_or_default();
+                Self::authorized_checkpoint_key(&ts_checkpoint, &operator.state)?.clone()
+            }
+        };
+
+        let log_length = ts_checkpoint.as_ref().checkpoint.log_length;
+        let bodies = client.package_log_records(id, log_length, None);
+        futures_util::pin_mut!(bodies);
+
+        // Collect the records from the release of the version to the head
+        let mut records = Vec::new();
+        let mut registry_index = None;
+        while let Some(body) = bodies.try_next().await? {
+            if records.is_empty() {
+                let published: PublishedProtoEnvelope<package::PackageRecord> =
+                    body.clone().try_into()?;
+                if !proof::releases(published.envelope.as_ref(), version) {
+                    continue;
+                }
+            }
+
+            registry_index = Some(body.registry_index);
+            records.push(body.envelope);
+        }
+
+        let Some(registry_index) = registry_index else {
+            return Err(ClientError::PackageVersionDoesNotExist {
+                version: version.clone(),
+                id: id.clone(),
+            });
+        };
+
+        let proof = InclusionProof {
+            id: id.clone(),
+            version: version.clone(),
+            records,
+            registry_index,
+            proof: client
+                .api()?
+                .inclusion_proof(InclusionRequest {
+                    log_length,
+                    leafs: vec![registry_index],
+                })
+                .await
+                .map_err(proof_service_error)?,
+            checkpoint: ts_checkpoint,
+        };
+        proof.verify(&key)?;
+
+        Ok(proof)
+    }
+
+    /// Verifies the latest registry checkpoint against the log of a package
+    /// in client storage.
+    ///
+    /// This is an integrity check independent of updating the package log:
+    /// the roots of the checkpoint the package log was last updated to are
+    /// recomputed from the head of the stored package log and the registry's
+    /// inclusion proofs for it, and compared to the roots the checkpoint
+    /// claims. The latest registry checkpoint is proven consistent with that
+    /// checkpoint. Nothing is stored.
+    ///
+    /// Returns [`ClientError::PackageDoesNotExist`] if the package log is not
+    /// in client storage, [`ClientError::CheckpointMismatch`] if a recomputed
+    /// root differs from the checkpoint and [`ClientError::LogForkDetected`]
+    /// if the latest registry checkpoint is not consistent with it.
+    pub async fn verify_registry_checkpoint(&self, id: &PackageId) -> ClientResult<()> {
+        let client = self.routed(id);
+        let local = client
+            .registry
+            .compute_checkpoint(id)
+            .await?
+            .ok_or_else(|| ClientError::PackageDoesNotExist { id: id.clone() })?;
+
+        let ts_checkpoint = client.api()?.latest_checkpoint().await?;
+        let key = match client.registry.load_registry_key().await? {
+            Some(key) => key,
+            None => {
+                let operator = client.registry.load_operator().await?.unwrap_or_default();
+                Self::authorized_checkpoint_key(&ts_checkpoint, &operator.state)?.clone()
+            }
+        };
+        proof::verify_checkpoint_signature(&ts_checkpoint, &key)?;
+
+        let checkpoint = &local.checkpoint;
+        let response = client
+            .api()?
+            .inclusion_proof(InclusionRequest {
+                log_length: checkpoint.log_length,
+                leafs: vec![local.registry_index],
+            })
+            .await
+            .map_err(proof_service_error)?;
+        let (log_root, map_root) =
+            api::Client::evaluate_inclusion_response(&response, std::slice::from_ref(&local.leaf))?
+                .remove(0);
+
+        let mismatch = |claimed: &AnyHash, computed| ClientError::CheckpointMismatch {
+            id: id.clone(),
+            claimed: claimed.clone(),
+            computed,
+        };
+        if log_root != checkpoint.log_root {
+            return Err(mismatch(&checkpoint.log_root, log_root));
+        }
+
+        if map_root != checkpoint.map_root {
+            return Err(mismatch(&checkpoint.map_root, map_root));
+        }
+
+        let latest = &ts_checkpoint.as_ref().checkpoint;
+        if checkpoint.log_length <= latest.log_length {
+            client
+                .prove_checkpoints_consistent(checkpoint, latest)
+                .await
+        } else {
+            client
+                .prove_checkpoints_consistent(latest, checkpoint)
+                .await
+        }
+    }
+
+    /// Fetches and validates the operator log of the registry.
+    ///
+    /// The operator log records the keys authorized to operate the registry.
+    /// Validated records are cached in client storage so that only records
+    /// newer than the cached records are fetched; an offline client returns
+    /// the cached records.
+    ///
+    /// Returns [`ClientError::OperatorValidationFailed`] if any record of the
+    /// log is invalid, in which case nothing is cached.
+    ///
+    /// Returns the records of the operator log as of the latest registry
+    /// checkpoint, in order.
+    #[tracing::instrument(skip_all)]
+    pub async fn fetch_operator_log(
+        &self,
+    ) -> ClientResult<Vec<PublishedProtoEnvelope<operator::OperatorRecord>>> {
+        let mut operator = self.registry.load_operator().await?.unwrap_or_default();
+
+        // Validate the cached records again to resume from their state
+        let mut state = operator::LogState::default();
+        let mut records = Vec::with_capacity(operator.records.len());
+        for record in &operator.records {
+            let record: PublishedProtoEnvelope<operator::OperatorRecord> =
+                record.clone().try_into()?;
+            state
+                .validate(&record.envelope)
+                .map_err(|inner| ClientError::OperatorValidationFailed { inner })?;
+            records.push(record);
+        }
+
+        if self.offline {
+            tracing::info!(
+                "returning {count} cached operator record(s)",
+                count = records.len()
+            );
+            return Ok(records);
+        }
+
+        let ts_checkpoint = self.api()?.latest_checkpoint().await?;
+        self.update_checkpoint(&ts_checkpoint, []).await?;
+
+        let cached = records.len();
+        loop {
+            self.check_cancelled()?;
+            let response = self
+                .api()?
+                .fetch_logs(FetchLogsRequest {
+                    log_length: ts_checkpoint.as_ref().checkpoint.log_length,
+                    operator: state.head().as_ref().map(|h| Cow::Borrowed(&h.digest)),
+                    limit: None,
+                    packages: Cow::Owned(HashMap::new()),
+                })
+                .await?;
+
+            for record in response.operator {
+                let record: PublishedProtoEnvelope<operator::OperatorRecord> = record.try_into()?;
+                state
+                    .validate(&record.envelope)
+                    .map_err(|inner| ClientError::OperatorValidationFailed { inner })?;
+                records.push(record);
+            }
+
+            if !response.more {
+                break;
+            }
+        }
+
+        // The log must authorize the key that signed the checkpoint it was
+        // fetched at
+        Self::authorized_checkpoint_key(&ts_checkpoint, &state)?;
+
+        tracing::info!(
+            "fetched {count} new operator record(s)",
+            count = records.len() - cached
+        );
+        if records.len() > cached {
+            operator.records = records.iter().cloned().map(Into::into).collect();
+            self.registry.store_operator(operator).await?;
+        }
+
+        Ok(records)
+    }
+
+    /// Fetches a single record of a package log from the registry.
+    ///
+    /// The record is looked up directly; if the registry does not support
+    /// looking up a single record, the package log is fetched and scanned for
+    /// the record instead. A record found by scanning is published and has no
+    /// content sources.
+    ///
+    /// Returns [`ClientError::RecordNotFound`] if the package log has no such
+    /// record.
+    pub async fn fetch_record(
+        &self,
+        id: &PackageId,
+        record: &RecordId,
+    ) -> ClientResult<PackageRecord> {
+        let log_id = LogId::package_log::<Sha256>(id);
+        let not_found = || ClientError::RecordNotFound {
+            id: id.clone(),
+            record: record.clone(),
+        };
+
+        let client = self.routed(id);
+        match client.get_package_record(id, &log_id, record).await {
+            Err(ClientError::Api(api::ClientError::Package(PackageError::RecordNotFound(_)))) => {
+                Err(not_found())
+            }
+            Err(ClientError::Unsupported { .. }) => {
+                tracing::debug!(
+                    "registry does not support fetching a single record; scanning package `{id}`"
+                );
+                client.scan_record(id, record).await?.ok_or_else(not_found)
+            }
+            res => res,
+        }
+    }
+
+    /// Fetches the signed envelope of a published record of a package log.
+    ///
+    /// The envelope holds the record's content bytes and signature exactly
+    /// as the registry sent them, so that the signature can be verified
+    /// independently of the client. The record is fetched as with
+    /// [`Client::fetch_record`] and is not validated against the package
+    /// log.
+    ///
+    /// Returns [`ClientError::RecordNotPublished`] if the record has not been
+    /// published yet.
+    pub async fn fetch_record_envelope(
+        &self,
+        id: &PackageId,
+        record: &RecordId,
+    ) -> ClientResult<PublishedProtoEnvelope<package::PackageRecord>> {
+        match self.fetch_record(id, record).await?.state {
+            PackageRecordState::Published {
+                record,
+                registry_index,
+                ..
+            } => Ok(PublishedProtoEnvelopeBody {
+                envelope: record,
+                registry_index,
+            }
+            .try_into()?),
+            _ => Err(ClientError::RecordNotPublished {
+                id: id.clone(),
+                record: record.clone(),
+            }),
+        }
+    }
+
+    /// Scans the package log in the registry for the given record.
+    async fn scan_record(
+        &self,
+        id: &PackageId,
+        record: &RecordId,
+    ) -> ClientResult<Option<PackageRecord>> {
+        let checkpoint = self.api()?.latest_checkpoint().await?;
+        let bodies = self.package_log_records(id, checkpoint.as_ref().checkpoint.log_length, None);
+        futures_util::pin_mut!(bodies);
+
+        while let Some(body) = bodies.try_next().await? {
+            let published: PublishedProtoEnvelope<package::PackageRecord> =
+                body.clone().try_into()?;
+            if RecordId::package_record::<Sha256>(&published.envelope) == *record {
+                return Ok(Some(PackageRecord {
+                    id: record.clone(),
+                    state: PackageRecordState::Published {
+                        record: body.envelope,
+                        content_sources: Default::default(),
+                        registry_index: body.registry_index,
+                    },
+                }));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Streams the records of a package log as of the given registry log
+    /// length, starting after the given record or at the start of the log.
+    ///
+    /// Records are yielded in log order and are neither validated nor stored.
+    ///
+    /// Fails with [`ClientError::PackageDoesNotExist`] if the registry has
+    /// no log for the package.
+    fn package_log_records(
+        &self,
+        id: &PackageId,
+        log_length: RegistryLen,
+        since: Option<RecordId>,
+    ) -> impl Stream<Item = ClientResult<PublishedProtoEnvelopeBody>> + '_ {
+        let id = id.clone();
+        let log_id = LogId::package_log::<Sha256>(&id);
+        self.fetch_logs_stream(FetchLogsRequest {
+            log_length,
+            operator: None,
+            limit: None,
+            packages: Cow::Owned(HashMap::from([(log_id.clone(), since)])),
+        })
+        .map_err(move |e| match e {
+            ClientError::Api(api::ClientError::Fetch(FetchError::LogNotFound(_))) => {
+                ClientError::PackageDoesNotExist { id: id.clone() }
+            }
+            e => e,
+        })
+        .map_ok(move |mut batch| {
+            futures_util::stream::iter(
+                batch
+                    .packages
+                    .remove(&log_id)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(Ok),
+            )
+        })
+        .try_flatten()
+    }
+
+    /// Determines if a package exists in the registry.
+    ///
+    /// A package with a log in client storage exists. Otherwise, the registry
+    /// is asked for at most one record of the package log; the package log is
+    /// neither validated nor stored.
+    ///
+    /// That a package does not exist is remembered for
+    /// [`PACKAGE_NOT_FOUND_CACHE_DURATION`] to avoid repeated requests for
+    /// the same missing package.
+    pub async fn package_exists(&self, id: &PackageId) -> ClientResult<bool> {
+        let client = self.routed(id);
+        if let Some(info) = client.registry.load_package(id).await? {
+            if info.state.head().is_some() {
+                return Ok(true);
+            }
+        }
+
+        if let Some(checked) = client.missing_packages.lock().unwrap().get(id) {
+            if checked.elapsed() < PACKAGE_NOT_FOUND_CACHE_DURATION {
+                tracing::debug!("package `{id}` was recently found to not exist");
+                return Ok(false);
+            }
+        }
+
+        tracing::info!("checking if package `{id}` exists");
+
+        let api = client.api()?;
+        let checkpoint = api.latest_checkpoint().await?;
+        let log_id = LogId::package_log::<Sha256>(id);
+        match api
+            .fetch_logs(FetchLogsRequest {
+                log_length: checkpoint.as_ref().checkpoint.log_length,
+                limit: Some(1),
+                operator: None,
+                packages: Cow::Owned(HashMap::from([(log_id.clone(), None)])),
+            })
+            .await
+        {
+            Ok(_) => {
+                client.missing_packages.lock().unwrap().remove(id);
+                Ok(true)
+            }
+            Err(api::ClientError::Fetch(FetchError::LogNotFound(missing))) if missing == log_id => {
+                client
+                    .missing_packages
+                    .lock()
+                    .unwrap()
+                    .insert(id.clone(), Instant::now());
+                Ok(false)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Lists the packages in the registry.
+    ///
+    /// If a prefix is given, only packages with ids starting with the prefix
+    /// are listed; for example, a prefix of `wasi:` lists the packages in the
+    /// `wasi` namespace. The listing is paginated by the registry and the
+    /// pages are fetched until the listing is complete.
+    ///
+    /// If the prefix names a namespace that is routed to another registry,
+    /// that registry is listed instead.
+    ///
+    /// Returns [`ClientError::Unsupported`] if the registry does not support
+    /// listing packages.
+    pub async fn list_packages(&self, prefix: Option<&str>) -> ClientResult<Vec<PackageId>> {
+        tracing::info!("listing packages");
+
+        let api = prefix
+            .and_then(|prefix| prefix.split_once(':'))
+            .map_or(self, |(namespace, _)| self.route(namespace))
+            .api()?;
+        let mut packages = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = api
+                .list_packages(&ListPackagesQuery {
+                    prefix: prefix.map(Cow::Borrowed),
+                    cursor: cursor.take().map(Cow::Owned),
+                    limit: None,
+                })
+                .await?;
+            packages.extend(
+                page.packages
+                    .into_iter()
+                    .filter(|id| prefix.map_or(true, |p| id.as_ref().starts_with(p))),
+            );
+
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => return Ok(packages),
+            }
+        }
+    }
+
+    /// Resolves the latest version of a package that satisfies the given
+    /// version requirement.
+    ///
+    /// If the requested package log is not present in client storage, it
+    /// will be fetched from the registry first.
+    ///
+    /// Yanked and prerelease versions are not considered; use
+    /// [`Client::resolve_version_including_prerelease`] to also consider
+    /// prerelease versions.
+    ///
+    /// An error is returned if the package does not exist or if no version
+    /// satisfies the requirement.
+    pub async fn resolve_version(
+        &self,
+        id: &PackageId,
+        requirement: &VersionReq,
+    ) -> ClientResult<Version> {
+        self.routed(id)
+            .resolve_version_with(id, requirement, false)
+            .await
+    }
+
+    /// Resolves the latest version of a package that satisfies the given
+    /// version requirement, considering prerelease versions.
+    ///
+    /// A prerelease version is considered to satisfy the requirement if its
+    /// release version does.
+    ///
+    /// This is otherwise the same as [`Client::resolve_version`].
+    pub async fn resolve_version_including_prerelease(
+        &self,
+        id: &PackageId,
+        requirement: &VersionReq,
+    ) -> ClientResult<Version> {
+        self.routed(id)
+            .resolve_version_with(id, requirement, true)
+            .await
+    }
+
+    async fn resolve_version_with(
+        &self,
+        id: &PackageId,
+        requirement: &VersionReq,
+        include_prerelease: bool,
+    ) -> ClientResult<Version> {
+        tracing::info!("resolving version of package `{id}` with requirement `{requirement}`");
+        let info = self.fetch_package(id).await?;
+
+        let matches = |version: &Version| {
+            if requirement.matches(version) {
+                return version.pre.is_empty() || include_prerelease;
+            }
+
+            include_prerelease
+                && !version.pre.is_empty()
+                && requirement.matches(&Version::new(version.major, version.minor, version.patch))
+        };
+
+        info.state
+            .releases()
+            .filter(|r| !r.yanked() && matches(&r.version))
+            .map(|r| &r.version)
+            .max()
+            .cloned()
+            .ok_or_else(|| ClientError::PackageVersionRequirementDoesNotExist {
+                requirement: requirement.clone(),
+                id: id.clone(),
+            })
+    }
+
+    /// Downloads the latest version of a package into client storage that
+    /// satisfies the given version requirement.
+    ///
+    /// If the requested package log is not present in client storage, it
+    /// will be fetched from the registry first.
+    ///
+    /// An error is returned if the package does not exist.
+    ///
+    /// If a version satisfying the requirement does not exist, `None` is
+    /// returned.
+    ///
+    /// Returns the path within client storage of the package contents for
+    /// the resolved version.
+    ///
+    /// Yanked versions are not considered; use
+    /// [`Client::download_including_yanked`] to also consider them.
+    pub async fn download(
+        &self,
+        id: &PackageId,
+        requirement: &VersionReq,
+    ) -> Result<Option<PackageDownload>, ClientError> {
+        self.routed(id).download_with(id, requirement, false).await
+    }
+
+    /// Downloads the latest version of a package into client storage that
+    /// satisfies the given version requirement, considering yanked versions.
+    ///
+    /// Versions yanked before their content was retained in client storage
+    /// are not considered.
+    ///
+    /// This is otherwise the same as [`Client::download`].
+    pub async fn download_including_yanked(
+        &self,
+        id: &PackageId,
+        requirement: &VersionReq,
+    ) -> Result<Option<PackageDownload>, ClientError> {
+        self.routed(id).download_with(id, requirement, true).await
+    }
+
+    #[tracing::instrument(name = "download", skip_all, fields(%id, %requirement))]
+    async fn download_with(
+        &self,
+        id: &PackageId,
+        requirement: &VersionReq,
+        include_yanked: bool,
+    ) -> Result<Option<PackageDownload>, ClientError> {
+        tracing::info!("downloading package `{id}` with requirement `{requirement}`");
+        let info = self.fetch_package(id).await?;
+        let log_id = LogId::package_log::<Sha256>(&info.id);
+
+        let release = if include_yanked {
+            info.state
+                .releases()
+                .filter(|r| requirement.matches(&r.version) && r.released_content().is_some())
+                .max_by(|a, b| a.version.cmp(&b.version))
+        } else {
+            info.state.find_latest_release(requirement)
+        };
+
+        match release.and_then(|r| Some((r, r.released_content()?))) {
+            Some((release, digest)) => {
+                warn_if_deprecated(id, release);
+                let digest = digest.clone();
+                let checkpoint = self.resolved_checkpoint(&info).await?;
                 let path = self
                     .download_content(&log_id, &release.record_id, &digest)
                     .await?;
@@ -301,6 +2999,8 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
                     version: release.version.clone(),
                     digest,
                     path,
+                    deprecation: release.deprecation.clone(),
+                    checkpoint,
                 }))
             }
             None => Ok(None),
@@ -316,13 +3016,171 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
     ///
     /// Returns the path within client storage of the package contents for
     /// the specified version.
+    #[tracing::instrument(name = "download", skip_all, fields(id = %package, %version))]
     pub async fn download_exact(
         &self,
         package: &PackageId,
         version: &Version,
+    ) -> Result<PackageDownload, ClientError> {
+        self.download_release(package, version, None).await
+    }
+
+    /// Downloads the specified version of a package into client storage,
+    /// requiring its content to have the given digest.
+    ///
+    /// This is intended for lockfiles that record the content digest of each
+    /// resolved version: if the registry now releases different content for
+    /// the version, [`ClientError::DigestMismatch`] is returned before any
+    /// content is transferred.
+    ///
+    /// If the requested package log is not present in client storage, it
+    /// will be fetched from the registry first.
+    #[tracing::instrument(name = "download", skip_all, fields(id = %package, %version))]
+    pub async fn download_pinned(
+        &self,
+        package: &PackageId,
+        version: &Version,
+        expected_digest: &AnyHash,
+    ) -> Result<PackageDownload, ClientError> {
+        self.download_release(package, version, Some(expected_digest))
+            .await
+    }
+
+    /// Ensures the content of each of the given package versions is in
+    /// client storage, downloading only the content that is missing.
+    ///
+    /// This is intended to restore the content listed in a lockfile: each
+    /// missing entry is downloaded as with [`Client::download_pinned`], so
+    /// the registry must still release the given digest for the version.
+    /// Missing content is downloaded concurrently, up to the client's
+    /// maximum number of concurrent downloads; if any download fails, the
+    /// remaining downloads are cancelled and the first error is returned.
+    pub async fn ensure_content(
+        &self,
+        entries: &[(PackageId, Version, AnyHash)],
+    ) -> ClientResult<EnsureContentReport> {
+        let mut report = EnsureContentReport::default();
+        let mut missing = Vec::new();
+        for entry in entries {
+            if self.content.contains_content(&entry.2).await? {
+                report.present.push(entry.clone());
+            } else {
+                missing.push(entry);
+            }
+        }
+
+        tracing::info!(
+            "{present} of {count} content entries already exist in storage",
+            present = report.present.len(),
+            count = entries.len()
+        );
+
+        report.downloaded = futures_util::stream::iter(missing)
+            .map(|entry| async move {
+                self.download_pinned(&entry.0, &entry.1, &entry.2).await?;
+                Ok::<_, ClientError>(entry.clone())
+            })
+            .buffered(self.max_concurrent_downloads)
+            .try_collect()
+            .await?;
+
+        Ok(report)
+    }
+
+    /// Gets the digests of the content that would be downloaded for the
+    /// latest version of a package satisfying the given requirement.
+    ///
+    /// The version is resolved as with [`Client::download`], fetching the
+    /// package log first if it is not present in client storage, and the
+    /// content released for it is returned if it is not in client storage.
+    /// No content is downloaded.
+    ///
+    /// Returns [`ClientError::PackageVersionRequirementDoesNotExist`] if a
+    /// version satisfying the requirement does not exist.
+    pub async fn missing_content(
+        &self,
+        id: &PackageId,
+        requirement: &VersionReq,
+    ) -> ClientResult<Vec<AnyHash>> {
+        let info = self.routed(id).fetch_package(id).await?;
+        let release = info.state.find_latest_release(requirement).ok_or_else(|| {
+            ClientError::PackageVersionRequirementDoesNotExist {
+                requirement: requirement.clone(),
+                id: id.clone(),
+            }
+        })?;
+
+        let mut missing = Vec::new();
+        if let Some(digest) = release.content() {
+            if !self.content.contains_content(digest).await? {
+                missing.push(digest.clone());
+            }
+        }
+
+        Ok(missing)
+    }
+
+    /// Verifies that a file has the content the specified version of a
+    /// package was released with.
+    ///
+    /// The file is hashed as it is read with the algorithm of the released
+    /// content's digest. The content of a yanked version is still compared.
+    ///
+    /// If the requested package log is not present in client storage, it
+    /// will be fetched from the registry first.
+    #[tracing::instrument(name = "verify_file", skip_all, fields(%id, %version))]
+    pub async fn verify_file(
+        &self,
+        id: &PackageId,
+        version: &Version,
+        path: impl AsRef<Path>,
+    ) -> ClientResult<FileVerification> {
+        let path = path.as_ref();
+        let info = self.routed(id).fetch_package(id).await?;
+        let Some(release) = info.state.release(version) else {
+            return Ok(FileVerification::VersionNotFound);
+        };
+
+        let Some(expected) = release.released_content() else {
+            return Ok(FileVerification::ContentUnknown);
+        };
+        let file = tokio::fs::File::open(path)
+            .await
+            .with_context(|| format!("failed to open `{path}`", path = path.display()))?;
+        let mut stream = ReaderStream::new(file);
+        let mut hasher = expected.algorithm().hasher();
+        while let Some(bytes) = stream
+            .try_next()
+            .await
+            .with_context(|| format!("failed to read `{path}`", path = path.display()))?
+        {
+            hasher.update(&bytes);
+        }
+
+        let actual = hasher.finalize();
+        if actual == *expected {
+            return Ok(FileVerification::Matches);
+        }
+
+        tracing::debug!(
+            "file `{path}` has digest `{actual}` but `{expected}` was released",
+            path = path.display()
+        );
+        Ok(FileVerification::Differs {
+            expected: expected.clone(),
+            actual,
+        })
+    }
+
+    async fn download_release(
+        &self,
+        package: &PackageId,
+        version: &Version,
+        expected_digest: Option<&AnyHash>,
     ) -> Result<PackageDownload, ClientError> {
         tracing::info!("downloading version {version} of package `{package}`");
-        let info = self.fetch_package(package).await?;
+        let client = self.routed(package);
+        let info = client.fetch_package(package).await?;
         let log_id = LogId::package_log::<Sha256>(&info.id);
 
         let release =
@@ -340,26 +3198,254 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
                 id: package.clone(),
             })?;
 
+        if let Some(expected) = expected_digest.filter(|expected| *expected != digest) {
+            return Err(ClientError::DigestMismatch {
+                id: package.clone(),
+                version: version.clone(),
+                expected: expected.clone(),
+                actual: digest.clone(),
+            });
+        }
+
+        warn_if_deprecated(package, release);
+        let checkpoint = client.resolved_checkpoint(&info).await?;
         Ok(PackageDownload {
             version: version.clone(),
             digest: digest.clone(),
-            path: self
+            path: client
                 .download_content(&log_id, &release.record_id, digest)
                 .await?,
+            deprecation: release.deprecation.clone(),
+            checkpoint,
         })
     }
 
-    async fn update_checkpoint<'a>(
+    /// Downloads the latest versions of the given packages into client
+    /// storage that satisfy the given version requirements.
+    ///
+    /// Any requested package log that is not present in client storage will
+    /// be fetched from the registry first.
+    ///
+    /// Package contents are downloaded concurrently, up to the client's
+    /// maximum number of concurrent downloads. If any download fails, the
+    /// remaining downloads are cancelled and the first error is returned.
+    ///
+    /// Returns a download for each requested package in the order given; the
+    /// download is `None` if a version satisfying the requirement does not
+    /// exist.
+    pub async fn download_many<'a, I>(
+        &self,
+        packages: I,
+    ) -> ClientResult<Vec<Option<PackageDownload>>>
+    where
+        I: IntoIterator<Item = (&'a PackageId, &'a VersionReq)>,
+    {
+        let packages = packages.into_iter().collect::<Vec<_>>();
+        tracing::info!(
+            "downloading {count} package(s) with up to {max} concurrent download(s)",
+            count = packages.len(),
+            max = self.max_concurrent_downloads
+        );
+
+        let mut infos = HashMap::with_capacity(packages.len());
+        let mut missing = Vec::new();
+        for (id, _) in &packages {
+            if infos.contains_key(*id) {
+                continue;
+            }
+
+            match self.routed(id).registry.load_package(id).await? {
+                Some(info) => {
+                    infos.insert((*id).clone(), info);
+                }
+                None => missing.push(PackageInfo::new((*id).clone())),
+            }
+        }
+
+        if let Some(info) = missing.first().filter(|_| self.offline) {
+            return Err(ClientError::OfflineDataMissing {
+                id: info.id.to_string(),
+            });
+        }
+
+        for (client, mut missing) in self.group_by_route(missing, |info| &info.id) {
+            client
+                .update_checkpoint(&client.api()?.latest_checkpoint().await?, &mut missing)
+                .await?;
+            infos.extend(missing.into_iter().map(|info| (info.id.clone(), info)));
+        }
+
+        let mut resolved = Vec::with_capacity(packages.len());
+        let mut contents = Vec::new();
+        for (id, requirement) in &packages {
+            let release = infos[*id].state.find_latest_release(requirement);
+            if let Some(release) = release {
+                warn_if_deprecated(id, release);
+                let digest = release
+                    .content()
+                    .context("invalid state: not yanked but missing content")?
+                    .clone();
+                contents.push((
+                    *id,
+                    LogId::package_log::<Sha256>(id),
+                    release.record_id.clone(),
+                    digest.clone(),
+                ));
+                resolved.push(Some((
+                    release.version.clone(),
+                    digest,
+                    release.deprecation.clone(),
+                    self.routed(id).resolved_checkpoint(&infos[*id]).await?,
+                )));
+            } else {
+                resolved.push(None);
+            }
+        }
+
+        let mut paths = HashMap::new();
+        for (client, contents) in self.group_by_route(contents, |(id, ..)| id) {
+            paths.extend(
+                client
+                    .download_contents(
+                        contents
+                            .into_iter()
+                            .map(|(_, log_id, record_id, digest)| (log_id, record_id, digest)),
+                    )
+                    .await?,
+            );
+        }
+
+        Ok(resolved
+            .into_iter()
+            .map(|r| {
+                r.map(
+                    |(version, digest, deprecation, checkpoint)| PackageDownload {
+                        version,
+                        path: paths[&digest].clone(),
+                        digest,
+                        deprecation,
+                        checkpoint,
+                    },
+                )
+            })
+            .collect())
+    }
+
+    async fn update_checkpoint<'a>(
+        &self,
+        ts_checkpoint: &SerdeEnvelope<TimestampedCheckpoint>,
+        packages: impl IntoIterator<Item = &mut PackageInfo>,
+    ) -> Result<HashMap<PackageId, usize>, ClientError> {
+        let staged = self.stage_checkpoint(ts_checkpoint, packages).await?;
+        self.commit_staged(&staged).await?;
+        Ok(staged.new_records)
+    }
+
+    /// Fetches and verifies the logs of the given packages at the given
+    /// checkpoint without storing them.
+    ///
+    /// The given package information is updated in place.
+    #[tracing::instrument(
+        name = "fetch",
+        skip_all,
+        fields(log_length = ts_checkpoint.as_ref().checkpoint.log_length)
+    )]
+    async fn stage_checkpoint(
         &self,
         ts_checkpoint: &SerdeEnvelope<TimestampedCheckpoint>,
         packages: impl IntoIterator<Item = &mut PackageInfo>,
-    ) -> Result<(), ClientError> {
+    ) -> ClientResult<StagedUpdate> {
+        self.check_cancelled()?;
+
         let checkpoint = &ts_checkpoint.as_ref().checkpoint;
         let checkpoint_id: AnyHash = Hash::<Sha256>::of(checkpoint).into();
         tracing::info!("updating to checkpoint `{checkpoint_id}`");
 
+        // Only accept a checkpoint signed by the pinned registry key
+        let pinned_key = self.registry.load_registry_key().await?;
+        if let Some(key) = &pinned_key {
+            proof::verify_checkpoint_signature(ts_checkpoint, key)?;
+        }
+
+        // Only accept a checkpoint old enough to have been witnessed by others
+        if let Some(min_age) = self.checkpoint_min_age {
+            let timestamp = ts_checkpoint.as_ref().timestamp;
+            let age = SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH + Duration::from_secs(timestamp))
+                .unwrap_or_default();
+            if age < min_age {
+                return Err(ClientError::CheckpointTooRecent { timestamp, min_age });
+            }
+        }
+
+        // Only accept a checkpoint that is consistent with the last-seen checkpoint
+        let mut verified = self.verify_proofs;
+        if let Some(pinned) = self.registry.load_checkpoint().await? {
+            let pinned = &pinned.as_ref().checkpoint;
+            if checkpoint.log_length < pinned.log_length {
+                return Err(ClientError::CheckpointRollback {
+                    pinned: pinned.log_length,
+                    found: checkpoint.log_length,
+                });
+            }
+
+            if self.verify_proofs && pinned != checkpoint {
+                let res = self
+                    .api()?
+                    .prove_log_consistency(
+                        ConsistencyRequest {
+                            from: pinned.log_length,
+                            to: checkpoint.log_length,
+                        },
+                        Cow::Borrowed(&pinned.log_root),
+                        Cow::Borrowed(&checkpoint.log_root),
+                    )
+                    .instrument(tracing::debug_span!(
+                        "verify_consistency",
+                        from = pinned.log_length,
+                        to = checkpoint.log_length
+                    ))
+                    .await;
+                match res {
+                    Ok(()) => {}
+                    Err(api::ClientError::Unsupported { operation }) => {
+                        self.skip_unavailable_proof(&checkpoint_id, operation)?;
+                        verified = false;
+                    }
+                    // A proof that does not connect the two roots means the
+                    // registry presented a log that forked from the pinned one
+                    Err(
+                        e @ (api::ClientError::IncorrectConsistencyProof { .. }
+                        | api::ClientError::ConsistencyProof(_)),
+                    ) => {
+                        tracing::warn!(
+                            "checkpoint `{checkpoint_id}` failed consistency verification: {e}"
+                        );
+                        return Err(ClientError::LogForkDetected {
+                            id: self.url().to_string(),
+                            pinned: Hash::<Sha256>::of(pinned).into(),
+                            presented: checkpoint_id.clone(),
+                        });
+                    }
+                    Err(e) => return Err(e.into()),
+                }
+            }
+        }
+
         let mut operator = self.registry.load_operator().await?.unwrap_or_default();
 
+        // Trust the key that signed the checkpoint on first use of the registry;
+        // if the key is not in the operator log yet, it is pinned once the
+        // operator log is fetched
+        let mut registry_key = None;
+        if let (None, Some(key)) = (
+            &pinned_key,
+            operator.state.public_key(ts_checkpoint.key_id()),
+        ) {
+            proof::verify_checkpoint_signature(ts_checkpoint, key)?;
+            registry_key = Some(key.clone());
+        }
+
         // Map package identifiers to package logs that need to be updated
         let mut packages = packages
             .into_iter()
@@ -371,7 +3457,13 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
             .inspect(|(_, p)| tracing::info!("package `{id}` will be updated", id = p.id))
             .collect::<HashMap<_, _>>();
         if packages.is_empty() {
-            return Ok(());
+            return Ok(StagedUpdate {
+                ts_checkpoint: ts_checkpoint.clone(),
+                registry_key,
+                operator: None,
+                packages: Vec::new(),
+                new_records: HashMap::new(),
+            });
         }
 
         let mut last_known = packages
@@ -383,10 +3475,12 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
                 )
             })
             .collect::<HashMap<_, _>>();
+        let mut new_records = HashMap::<PackageId, usize>::new();
 
         loop {
+            self.check_cancelled()?;
             let response: FetchLogsResponse = self
-                .api
+                .api()?
                 .fetch_logs(FetchLogsRequest {
                     log_length: checkpoint.log_length,
                     operator: operator
@@ -403,7 +3497,6 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
                         packages.get(id).map(|p| p.id.clone())
                     })
                 })?;
-
             for record in response.operator {
                 let record: PublishedProtoEnvelope<operator::OperatorRecord> = record.try_into()?;
                 operator
@@ -428,6 +3521,7 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
                         }
                     })?;
                     package.head_registry_index = Some(record.registry_index);
+                    *new_records.entry(package.id.clone()).or_default() += 1;
                 }
 
                 // At this point, the package log should not be empty
@@ -448,6 +3542,142 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
             }
         }
 
+        // Only accept a checkpoint signed by a key the operator log authorizes,
+        // even if the key is pinned
+        let key = Self::authorized_checkpoint_key(ts_checkpoint, &operator.state)?;
+
+        if self.verify_proofs {
+            match self
+                .verify_inclusion(checkpoint, &operator, &packages)
+                .await
+            {
+                Err(ClientError::InclusionProofFailed {
+                    inner: api::ClientError::Unsupported { operation },
+                    ..
+                }) => {
+                    self.skip_unavailable_proof(&checkpoint_id, operation)?;
+                    verified = false;
+                }
+                res => res?,
+            }
+        } else {
+            tracing::warn!("skipping proof verification for checkpoint `{checkpoint_id}`");
+        }
+
+        // Nothing is stored if the update is cancelled before this point
+        self.check_cancelled()?;
+
+        if pinned_key.is_none() {
+            registry_key = Some(key.clone());
+        }
+
+        for package in packages.values_mut() {
+            package.checkpoint = Some(checkpoint.clone());
+            package.checkpoint_timestamp = Some(ts_checkpoint.as_ref().timestamp);
+            package.unverified = !verified;
+        }
+
+        Ok(StagedUpdate {
+            ts_checkpoint: ts_checkpoint.clone(),
+            registry_key,
+            operator: Some(operator),
+            packages: packages.into_values().map(|p| p.clone()).collect(),
+            new_records,
+        })
+    }
+
+    /// Stores a staged update in registry storage.
+    ///
+    /// The operator and package logs are stored before the checkpoint they
+    /// were verified against.
+    async fn commit_staged(&self, staged: &StagedUpdate) -> ClientResult<()> {
+        if let Some(key) = &staged.registry_key {
+            self.pin_registry_key(&staged.ts_checkpoint, key).await?;
+        }
+
+        let Some(operator) = &staged.operator else {
+            return Ok(());
+        };
+
+        self.registry.store_operator(operator.clone()).await?;
+        for package in &staged.packages {
+            self.registry.store_package(package).await?;
+        }
+
+        self.registry
+            .store_checkpoint(&staged.ts_checkpoint)
+            .await?;
+        Ok(())
+    }
+
+    /// Skips verification with a proof the registry does not serve.
+    ///
+    /// Returns [`ClientError::ProofServiceUnavailable`] unless the client
+    /// allows unverified package logs.
+    fn skip_unavailable_proof(
+        &self,
+        checkpoint_id: &AnyHash,
+        operation: String,
+    ) -> ClientResult<()> {
+        if !self.allow_unverified {
+            return Err(ClientError::ProofServiceUnavailable { operation });
+        }
+
+        tracing::warn!(
+            "the registry does not serve {operation}; checkpoint `{checkpoint_id}` is accepted unverified"
+        );
+        Ok(())
+    }
+
+    /// Pins the given registry key after verifying that it signed the given
+    /// checkpoint.
+    async fn pin_registry_key(
+        &self,
+        ts_checkpoint: &SerdeEnvelope<TimestampedCheckpoint>,
+        key: &PublicKey,
+    ) -> ClientResult<()> {
+        proof::verify_checkpoint_signature(ts_checkpoint, key)?;
+        tracing::info!(
+            "pinning registry key `{key_id}` on first use",
+            key_id = ts_checkpoint.key_id()
+        );
+        self.registry.store_registry_key(Some(key)).await?;
+        Ok(())
+    }
+
+    /// Gets the key that signed the given checkpoint from the given operator
+    /// log state.
+    ///
+    /// The key must be authorized by the operator log to commit checkpoints;
+    /// the signature is verified with the algorithm of the key.
+    fn authorized_checkpoint_key<'a>(
+        ts_checkpoint: &SerdeEnvelope<TimestampedCheckpoint>,
+        state: &'a operator::LogState,
+    ) -> ClientResult<&'a PublicKey> {
+        let key_id = ts_checkpoint.key_id();
+        let key = state
+            .public_key(key_id)
+            .filter(|_| state.key_has_permission(key_id, operator::Permission::Commit))
+            .ok_or_else(|| ClientError::UntrustedCheckpointKey {
+                key_id: key_id.clone(),
+            })?;
+
+        proof::verify_checkpoint_signature(ts_checkpoint, key)?;
+        Ok(key)
+    }
+
+    /// Verifies that the heads of the given logs are included in the given
+    /// checkpoint.
+    ///
+    /// As each log is a hash chain, proving inclusion of the head record
+    /// proves inclusion of every record in the log.
+    #[tracing::instrument(level = "debug", skip_all, fields(logs = field::Empty))]
+    async fn verify_inclusion(
+        &self,
+        checkpoint: &Checkpoint,
+        operator: &OperatorInfo,
+        packages: &HashMap<LogId, &mut PackageInfo>,
+    ) -> ClientResult<()> {
         // Prove inclusion for the current log heads
         let mut leaf_indices = Vec::with_capacity(packages.len() + 1 /* for operator */);
         let mut leafs = Vec::with_capacity(leaf_indices.len());
@@ -459,7 +3689,7 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
             });
         }
 
-        for (log_id, package) in &packages {
+        for (log_id, package) in packages {
             if let Some(index) = package.head_registry_index {
                 leaf_indices.push(index);
                 leafs.push(LogLeaf {
@@ -469,8 +3699,9 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
             }
         }
 
+        tracing::Span::current().record("logs", leafs.len());
         if !leafs.is_empty() {
-            self.api
+            self.api()?
                 .prove_inclusion(
                     InclusionRequest {
                         log_length: checkpoint.log_length,
@@ -479,31 +3710,13 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
                     checkpoint,
                     &leafs,
                 )
-                .await?;
-        }
-
-        if let Some(from) = self.registry.load_checkpoint().await? {
-            self.api
-                .prove_log_consistency(
-                    ConsistencyRequest {
-                        from: from.as_ref().checkpoint.log_length,
-                        to: ts_checkpoint.as_ref().checkpoint.log_length,
-                    },
-                    Cow::Borrowed(&from.as_ref().checkpoint.log_root),
-                    Cow::Borrowed(&ts_checkpoint.as_ref().checkpoint.log_root),
-                )
-                .await?;
-        }
-
-        self.registry.store_operator(operator).await?;
-
-        for package in packages.values_mut() {
-            package.checkpoint = Some(checkpoint.clone());
-            self.registry.store_package(package).await?;
+                .await
+                .map_err(|inner| ClientError::InclusionProofFailed {
+                    id: Hash::<Sha256>::of(checkpoint).into(),
+                    inner,
+                })?;
         }
 
-        self.registry.store_checkpoint(ts_checkpoint).await?;
-
         Ok(())
     }
 
@@ -513,65 +3726,585 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
                 tracing::info!("log for package `{id}` already exists in storage");
                 Ok(info)
             }
+            None if self.offline => Err(ClientError::OfflineDataMissing { id: id.to_string() }),
             None => {
                 let mut info = PackageInfo::new(id.clone());
-                self.update_checkpoint(&self.api.latest_checkpoint().await?, [&mut info])
+                self.update_checkpoint(&self.api()?.latest_checkpoint().await?, [&mut info])
                     .await?;
 
-                Ok(info)
+                Ok(info)
+            }
+        }
+    }
+
+    /// Gets the checkpoint the given package was resolved against.
+    ///
+    /// Packages stored without the timestamp of their checkpoint fall back to
+    /// the stored registry checkpoint if it is the same checkpoint.
+    async fn resolved_checkpoint(&self, info: &PackageInfo) -> ClientResult<TimestampedCheckpoint> {
+        if let Some(checkpoint) = info.timestamped_checkpoint() {
+            return Ok(checkpoint);
+        }
+
+        match self.registry.load_checkpoint().await? {
+            Some(ts_checkpoint) if info.checkpoint.as_ref() == Some(&ts_checkpoint.as_ref().checkpoint) => {
+                Ok(ts_checkpoint.as_ref().clone())
+            }
+            _ => Err(anyhow!(
+                "the checkpoint of package `{id}` is not known; update the package to resolve it again",
+                id = info.id
+            )
+            .into()),
+        }
+    }
+
+    async fn get_package_record(
+        &self,
+        package: &PackageId,
+        log_id: &LogId,
+        record_id: &RecordId,
+    ) -> ClientResult<PackageRecord> {
+        let record = self
+            .api()?
+            .get_package_record(log_id, record_id)
+            .await
+            .map_err(|e| {
+                ClientError::translate_log_not_found(e, |id| {
+                    if id == log_id {
+                        Some(package.clone())
+                    } else {
+                        None
+                    }
+                })
+            })?;
+        Ok(record)
+    }
+
+    /// Downloads the content of every released version of a package into
+    /// client storage.
+    ///
+    /// If the requested package log is not present in client storage, it
+    /// will be fetched from the registry first.
+    ///
+    /// Contents are downloaded concurrently, up to the client's maximum
+    /// number of concurrent downloads; content shared by multiple versions is
+    /// only downloaded once. If some downloads fail,
+    /// [`ClientError::PartialDownload`] is returned with the versions that
+    /// were downloaded.
+    ///
+    /// Returns a download for each version in ascending version order.
+    ///
+    /// Yanked versions are not downloaded; use
+    /// [`Client::download_all_versions_including_yanked`] to also download
+    /// them.
+    pub async fn download_all_versions(
+        &self,
+        id: &PackageId,
+    ) -> ClientResult<Vec<PackageDownload>> {
+        self.routed(id).download_all_versions_with(id, false).await
+    }
+
+    /// Downloads the content of every version of a package into client
+    /// storage, including yanked versions.
+    ///
+    /// Versions yanked before their content was retained in client storage
+    /// are not downloaded.
+    ///
+    /// This is otherwise the same as [`Client::download_all_versions`].
+    pub async fn download_all_versions_including_yanked(
+        &self,
+        id: &PackageId,
+    ) -> ClientResult<Vec<PackageDownload>> {
+        self.routed(id).download_all_versions_with(id, true).await
+    }
+
+    #[tracing::instrument(name = "download_all_versions", skip_all, fields(%id))]
+    async fn download_all_versions_with(
+        &self,
+        id: &PackageId,
+        include_yanked: bool,
+    ) -> ClientResult<Vec<PackageDownload>> {
+        let info = self.fetch_package(id).await?;
+        let log_id = LogId::package_log::<Sha256>(&info.id);
+        let checkpoint = self.resolved_checkpoint(&info).await?;
+
+        let mut releases = info
+            .state
+            .releases()
+            .filter(|r| include_yanked || !r.yanked())
+            .filter_map(|r| Some((r, r.released_content()?)))
+            .collect::<Vec<_>>();
+        releases.sort_by(|(a, _), (b, _)| a.version.cmp(&b.version));
+        tracing::info!(
+            "downloading {count} version(s) of package `{id}`",
+            count = releases.len()
+        );
+
+        // Unlike `download_contents`, a failed download does not stop the
+        // others so that the successful downloads can be reported
+        let mut seen = HashSet::new();
+        let mut results =
+            futures_util::stream::iter(releases.iter().filter(|(_, digest)| seen.insert(*digest)))
+                .map(|&(release, digest)| {
+                    let log_id = &log_id;
+                    async move {
+                        let result = self
+                            .download_content(log_id, &release.record_id, digest)
+                            .await;
+                        (digest.clone(), result)
+                    }
+                })
+                .buffer_unordered(self.max_concurrent_downloads)
+                .collect::<HashMap<_, _>>()
+                .await;
+
+        let mut downloaded = Vec::with_capacity(releases.len());
+        let mut failed = Vec::new();
+        let mut error = None;
+        for (release, digest) in releases {
+            match &results[digest] {
+                Ok(path) => {
+                    warn_if_deprecated(id, release);
+                    downloaded.push(PackageDownload {
+                        version: release.version.clone(),
+                        digest: digest.clone(),
+                        path: path.clone(),
+                        deprecation: release.deprecation.clone(),
+                        checkpoint: checkpoint.clone(),
+                    });
+                }
+                Err(_) => {
+                    failed.push(release.version.clone());
+                    error.get_or_insert_with(|| digest.clone());
+                }
+            }
+        }
+
+        match error {
+            None => Ok(downloaded),
+            Some(digest) => {
+                let source = results.remove(&digest).unwrap().unwrap_err();
+                if downloaded.is_empty() {
+                    return Err(source);
+                }
+
+                Err(ClientError::PartialDownload {
+                    id: id.clone(),
+                    downloaded,
+                    failed,
+                    source: Box::new(source),
+                })
+            }
+        }
+    }
+
+    /// Downloads the given contents into client storage concurrently.
+    ///
+    /// Returns the paths within client storage of the downloaded contents.
+    async fn download_contents(
+        &self,
+        contents: impl IntoIterator<Item = (LogId, RecordId, AnyHash)>,
+    ) -> ClientResult<HashMap<AnyHash, PathBuf>> {
+        let mut seen = HashSet::new();
+        let result = futures_util::stream::iter(
+            contents
+                .into_iter()
+                .filter(|(_, _, digest)| seen.insert(digest.clone())),
+        )
+        .map(|(log_id, record_id, digest)| async move {
+            let path = self.download_content(&log_id, &record_id, &digest).await?;
+            Ok((digest, path))
+        })
+        .buffer_unordered(self.max_concurrent_downloads)
+        .try_collect()
+        .await;
+
+        // Concurrent downloads are dropped when one is cancelled; discard
+        // whatever content they kept
+        if let Err(ClientError::Cancelled) = &result {
+            for digest in &seen {
+                self.content.discard_download(digest).await?;
+            }
+        }
+
+        result
+    }
+
+    #[tracing::instrument(level = "debug", skip_all, fields(%digest, bytes = field::Empty))]
+    async fn download_content(
+        &self,
+        log_id: &LogId,
+        record_id: &RecordId,
+        digest: &AnyHash,
+    ) -> Result<PathBuf, ClientError> {
+        let location = self.content.content_location(digest);
+        self.metrics.record_cache_lookup(location.is_some());
+        match location {
+            Some(path) => {
+                tracing::info!("content for digest `{digest}` already exists in storage");
+                Ok(path)
+            }
+            None if self.offline => Err(ClientError::OfflineDataMissing {
+                id: digest.to_string(),
+            }),
+            None => {
+                let mut retry_sources: Option<Vec<String>> = None;
+                let mut identity = false;
+                let mut attempt = 0;
+                'attempts: loop {
+                    let api = self.api()?;
+                    let sources = match retry_sources.take() {
+                        Some(sources) => sources,
+                        None => api.content_sources(log_id, record_id, digest).await?,
+                    };
+
+                    // Fail over to the next source when content fails verification
+                    let mut failure = None;
+                    let mut encoded = false;
+                    for source in &sources {
+                        // Resume from any bytes kept from an interrupted download
+                        let partial = self.content.partial_download_len(digest).await?;
+                        match self
+                            .download_from(api, source, digest, partial, identity)
+                            .await
+                        {
+                            Ok(()) => break 'attempts,
+                            Err(ClientError::Api(api::ClientError::AllSourcesFailed(_))) => {
+                                continue
+                            }
+                            Err(e @ ClientError::ContentDigestMismatch { .. }) => {
+                                tracing::debug!("content from `{source}` failed verification: {e}");
+                                encoded |= partial == 0 && api.compression();
+                                failure = Some((source.clone(), e));
+                            }
+                            Err(e) => return Err(e),
+                        }
+                    }
+
+                    let Some((source, e)) = failure else {
+                        return Err(ClientError::Api(api::ClientError::AllSourcesFailed(
+                            digest.clone(),
+                        )));
+                    };
+
+                    // An intermediary may have mangled the encoded content;
+                    // retry once without any encoding
+                    if self.retry_identity_encoding && !identity && encoded {
+                        tracing::warn!(
+                            "content from `{source}` failed verification: {e}; retrying with identity encoding"
+                        );
+                        identity = true;
+                        retry_sources = Some(sources);
+                        continue;
+                    }
+
+                    attempt += 1;
+                    match self
+                        .verification_failure
+                        .on_verification_failure(digest, &source, attempt)
+                    {
+                        RetryDecision::GiveUp => return Err(e),
+                        RetryDecision::Retry => {
+                            tracing::warn!(
+                                "content from `{source}` failed verification: {e}; retrying"
+                            );
+                        }
+                        RetryDecision::RetryFrom(url) => {
+                            tracing::warn!(
+                                "content from `{source}` failed verification: {e}; retrying from `{url}`"
+                            );
+                            retry_sources = Some(vec![url]);
+                        }
+                    }
+                }
+
+                self.content
+                    .content_location(digest)
+                    .ok_or_else(|| ClientError::ContentNotFound {
+                        digest: digest.clone(),
+                    })
+            }
+        }
+    }
+
+    /// Downloads content from the given source URL into client storage.
+    ///
+    /// Returns [`api::ClientError::AllSourcesFailed`] if the source responds
+    /// with an error.
+    async fn download_from(
+        &self,
+        api: &api::Client,
+        source: &str,
+        digest: &AnyHash,
+        partial: u64,
+        identity: bool,
+    ) -> Result<(), ClientError> {
+        let (offset, total, stream) = api
+            .download_content_from(source, digest, partial, identity)
+            .await?;
+
+        let received = Arc::new(AtomicU64::new(offset));
+        let progress = self.progress.clone();
+        let result = self
+            .content
+            .store_download(
+                digest,
+                offset,
+                Box::pin(cancellable(stream, self.cancel.clone()).inspect_ok({
+                    let received = received.clone();
+                    move |bytes| {
+                        let len = bytes.len() as u64;
+                        progress
+                            .on_progress(received.fetch_add(len, Ordering::Relaxed) + len, total);
+                    }
+                })),
+            )
+            .await
+            .map_err(|e| match e.downcast::<ClientError>() {
+                Ok(e) => e,
+                Err(e) => match e.downcast::<api::ClientError>() {
+                    Ok(e) => e.into(),
+                    Err(e) => ClientError::Other(e),
+                },
+            });
+
+        let bytes = received.load(Ordering::Relaxed) - offset;
+        self.metrics.record_download(bytes);
+        if let Err(ClientError::Cancelled) = &result {
+            // Don't keep partial content of a cancelled download
+            tracing::info!("download of content `{digest}` was cancelled");
+            self.content.discard_download(digest).await?;
+        }
+
+        result?;
+        tracing::Span::current().record("bytes", bytes);
+        Ok(())
+    }
+}
+
+/// Represents the outcome of publishing a batch of records.
+///
+/// See [`Client::publish_batch`].
+#[derive(Debug, Default)]
+pub struct PublishBatch {
+    /// The package and record identifiers of the published records, in the
+    /// order they were published.
+    pub published: Vec<(PackageId, RecordId)>,
+    /// The publish information that failed to publish along with the error.
+    ///
+    /// This is `None` if every record in the batch was published.
+    pub failed: Option<(PublishInfo, ClientError)>,
+    /// The publish information that was not submitted due to a failure.
+    pub remaining: Vec<PublishInfo>,
+}
+
+impl PublishBatch {
+    /// Determines if every record in the batch was published.
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_none()
+    }
+}
+
+/// Represents the outcome of prefetching packages.
+///
+/// See [`Client::prefetch`].
+#[derive(Debug, Default)]
+pub struct PrefetchReport {
+    /// The outcome of each prefetched package, in the order requested.
+    ///
+    /// The download is `None` if content was not requested or if the package
+    /// has no version that has not been yanked.
+    pub packages: Vec<(PackageId, ClientResult<Option<PackageDownload>>)>,
+}
+
+impl PrefetchReport {
+    /// Determines if every package was prefetched.
+    pub fn is_complete(&self) -> bool {
+        self.packages.iter().all(|(_, result)| result.is_ok())
+    }
+
+    /// Gets the packages that failed to prefetch along with the error.
+    pub fn failed(&self) -> impl Iterator<Item = (&PackageId, &ClientError)> {
+        self.packages
+            .iter()
+            .filter_map(|(id, result)| result.as_ref().err().map(|e| (id, e)))
+    }
+}
+
+/// Represents the outcome of repairing content in client storage.
+///
+/// See [`Client::repair`].
+#[derive(Debug, Default)]
+pub struct RepairReport {
+    /// The digests of the content that was downloaded again, in the order
+    /// it was repaired.
+    pub repaired: Vec<AnyHash>,
+    /// The content that could not be repaired along with the error.
+    pub failed: Vec<(AnyHash, ClientError)>,
+}
+
+impl RepairReport {
+    /// Determines if all damaged or missing content was repaired.
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// Represents the records of a package log fetched since a point in time.
+///
+/// See [`Client::fetch_logs_since`].
+#[derive(Debug, Clone)]
+pub struct LogsSince {
+    /// The records first included in a checkpoint timestamped at or after
+    /// the requested time, in log order.
+    pub records: Vec<PublishedProtoEnvelope<package::PackageRecord>>,
+    /// The registry checkpoint the package log was fetched at.
+    pub checkpoint: SerdeEnvelope<TimestampedCheckpoint>,
+}
+
+/// Wraps a content stream so that it fails with [`ClientError::Cancelled`]
+/// once the given token is cancelled.
+///
+/// A chunk already received is passed through before the cancellation is
+/// observed, so consumers never see a partially received chunk.
+fn cancellable<S>(stream: S, cancel: CancellationToken) -> impl Stream<Item = Result<Bytes>>
+where
+    S: Stream<Item = Result<Bytes>>,
+{
+    futures_util::stream::unfold(
+        (Box::pin(stream), cancel, false),
+        |(mut stream, cancel, done)| async move {
+            if done {
+                return None;
+            }
+
+            tokio::select! {
+                biased;
+                _ = cancel.cancelled() => {
+                    Some((Err(anyhow!(ClientError::Cancelled)), (stream, cancel, true)))
+                }
+                item = stream.next() => item.map(|item| (item, (stream, cancel, false))),
             }
-        }
+        },
+    )
+}
+
+/// Represents whether a package log in client storage is up to date with the
+/// registry.
+///
+/// See [`Client::sync_status`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncStatus {
+    /// The package log is up to date with the latest registry checkpoint.
+    UpToDate,
+    /// The latest registry checkpoint includes records of the package that
+    /// are not in client storage.
+    Behind {
+        /// The number of records of the package in the latest registry
+        /// checkpoint after the head of the package log in client storage.
+        records_behind: usize,
+    },
+    /// The head of the package log in client storage is not included in the
+    /// latest registry checkpoint.
+    ///
+    /// This is an anomaly, such as the registry having rolled back its log.
+    LocalAhead,
+}
+
+/// Represents the outcome of synchronizing package logs.
+///
+/// See [`Client::sync`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    /// The number of new records each synchronized package gained.
+    pub packages: HashMap<PackageId, usize>,
+}
+
+impl SyncReport {
+    /// Gets the number of new records the given package gained.
+    pub fn new_records(&self, id: &PackageId) -> usize {
+        self.packages.get(id).copied().unwrap_or_default()
     }
 
-    async fn get_package_record(
-        &self,
-        package: &PackageId,
-        log_id: &LogId,
-        record_id: &RecordId,
-    ) -> ClientResult<PackageRecord> {
-        let record = self
-            .api
-            .get_package_record(log_id, record_id)
-            .await
-            .map_err(|e| {
-                ClientError::translate_log_not_found(e, |id| {
-                    if id == log_id {
-                        Some(package.clone())
-                    } else {
-                        None
-                    }
-                })
-            })?;
-        Ok(record)
+    /// Determines if every synchronized package was already up to date.
+    pub fn is_current(&self) -> bool {
+        self.packages.values().all(|count| *count == 0)
     }
+}
 
-    async fn download_content(
-        &self,
-        log_id: &LogId,
-        record_id: &RecordId,
-        digest: &AnyHash,
-    ) -> Result<PathBuf, ClientError> {
-        match self.content.content_location(digest) {
-            Some(path) => {
-                tracing::info!("content for digest `{digest}` already exists in storage");
-                Ok(path)
-            }
-            None => {
-                self.content
-                    .store_content(
-                        Box::pin(self.api.download_content(log_id, record_id, digest).await?),
-                        Some(digest),
-                    )
-                    .await?;
+/// Represents package logs fetched and verified at a checkpoint that are yet
+/// to be stored.
+struct StagedUpdate {
+    /// The checkpoint the logs were verified against.
+    ts_checkpoint: SerdeEnvelope<TimestampedCheckpoint>,
+    /// The registry key to pin on first use of the registry.
+    registry_key: Option<PublicKey>,
+    /// The updated operator log; `None` if no package log was updated.
+    operator: Option<OperatorInfo>,
+    /// The updated package logs.
+    packages: Vec<PackageInfo>,
+    /// The number of new records each updated package gained.
+    new_records: HashMap<PackageId, usize>,
+}
 
-                self.content
-                    .content_location(digest)
-                    .ok_or_else(|| ClientError::ContentNotFound {
-                        digest: digest.clone(),
-                    })
-            }
-        }
-    }
+/// Represents a receipt of a published record.
+///
+/// See [`Client::publish_with_receipt`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PublishReceipt {
+    /// The id of the published package.
+    pub id: PackageId,
+    /// The id of the published record.
+    pub record_id: RecordId,
+    /// The index of the record in the registry log.
+    pub registry_index: RegistryIndex,
+    /// The versions released by the record.
+    pub versions: Vec<Version>,
+    /// The digests of the content released by the record.
+    pub content: Vec<AnyHash>,
+    /// The checkpoint the record was included under.
+    pub checkpoint: Checkpoint,
+}
+
+/// Represents the outcome of ensuring content is in client storage.
+///
+/// See [`Client::ensure_content`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EnsureContentReport {
+    /// The entries whose content was already in client storage, in the order
+    /// given.
+    pub present: Vec<(PackageId, Version, AnyHash)>,
+    /// The entries whose content was downloaded, in the order given.
+    pub downloaded: Vec<(PackageId, Version, AnyHash)>,
+}
+
+/// Represents the content shared by packages in client storage.
+///
+/// See [`Client::content_sharing_report`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContentSharingReport {
+    /// The digests of content referenced by more than one package, in order,
+    /// with the referencing packages in order of their ids.
+    pub shared: Vec<(AnyHash, Vec<PackageId>)>,
+    /// The number of bytes saved by storing shared content once.
+    pub bytes_saved: u64,
+}
+
+/// Represents a package in client storage.
+///
+/// See [`Client::local_inventory`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalPackage {
+    /// The id of the package.
+    pub id: PackageId,
+    /// The versions released in the package log, including yanked versions.
+    pub versions: Vec<Version>,
+    /// The number of released content digests present in content storage.
+    pub content: usize,
+    /// Whether the package log is at the last checkpoint pinned by the
+    /// client.
+    pub up_to_date: bool,
 }
 
 /// A Warg registry client that uses the local file system to store
@@ -598,12 +4331,140 @@ impl FileSystemClient {
     pub fn try_new_with_config(
         url: Option<&str>,
         config: &Config,
+    ) -> Result<StorageLockResult<Self>, ClientError> {
+        let paths = config.storage_paths_for_url(url)?;
+        let auth_token = config.resolve_auth_token(&paths.registry_url)?;
+        Self::try_new_with_paths(paths, auth_token, config)
+    }
+
+    /// Attempts to create a client for the given registry profile.
+    ///
+    /// If the profile name is `None`, the default profile is used.
+    ///
+    /// An error is returned if the profile does not exist.
+    ///
+    /// If a lock cannot be acquired for a storage directory, then
+    /// `NewClientResult::Blocked` is returned with the path to the
+    /// directory that could not be locked.
+    pub fn try_new_with_profile(
+        profile: Option<&str>,
+        config: &Config,
+    ) -> Result<StorageLockResult<Self>, ClientError> {
+        let profile = config.resolve_profile(profile)?;
+        let paths = config.storage_paths_for_url(Some(&profile.url))?;
+        Self::try_new_with_paths(paths, profile.resolve_auth_token()?, config)
+    }
+
+    /// Creates a client for the given registry profile.
+    ///
+    /// If the profile name is `None`, the default profile is used.
+    ///
+    /// An error is returned if the profile does not exist.
+    ///
+    /// This method blocks if storage locks cannot be acquired.
+    pub fn new_with_profile(profile: Option<&str>, config: &Config) -> Result<Self, ClientError> {
+        let profile = config.resolve_profile(profile)?;
+        let paths = config.storage_paths_for_url(Some(&profile.url))?;
+        Self::new_with_paths(paths, profile.resolve_auth_token()?, config)
+    }
+
+    /// Creates a client for the given registry URL.
+    ///
+    /// If the URL is `None`, the default URL is used; if there is no default
+    /// URL, an error is returned.
+    ///
+    /// This method blocks if storage locks cannot be acquired.
+    pub fn new_with_config(url: Option<&str>, config: &Config) -> Result<Self, ClientError> {
+        let paths = config.storage_paths_for_url(url)?;
+        let auth_token = config.resolve_auth_token(&paths.registry_url)?;
+        Self::new_with_paths(paths, auth_token, config)
+    }
+
+    /// Creates a client for the given registry URL, waiting at most the given
+    /// timeout for the storage locks to be acquired.
+    ///
+    /// If the URL is `None`, the default URL is used; if there is no default
+    /// URL, an error is returned.
+    ///
+    /// If a storage directory cannot be locked before the timeout elapses,
+    /// [`ClientError::StorageLockTimeout`] is returned. The locks are released
+    /// when the client is dropped.
+    pub fn lock_storage_timeout(
+        url: Option<&str>,
+        config: &Config,
+        timeout: Duration,
+    ) -> Result<Self, ClientError> {
+        let StoragePaths {
+            registry_url,
+            registries_dir,
+            content_dir,
+        } = config.storage_paths_for_url(url)?;
+        let auth_token = config.resolve_auth_token(&registry_url)?;
+
+        let deadline = Instant::now() + timeout;
+        let remaining = || deadline.saturating_duration_since(Instant::now());
+        let packages = FileSystemRegistryStorage::lock_timeout(&registries_dir, remaining())?
+            .ok_or(ClientError::StorageLockTimeout {
+                path: registries_dir,
+                timeout,
+            })?;
+        let content = FileSystemContentStorage::lock_timeout(&content_dir, remaining())?.ok_or(
+            ClientError::StorageLockTimeout {
+                path: content_dir,
+                timeout,
+            },
+        )?;
+
+        let builder = Self::builder(
+            registry_url.clone().into_url(),
+            packages,
+            config.apply_content_storage(content)?,
+        )?;
+        match Self::with_namespace_routes(builder, &registry_url, config, |dir| {
+            FileSystemRegistryStorage::lock_timeout(dir, remaining())
+        })? {
+            StorageLockResult::Acquired(builder) => config.apply(builder, auth_token),
+            StorageLockResult::NotAcquired(path) => {
+                Err(ClientError::StorageLockTimeout { path, timeout })
+            }
+        }
+    }
+
+    /// Routes the namespaces in the configuration's namespace map to their
+    /// registries, locking the registry storage of each with `lock`.
+    fn with_namespace_routes(
+        mut builder: ClientBuilder<FileSystemRegistryStorage, FileSystemContentStorage>,
+        url: &RegistryUrl,
+        config: &Config,
+        mut lock: impl FnMut(&Path) -> Result<Option<FileSystemRegistryStorage>>,
+    ) -> Result<
+        StorageLockResult<ClientBuilder<FileSystemRegistryStorage, FileSystemContentStorage>>,
+        ClientError,
+    > {
+        for (url, namespaces) in config.namespace_routes(url)? {
+            let StoragePaths { registries_dir, .. } =
+                config.storage_paths_for_url(Some(&url.to_string()))?;
+            let Some(registry) = lock(&registries_dir)? else {
+                return Ok(StorageLockResult::NotAcquired(registries_dir));
+            };
+
+            let auth_token = config.resolve_auth_token(&url)?;
+            builder = builder.with_route(url, registry, auth_token, namespaces);
+        }
+
+        Ok(StorageLockResult::Acquired(builder))
+    }
+
+    fn try_new_with_paths(
+        paths: StoragePaths,
+        auth_token: Option<String>,
+        config: &Config,
     ) -> Result<StorageLockResult<Self>, ClientError> {
         let StoragePaths {
             registry_url: url,
             registries_dir,
             content_dir,
-        } = config.storage_paths_for_url(url)?;
+        } = paths;
 
         let (packages, content) = match (
             FileSystemRegistryStorage::try_lock(registries_dir.clone())?,
@@ -614,30 +4475,70 @@ impl FileSystemClient {
             (_, None) => return Ok(StorageLockResult::NotAcquired(content_dir)),
         };
 
-        Ok(StorageLockResult::Acquired(Self::new(
-            url.into_url(),
+        let builder = Self::builder(
+            url.clone().into_url(),
             packages,
-            content,
-        )?))
+            config.apply_content_storage(content)?,
+        )?;
+        match Self::with_namespace_routes(builder, &url, config, |dir| {
+            FileSystemRegistryStorage::try_lock(dir)
+        })? {
+            StorageLockResult::Acquired(builder) => Ok(StorageLockResult::Acquired(
+                config.apply(builder, auth_token)?,
+            )),
+            StorageLockResult::NotAcquired(path) => Ok(StorageLockResult::NotAcquired(path)),
+        }
     }
 
-    /// Creates a client for the given registry URL.
-    ///
-    /// If the URL is `None`, the default URL is used; if there is no default
-    /// URL, an error is returned.
-    ///
-    /// This method blocks if storage locks cannot be acquired.
-    pub fn new_with_config(url: Option<&str>, config: &Config) -> Result<Self, ClientError> {
+    fn new_with_paths(
+        paths: StoragePaths,
+        auth_token: Option<String>,
+        config: &Config,
+    ) -> Result<Self, ClientError> {
         let StoragePaths {
             registry_url,
             registries_dir,
             content_dir,
-        } = config.storage_paths_for_url(url)?;
-        Self::new(
-            registry_url.into_url(),
+        } = paths;
+        let builder = Self::builder(
+            registry_url.clone().into_url(),
             FileSystemRegistryStorage::lock(registries_dir)?,
-            FileSystemContentStorage::lock(content_dir)?,
-        )
+            config.apply_content_storage(FileSystemContentStorage::lock(content_dir)?)?,
+        )?;
+        match Self::with_namespace_routes(builder, &registry_url, config, |dir| {
+            FileSystemRegistryStorage::lock(dir).map(Some)
+        })? {
+            StorageLockResult::Acquired(builder) => config.apply(builder, auth_token),
+            StorageLockResult::NotAcquired(_) => unreachable!("blocking locks are always acquired"),
+        }
+    }
+}
+
+/// Represents the result of verifying a file against a package version.
+///
+/// See [`Client::verify_file`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileVerification {
+    /// The file has the content the version was released with.
+    Matches,
+    /// The file differs from the content the version was released with.
+    Differs {
+        /// The digest of the content the version was released with.
+        expected: AnyHash,
+        /// The digest of the file.
+        actual: AnyHash,
+    },
+    /// The package has no release with the version.
+    VersionNotFound,
+    /// The version was yanked before its content was retained in client
+    /// storage.
+    ContentUnknown,
+}
+
+impl FileVerification {
+    /// Determines if the file has the content the version was released with.
+    pub fn is_match(&self) -> bool {
+        matches!(self, Self::Matches)
     }
 }
 
@@ -650,6 +4551,76 @@ pub struct PackageDownload {
     pub digest: AnyHash,
     /// The path to the downloaded package contents.
     pub path: PathBuf,
+    /// The deprecation of the downloaded package version.
+    ///
+    /// Deprecated versions are still downloaded; this is `None` if the
+    /// version has not been deprecated.
+    pub deprecation: Option<package::Deprecation>,
+    /// The checkpoint of the registry the package version was resolved
+    /// against.
+    ///
+    /// Recording the checkpoint allows auditing which state of the registry
+    /// produced the downloaded contents.
+    pub checkpoint: TimestampedCheckpoint,
+}
+
+/// Warns if the given release of a package has been deprecated.
+fn warn_if_deprecated(id: &PackageId, release: &package::Release) {
+    if let Some(deprecation) = &release.deprecation {
+        match &deprecation.superseded_by {
+            Some(replacement) => tracing::warn!(
+                "version {version} of package `{id}` is deprecated in favor of version {replacement}: {message}",
+                version = release.version,
+                message = deprecation.message
+            ),
+            None => tracing::warn!(
+                "version {version} of package `{id}` is deprecated: {message}",
+                version = release.version,
+                message = deprecation.message
+            ),
+        }
+    }
+}
+
+/// Formats the given digests as a comma-separated list for an error message.
+/// Converts an error from a proof request, mapping a proof the registry does
+/// not serve to [`ClientError::ProofServiceUnavailable`].
+fn proof_service_error(e: api::ClientError) -> ClientError {
+    match e {
+        api::ClientError::Unsupported { operation } => {
+            ClientError::ProofServiceUnavailable { operation }
+        }
+        e => e.into(),
+    }
+}
+
+fn display_digests(digests: &[AnyHash]) -> String {
+    digests
+        .iter()
+        .map(|digest| format!("`{digest}`"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Formats the error message of [`ClientError::PublishConflict`].
+fn display_conflict(id: &PackageId, expected: &RecordId, actual: Option<&RecordId>) -> String {
+    match actual {
+        Some(actual) => format!(
+            "the log of package `{id}` has head `{actual}` but a head of `{expected}` was expected"
+        ),
+        None => format!(
+            "the log of package `{id}` has no records but a head of `{expected}` was expected"
+        ),
+    }
+}
+
+/// Formats the failed versions of [`ClientError::PartialDownload`].
+fn display_versions(versions: &[Version]) -> String {
+    versions
+        .iter()
+        .map(Version::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 /// Represents an error returned by Warg registry clients.
@@ -659,6 +4630,122 @@ pub enum ClientError {
     #[error("no default registry server URL is configured")]
     NoDefaultUrl,
 
+    /// The registry checkpoint is older than the last-seen checkpoint in
+    /// client storage.
+    #[error("the registry checkpoint with log length {found} is older than the last-seen checkpoint with log length {pinned}")]
+    CheckpointRollback {
+        /// The log length of the last-seen checkpoint.
+        pinned: RegistryLen,
+        /// The log length of the checkpoint returned by the registry.
+        found: RegistryLen,
+    },
+
+    /// The registry checkpoint is more recent than the minimum age of
+    /// checkpoints the client accepts.
+    ///
+    /// The checkpoint may not yet have been witnessed by other clients; a
+    /// later attempt may find an older checkpoint acceptable.
+    #[error("the registry checkpoint timestamped {timestamp} is more recent than the minimum checkpoint age of {min_age:?}")]
+    CheckpointTooRecent {
+        /// The timestamp of the checkpoint, in seconds since the Unix epoch.
+        timestamp: u64,
+        /// The minimum age of checkpoints the client accepts.
+        min_age: Duration,
+    },
+
+    /// The registry checkpoint is not consistent with the last-seen
+    /// checkpoint in client storage, meaning the registry presented a forked
+    /// log.
+    ///
+    /// The last-seen checkpoint is kept and client storage is not updated.
+    #[error("the log of registry `{id}` has forked: checkpoint `{presented}` is not consistent with the last-seen checkpoint `{pinned}`")]
+    LogForkDetected {
+        /// The URL of the registry that presented the checkpoint.
+        id: String,
+        /// The identifier of the last-seen checkpoint.
+        pinned: AnyHash,
+        /// The identifier of the inconsistent checkpoint presented by the
+        /// registry.
+        presented: AnyHash,
+    },
+
+    /// The registry checkpoint is signed by a key other than the pinned
+    /// registry key.
+    ///
+    /// The new key must be trusted with [`Client::trust_registry_key`].
+    #[error("the registry checkpoint is signed by key `{found}` but key `{pinned}` is pinned for the registry")]
+    RegistryKeyChanged {
+        /// The identifier of the pinned registry key.
+        pinned: KeyID,
+        /// The identifier of the key that signed the checkpoint.
+        found: KeyID,
+    },
+
+    /// The registry checkpoint is signed by a key that the operator log does
+    /// not authorize to commit checkpoints.
+    #[error("the registry checkpoint is signed by key `{key_id}` which the operator log does not authorize")]
+    UntrustedCheckpointKey {
+        /// The identifier of the key that signed the checkpoint.
+        key_id: KeyID,
+    },
+
+    /// The signature of the registry checkpoint is invalid.
+    #[error("the registry checkpoint has an invalid signature for key `{key_id}`")]
+    InvalidCheckpointSignature {
+        /// The identifier of the key that signed the checkpoint.
+        key_id: KeyID,
+    },
+
+    /// The records fetched from the registry could not be proven to be
+    /// included in the registry checkpoint.
+    #[error("failed to prove inclusion of fetched records in checkpoint `{id}`: {inner}")]
+    InclusionProofFailed {
+        /// The identifier of the checkpoint.
+        id: AnyHash,
+        /// The proof error.
+        inner: api::ClientError,
+    },
+
+    /// A root of the registry checkpoint differs from the root recomputed
+    /// from the package log in client storage, meaning either the stored
+    /// package log or the registry's checkpoint has been tampered with.
+    #[error("the registry checkpoint root `{claimed}` does not match the root `{computed}` computed from the log of package `{id}`")]
+    CheckpointMismatch {
+        /// The package whose log the root was computed from.
+        id: PackageId,
+        /// The root claimed by the registry checkpoint.
+        claimed: AnyHash,
+        /// The root computed from the package log.
+        computed: AnyHash,
+    },
+
+    /// The registry does not serve the proofs needed to verify fetched
+    /// records and the client does not allow unverified package logs.
+    #[error("the registry does not serve {operation} needed to verify fetched records")]
+    ProofServiceUnavailable {
+        /// A description of the proofs the registry does not serve.
+        operation: String,
+    },
+
+    /// The operation requires the registry but the client is offline.
+    #[error("the operation requires network access but the client is in offline mode")]
+    Offline,
+
+    /// A package log or content is not present in client storage and the
+    /// client is offline.
+    #[error("`{id}` is not present in client storage and the client is in offline mode")]
+    OfflineDataMissing {
+        /// The identifier of the missing package log or content digest.
+        id: String,
+    },
+
+    /// The registry profile does not exist in the configuration.
+    #[error("registry profile `{name}` does not exist in the configuration")]
+    ProfileDoesNotExist {
+        /// The name of the missing profile.
+        name: String,
+    },
+
     /// The operator failed validation.
     #[error("operator failed validation: {inner}")]
     OperatorValidationFailed {
@@ -707,6 +4794,33 @@ pub enum ClientError {
         id: PackageId,
     },
 
+    /// No version of the package satisfies a version requirement.
+    #[error("no version of package `{id}` satisfies requirement `{requirement}`")]
+    PackageVersionRequirementDoesNotExist {
+        /// The version requirement that was not satisfied.
+        requirement: VersionReq,
+        /// The identifier of the package.
+        id: PackageId,
+    },
+
+    /// The record does not exist in the package log.
+    #[error("record `{record}` of package `{id}` does not exist")]
+    RecordNotFound {
+        /// The identifier of the package.
+        id: PackageId,
+        /// The identifier of the record.
+        record: RecordId,
+    },
+
+    /// The record of the package log has not been published yet.
+    #[error("record `{record}` of package `{id}` has not been published")]
+    RecordNotPublished {
+        /// The identifier of the package.
+        id: PackageId,
+        /// The identifier of the record.
+        record: RecordId,
+    },
+
     /// The package failed validation.
     #[error("package `{id}` failed validation: {inner}")]
     PackageValidationFailed {
@@ -730,6 +4844,17 @@ pub enum ClientError {
         id: PackageId,
     },
 
+    /// The package log advanced past the head a publish expected.
+    #[error("{}", display_conflict(.id, .expected, .actual.as_ref()))]
+    PublishConflict {
+        /// The identifier of the package.
+        id: PackageId,
+        /// The head the package log was expected to have.
+        expected: RecordId,
+        /// The actual head of the package log, if the log has any records.
+        actual: Option<RecordId>,
+    },
+
     /// A publish operation was rejected.
     #[error("the publishing of package `{id}` was rejected due to: {reason}")]
     PublishRejected {
@@ -742,12 +4867,194 @@ pub enum ClientError {
     },
 
     /// The package is still missing content.
-    #[error("the package is still missing content after all content was uploaded")]
-    PackageMissingContent,
+    #[error(
+        "record `{record_id}` of package `{id}` is still missing content after all content was \
+         uploaded: {digests}",
+        digests = display_digests(.digests)
+    )]
+    PackageMissingContent {
+        /// The identifier of the package.
+        id: PackageId,
+        /// The identifier of the record that is missing content.
+        record_id: RecordId,
+        /// The digests of the content the registry is still missing.
+        digests: Vec<AnyHash>,
+    },
+
+    /// Content does not match the digest it was published with.
+    #[error("content has digest `{actual}` but a digest of `{expected}` was expected")]
+    ContentDigestMismatch {
+        /// The expected digest of the content.
+        expected: AnyHash,
+        /// The actual digest of the content.
+        actual: AnyHash,
+    },
+
+    /// A package version was released with content other than the pinned
+    /// digest.
+    #[error("version {version} of package `{id}` has content digest `{actual}` but was pinned to `{expected}`")]
+    DigestMismatch {
+        /// The identifier of the package.
+        id: PackageId,
+        /// The pinned version of the package.
+        version: Version,
+        /// The pinned digest of the content.
+        expected: AnyHash,
+        /// The digest of the content released for the version.
+        actual: AnyHash,
+    },
+
+    /// Only some versions of a package were downloaded.
+    ///
+    /// See [`Client::download_all_versions`].
+    #[error("failed to download version(s) {versions} of package `{id}`: {source}", versions = display_versions(failed))]
+    PartialDownload {
+        /// The identifier of the package.
+        id: PackageId,
+        /// The versions that were downloaded, in ascending version order.
+        downloaded: Vec<PackageDownload>,
+        /// The versions that failed to download, in ascending version order.
+        failed: Vec<Version>,
+        /// The error of the first failed download.
+        source: Box<ClientError>,
+    },
+
+    /// A blocking client was used from within an asynchronous runtime.
+    ///
+    /// See [`blocking::BlockingClient`].
+    #[cfg(feature = "blocking")]
+    #[error("a blocking client cannot be used from within an asynchronous runtime")]
+    BlockingInAsyncContext,
+
+    /// The operation was cancelled with the client's cancellation token.
+    #[error("the operation was cancelled")]
+    Cancelled,
+
+    /// The registry requires authentication or rejected the provided
+    /// authentication token.
+    #[error("the registry requires authentication or rejected the provided authentication token")]
+    Unauthorized,
+
+    /// The registry does not support the requested operation.
+    #[error("the registry does not support {operation}")]
+    Unsupported {
+        /// A description of the unsupported operation.
+        operation: String,
+    },
+
+    /// The registry uses a hash algorithm the client does not support.
+    #[error("the registry uses unsupported hash algorithm `{algorithm}`")]
+    UnsupportedHashAlgorithm {
+        /// The name of the unsupported hash algorithm.
+        algorithm: String,
+    },
+
+    /// A connection to the registry could not be established within the
+    /// connect timeout.
+    #[error("failed to connect to the registry within {timeout:?}")]
+    ConnectTimedOut {
+        /// The connect timeout that elapsed.
+        timeout: Duration,
+    },
+
+    /// A request to the registry did not complete within the request
+    /// timeout.
+    #[error("the request to the registry did not complete within {timeout:?}")]
+    RequestTimedOut {
+        /// The request timeout that elapsed.
+        timeout: Duration,
+    },
+
+    /// A content transfer made no progress within the content transfer
+    /// timeout.
+    #[error("the content transfer made no progress for {timeout:?}")]
+    TransferStalled {
+        /// The content transfer timeout that elapsed.
+        timeout: Duration,
+    },
+
+    /// A response from the registry exceeded the maximum size.
+    ///
+    /// Package log fetch and proof responses are subject to the maximum
+    /// response size; downloaded content is subject to the maximum content
+    /// size.
+    #[error("the registry response exceeds the limit of {limit} bytes")]
+    ResponseTooLarge {
+        /// The maximum size of the response, in bytes.
+        limit: u64,
+    },
+
+    /// An environment variable referenced by an authentication token is not
+    /// set.
+    #[error("environment variable `{name}` referenced by the authentication token is not set")]
+    AuthTokenVariableNotSet {
+        /// The name of the environment variable.
+        name: String,
+    },
+
+    /// A storage directory could not be locked in time.
+    #[error("failed to lock directory `{path}` within {timeout:?}", path = path.display())]
+    StorageLockTimeout {
+        /// The path to the directory that could not be locked.
+        path: PathBuf,
+        /// The timeout that elapsed.
+        timeout: Duration,
+    },
+
+    /// A package identifier is invalid.
+    #[error("invalid package identifier `{id}`: {reason}")]
+    InvalidPackageId {
+        /// The invalid package identifier.
+        id: String,
+        /// The reason the package identifier is invalid.
+        reason: String,
+    },
+
+    /// A proxy URL is invalid.
+    #[error("invalid proxy URL `{url}`: {message}")]
+    InvalidProxyUrl {
+        /// The invalid proxy URL.
+        url: String,
+        /// The reason the URL is invalid.
+        message: String,
+    },
+
+    /// A TLS client identity could not be loaded.
+    #[error(
+        "failed to load TLS client identity from certificate `{cert}` and key `{key}`: {message}",
+        cert = cert.display(),
+        key = key.display()
+    )]
+    InvalidClientIdentity {
+        /// The path to the certificate file.
+        cert: PathBuf,
+        /// The path to the private key file.
+        key: PathBuf,
+        /// The reason the identity could not be loaded.
+        message: String,
+    },
+
+    /// The inclusion proof of a package version is invalid.
+    #[error("the inclusion proof of version `{version}` of package `{id}` is invalid: {reason}")]
+    InvalidInclusionProof {
+        /// The id of the package.
+        id: PackageId,
+        /// The version of the package.
+        version: Version,
+        /// The reason the proof is invalid.
+        reason: String,
+    },
+
+    /// A package bundle is invalid.
+    #[error("invalid package bundle: {reason}")]
+    InvalidBundle {
+        /// The reason the bundle is invalid.
+        reason: String,
+    },
 
     /// An error occurred during an API operation.
     #[error(transparent)]
-    Api(#[from] api::ClientError),
+    Api(api::ClientError),
 
     /// An error occurred while performing a client operation.
     #[error("{0:?}")]
@@ -755,6 +5062,66 @@ pub enum ClientError {
 }
 
 impl ClientError {
+    /// Gets a stable, machine-readable code for the error.
+    ///
+    /// Unlike the error message, the code does not change between releases
+    /// and can be used to map errors to exit codes or localized messages.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NoDefaultUrl => "no_default_url",
+            Self::CheckpointRollback { .. } => "checkpoint_rollback",
+            Self::CheckpointTooRecent { .. } => "checkpoint_too_recent",
+            Self::LogForkDetected { .. } => "log_fork_detected",
+            Self::RegistryKeyChanged { .. } => "registry_key_changed",
+            Self::UntrustedCheckpointKey { .. } => "untrusted_checkpoint_key",
+            Self::InvalidCheckpointSignature { .. } => "invalid_checkpoint_signature",
+            Self::InclusionProofFailed { .. } => "inclusion_proof_failed",
+            Self::CheckpointMismatch { .. } => "checkpoint_mismatch",
+            Self::ProofServiceUnavailable { .. } => "proof_service_unavailable",
+            Self::Offline => "offline",
+            Self::OfflineDataMissing { .. } => "offline_data_missing",
+            Self::ProfileDoesNotExist { .. } => "profile_not_found",
+            Self::OperatorValidationFailed { .. } => "operator_validation_failed",
+            Self::CannotInitializePackage { .. } => "package_already_exists",
+            Self::MustInitializePackage { .. } => "package_not_initialized",
+            Self::NotPublishing => "not_publishing",
+            Self::NothingToPublish { .. } => "nothing_to_publish",
+            Self::PackageDoesNotExist { .. } => "package_not_found",
+            Self::PackageVersionDoesNotExist { .. } => "version_not_found",
+            Self::PackageVersionRequirementDoesNotExist { .. } => "no_matching_version",
+            Self::RecordNotFound { .. } => "record_not_found",
+            Self::RecordNotPublished { .. } => "record_not_published",
+            Self::PackageValidationFailed { .. } => "package_validation_failed",
+            Self::ContentNotFound { .. } => "content_not_found",
+            Self::PackageLogEmpty { .. } => "package_log_empty",
+            Self::PublishConflict { .. } => "publish_conflict",
+            Self::PublishRejected { .. } => "publish_rejected",
+            Self::PackageMissingContent { .. } => "missing_content",
+            Self::ContentDigestMismatch { .. } => "content_digest_mismatch",
+            Self::DigestMismatch { .. } => "pinned_digest_mismatch",
+            Self::PartialDownload { .. } => "partial_download",
+            #[cfg(feature = "blocking")]
+            Self::BlockingInAsyncContext => "blocking_in_async_context",
+            Self::Cancelled => "cancelled",
+            Self::Unauthorized => "unauthorized",
+            Self::Unsupported { .. } => "unsupported",
+            Self::UnsupportedHashAlgorithm { .. } => "unsupported_hash_algorithm",
+            Self::ConnectTimedOut { .. } => "connect_timed_out",
+            Self::RequestTimedOut { .. } => "request_timed_out",
+            Self::TransferStalled { .. } => "transfer_stalled",
+            Self::ResponseTooLarge { .. } => "response_too_large",
+            Self::AuthTokenVariableNotSet { .. } => "auth_token_variable_not_set",
+            Self::StorageLockTimeout { .. } => "storage_lock_timeout",
+            Self::InvalidPackageId { .. } => "invalid_package_id",
+            Self::InvalidProxyUrl { .. } => "invalid_proxy_url",
+            Self::InvalidClientIdentity { .. } => "invalid_client_identity",
+            Self::InvalidInclusionProof { .. } => "invalid_inclusion_proof",
+            Self::InvalidBundle { .. } => "invalid_bundle",
+            Self::Api(_) => "registry_error",
+            Self::Other(_) => "other",
+        }
+    }
+
     fn translate_log_not_found(
         e: api::ClientError,
         lookup: impl Fn(&LogId) -> Option<PackageId>,
@@ -769,9 +5136,43 @@ impl ClientError {
             _ => {}
         }
 
-        Self::Api(e)
+        e.into()
+    }
+}
+
+impl From<api::ClientError> for ClientError {
+    fn from(e: api::ClientError) -> Self {
+        match e {
+            api::ClientError::Unauthorized => Self::Unauthorized,
+            api::ClientError::Unsupported { operation } => Self::Unsupported { operation },
+            api::ClientError::UnsupportedHashAlgorithm { algorithm } => {
+                Self::UnsupportedHashAlgorithm { algorithm }
+            }
+            api::ClientError::ConnectTimedOut { timeout } => Self::ConnectTimedOut { timeout },
+            api::ClientError::RequestTimedOut { timeout } => Self::RequestTimedOut { timeout },
+            api::ClientError::TransferStalled { timeout } => Self::TransferStalled { timeout },
+            api::ClientError::ResponseTooLarge { limit } => Self::ResponseTooLarge { limit },
+            e => Self::Api(e),
+        }
+    }
+}
+
+impl From<InvalidPackageIdError> for ClientError {
+    fn from(e: InvalidPackageIdError) -> Self {
+        Self::InvalidPackageId {
+            id: e.id,
+            reason: e.reason,
+        }
     }
 }
 
 /// Represents the result of a client operation.
 pub type ClientResult<T> = Result<T, ClientError>;
+
+/// Parses a package identifier, such as one given on a command line.
+///
+/// Returns [`ClientError::InvalidPackageId`] describing why the identifier
+/// is invalid, without making any requests to the registry.
+pub fn parse_package_id(id: &str) -> ClientResult<PackageId> {
+    Ok(PackageId::parse(id)?)
+}
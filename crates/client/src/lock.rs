@@ -6,6 +6,7 @@ use std::fs::{File, OpenOptions};
 use std::io;
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 /// A file system lock.
 ///
@@ -65,6 +66,33 @@ impl FileLock {
         .unwrap())
     }
 
+    /// Opens exclusive access to a file like [`FileLock::open_rw`], waiting at
+    /// most the given timeout for the lock to be acquired.
+    ///
+    /// Acquiring the lock is retried with an increasing delay; if the lock
+    /// cannot be acquired before the timeout elapses, `Ok(None)` is returned.
+    pub fn open_rw_timeout(path: impl Into<PathBuf>, timeout: Duration) -> Result<Option<Self>> {
+        const MIN_DELAY: Duration = Duration::from_millis(10);
+        const MAX_DELAY: Duration = Duration::from_millis(500);
+
+        let path = path.into();
+        let deadline = Instant::now() + timeout;
+        let mut delay = MIN_DELAY;
+        loop {
+            if let Some(lock) = Self::try_open_rw(path.clone())? {
+                return Ok(Some(lock));
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+
+            std::thread::sleep(delay.min(remaining));
+            delay = (delay * 2).min(MAX_DELAY);
+        }
+    }
+
     /// Attempts to acquire shared access to a file, returning the locked version
     /// of a file.
     ///
@@ -0,0 +1,115 @@
+//! A module for client metrics.
+
+use crate::storage::StorageUsage;
+use std::{
+    fmt::Write,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// Represents the counters of a client and its routed registries.
+///
+/// Counters only ever increase for the lifetime of the client.
+#[derive(Debug, Default)]
+pub(crate) struct Metrics {
+    requests: AtomicU64,
+    downloaded_bytes: AtomicU64,
+    uploaded_bytes: AtomicU64,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+}
+
+impl Metrics {
+    /// Records that an HTTP request was sent.
+    pub(crate) fn record_request(&self) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that the given number of content bytes were downloaded.
+    pub(crate) fn record_download(&self, bytes: u64) {
+        self.downloaded_bytes.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Records that the given number of content bytes were uploaded.
+    pub(crate) fn record_upload(&self, bytes: u64) {
+        self.uploaded_bytes.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Records whether content to download was already in storage.
+    pub(crate) fn record_cache_lookup(&self, hit: bool) {
+        match hit {
+            true => self.cache_hits.fetch_add(1, Ordering::Relaxed),
+            false => self.cache_misses.fetch_add(1, Ordering::Relaxed),
+        };
+    }
+
+    /// Renders the counters and the given storage usage in the Prometheus
+    /// text exposition format.
+    pub(crate) fn render(&self, usage: &StorageUsage) -> String {
+        let mut text = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, samples: &[(&str, u64)]| {
+            writeln!(text, "# HELP {name} {help}").unwrap();
+            writeln!(text, "# TYPE {name} {kind}").unwrap();
+            for (labels, value) in samples {
+                writeln!(text, "{name}{labels} {value}").unwrap();
+            }
+        };
+
+        metric(
+            "warg_client_requests_total",
+            "counter",
+            "The number of HTTP requests sent, including retries.",
+            &[("", self.requests.load(Ordering::Relaxed))],
+        );
+        metric(
+            "warg_client_transferred_bytes_total",
+            "counter",
+            "The number of content bytes transferred.",
+            &[
+                (
+                    "{direction=\"download\"}",
+                    self.downloaded_bytes.load(Ordering::Relaxed),
+                ),
+                (
+                    "{direction=\"upload\"}",
+                    self.uploaded_bytes.load(Ordering::Relaxed),
+                ),
+            ],
+        );
+        metric(
+            "warg_client_cache_hits_total",
+            "counter",
+            "The number of content downloads served from storage.",
+            &[("", self.cache_hits.load(Ordering::Relaxed))],
+        );
+        metric(
+            "warg_client_cache_misses_total",
+            "counter",
+            "The number of content downloads not served from storage.",
+            &[("", self.cache_misses.load(Ordering::Relaxed))],
+        );
+        metric(
+            "warg_client_storage_bytes",
+            "gauge",
+            "The disk usage of client storage, in bytes.",
+            &[
+                ("{kind=\"content\"}", usage.content_bytes),
+                ("{kind=\"registry\"}", usage.registry_bytes),
+                ("{kind=\"partial\"}", usage.partial_bytes),
+            ],
+        );
+        metric(
+            "warg_client_storage_blobs",
+            "gauge",
+            "The number of stored content blobs.",
+            &[("", usage.blobs as u64)],
+        );
+        metric(
+            "warg_client_storage_packages",
+            "gauge",
+            "The number of stored package logs.",
+            &[("", usage.packages as u64)],
+        );
+
+        text
+    }
+}
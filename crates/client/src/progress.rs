@@ -0,0 +1,31 @@
+//! A module for reporting the progress of content transfers.
+
+/// A trait implemented by types that receive progress of content uploads
+/// and downloads.
+///
+/// Handlers are invoked from the async runtime and therefore must be `Send`
+/// and `Sync`.
+pub trait ProgressHandler: Send + Sync {
+    /// Called as bytes of content are transferred.
+    ///
+    /// `transferred` is the total number of bytes transferred so far and
+    /// `total` is the size of the content, if known.
+    fn on_progress(&self, transferred: u64, total: Option<u64>);
+}
+
+/// A progress handler that ignores all progress.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoProgress;
+
+impl ProgressHandler for NoProgress {
+    fn on_progress(&self, _transferred: u64, _total: Option<u64>) {}
+}
+
+impl<F> ProgressHandler for F
+where
+    F: Fn(u64, Option<u64>) + Send + Sync,
+{
+    fn on_progress(&self, transferred: u64, total: Option<u64>) {
+        self(transferred, total)
+    }
+}
@@ -0,0 +1,127 @@
+//! A module for verifiable proofs of the inclusion of package versions in a
+//! registry log.
+
+use crate::{api, ClientError, ClientResult};
+use serde::{Deserialize, Serialize};
+use warg_api::v1::proof::InclusionResponse;
+use warg_crypto::{
+    hash::{Hash, Sha256},
+    signing::PublicKey,
+    Encode, Signable,
+};
+use warg_protocol::{
+    package,
+    registry::{LogId, LogLeaf, PackageId, RecordId, RegistryIndex, TimestampedCheckpoint},
+    ProtoEnvelope, ProtoEnvelopeBody, SerdeEnvelope, Version,
+};
+
+/// Represents a proof that a version of a package is included in a
+/// registry log.
+///
+/// As a package log is a hash chain, the proof consists of the records of the
+/// package log from the record releasing the version to the head of the log,
+/// and the inclusion proofs of that head in the signed registry checkpoint.
+///
+/// The proof is self-contained; it may be serialized for storage and
+/// verified later with [`InclusionProof::verify`] without contacting the
+/// registry.
+///
+/// See [`Client::inclusion_proof`](crate::Client::inclusion_proof).
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InclusionProof {
+    /// The id of the package.
+    pub id: PackageId,
+    /// The version of the package the proof is for.
+    pub version: Version,
+    /// The records of the package log from the record releasing the version
+    /// to the head of the log, in order.
+    pub records: Vec<ProtoEnvelopeBody>,
+    /// The index of the head of the package log in the registry log.
+    pub registry_index: RegistryIndex,
+    /// The signed registry checkpoint the proof is for.
+    pub checkpoint: SerdeEnvelope<TimestampedCheckpoint>,
+    /// The log and map inclusion proof bundles of the head of the package
+    /// log.
+    pub proof: InclusionResponse,
+}
+
+impl InclusionProof {
+    /// Verifies the proof with the given key of the registry.
+    ///
+    /// The checkpoint must be signed by the key, the records must release
+    /// the version and form a chain, and the head of the chain must be
+    /// included in the checkpoint.
+    pub fn verify(&self, key: &PublicKey) -> ClientResult<()> {
+        verify_checkpoint_signature(&self.checkpoint, key)?;
+
+        let invalid = |reason: &str| ClientError::InvalidInclusionProof {
+            id: self.id.clone(),
+            version: self.version.clone(),
+            reason: reason.to_string(),
+        };
+
+        let mut head: Option<RecordId> = None;
+        for body in &self.records {
+            let record: ProtoEnvelope<package::PackageRecord> = body
+                .clone()
+                .try_into()
+                .map_err(|_| invalid("a record of the package log is malformed"))?;
+
+            match &head {
+                None if !releases(record.as_ref(), &self.version) => {
+                    return Err(invalid("the first record does not release the version"));
+                }
+                Some(prev) if record.as_ref().prev.as_ref() != Some(prev) => {
+                    return Err(invalid("the records do not form a chain"));
+                }
+                _ => {}
+            }
+
+            head = Some(RecordId::package_record::<Sha256>(&record));
+        }
+
+        let head = head.ok_or_else(|| invalid("the proof has no records"))?;
+        let checkpoint = &self.checkpoint.as_ref().checkpoint;
+        api::Client::validate_inclusion_response(
+            &self.proof,
+            checkpoint,
+            &[LogLeaf {
+                log_id: LogId::package_log::<Sha256>(&self.id),
+                record_id: head,
+            }],
+        )
+        .map_err(|inner| ClientError::InclusionProofFailed {
+            id: Hash::<Sha256>::of(checkpoint).into(),
+            inner,
+        })
+    }
+}
+
+/// Determines if the given record releases the given version.
+pub(crate) fn releases(record: &package::PackageRecord, version: &Version) -> bool {
+    record.entries.iter().any(
+        |entry| matches!(entry, package::PackageEntry::Release { version: v, .. } if v == version),
+    )
+}
+
+/// Verifies that the given checkpoint is signed by the given registry key.
+pub(crate) fn verify_checkpoint_signature(
+    ts_checkpoint: &SerdeEnvelope<TimestampedCheckpoint>,
+    key: &PublicKey,
+) -> ClientResult<()> {
+    let pinned = key.fingerprint();
+    if *ts_checkpoint.key_id() != pinned {
+        return Err(ClientError::RegistryKeyChanged {
+            pinned,
+            found: ts_checkpoint.key_id().clone(),
+        });
+    }
+
+    TimestampedCheckpoint::verify(
+        key,
+        &ts_checkpoint.as_ref().encode(),
+        ts_checkpoint.signature(),
+    )
+    .map_err(|_| ClientError::InvalidCheckpointSignature { key_id: pinned })
+}
@@ -0,0 +1,75 @@
+//! A module for constructing package records.
+
+use anyhow::Result;
+use std::time::SystemTime;
+use warg_crypto::signing;
+use warg_protocol::{
+    package::{PackageEntry, PackageRecord, PACKAGE_RECORD_VERSION},
+    registry::RecordId,
+    ProtoEnvelope,
+};
+
+/// A builder for signed package records.
+///
+/// Unlike publishing with [`PublishInfo`](crate::storage::PublishInfo), the
+/// builder takes package entries as-is: nothing is looked up in the package
+/// log, and the previous record and every entry are recorded exactly as
+/// given. An `init` entry must name the key that signs the record.
+///
+/// The signed record can be submitted with
+/// [`Client::submit_record`](crate::Client::submit_record).
+#[derive(Debug, Clone)]
+pub struct PackageRecordBuilder {
+    prev: Option<RecordId>,
+    timestamp: Option<SystemTime>,
+    entries: Vec<PackageEntry>,
+}
+
+impl PackageRecordBuilder {
+    /// Creates a new builder for a record following the given record.
+    ///
+    /// The previous record is `None` only for the first record of a package
+    /// log.
+    pub fn new(prev: Option<RecordId>) -> Self {
+        Self {
+            prev,
+            timestamp: None,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Sets the timestamp of the record.
+    ///
+    /// By default, the record is timestamped when it is signed.
+    pub fn with_timestamp(mut self, timestamp: SystemTime) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Appends an entry to the record.
+    pub fn with_entry(mut self, entry: PackageEntry) -> Self {
+        self.entries.push(entry);
+        self
+    }
+
+    /// Appends the given entries to the record, in order.
+    pub fn with_entries(mut self, entries: impl IntoIterator<Item = PackageEntry>) -> Self {
+        self.entries.extend(entries);
+        self
+    }
+
+    /// Signs the record with the given key.
+    pub fn sign(self, signing_key: &dyn signing::Signer) -> Result<ProtoEnvelope<PackageRecord>> {
+        let record = PackageRecord {
+            prev: self.prev,
+            version: PACKAGE_RECORD_VERSION,
+            // TODO: this seems wrong to record the current time client-side
+            // How can we guarantee that the timestamps are monotonic?
+            // Should incrementing timestamps even be a requirement?
+            timestamp: self.timestamp.unwrap_or_else(SystemTime::now),
+            entries: self.entries,
+        };
+
+        ProtoEnvelope::signed_contents_with(signing_key, record)
+    }
+}
@@ -0,0 +1,135 @@
+//! A module for signing with an external command.
+
+use anyhow::{bail, Context, Result};
+use std::{
+    ffi::OsString,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+use warg_crypto::signing::{PublicKey, Signature, Signer};
+
+/// A signer that signs messages by invoking an external command.
+///
+/// This allows the private key to be held outside of the process, such as
+/// in a hardware token exposed through a helper program.
+///
+/// The command is invoked with its configured arguments followed by one of
+/// the following operations:
+///
+/// * `public-key`: the command writes the public key of the signer to
+///   stdout, in the same `<algorithm>:<base64>` form used for public keys by
+///   the registry (e.g. `ecdsa-p256:...`).
+/// * `sign`: the command reads the message to sign from stdin until end of
+///   file and writes the signature of the message to stdout, in the same
+///   `<algorithm>:<base64>` form used for signatures by the registry.
+///
+/// Leading and trailing whitespace in the output of the command is ignored.
+/// A command that exits with a non-zero status fails the operation; the
+/// command's stderr is included in the error.
+///
+/// The command is run synchronously for each signature.
+pub struct CommandSigner {
+    program: PathBuf,
+    args: Vec<OsString>,
+    public_key: PublicKey,
+}
+
+impl CommandSigner {
+    /// Creates a new signer for the given program and arguments.
+    ///
+    /// The program is invoked once to get the public key of the signer.
+    pub fn new(
+        program: impl Into<PathBuf>,
+        args: impl IntoIterator<Item = impl Into<OsString>>,
+    ) -> Result<Self> {
+        let program = program.into();
+        let args: Vec<_> = args.into_iter().map(Into::into).collect();
+        let public_key = run(&program, &args, "public-key", None)?;
+        let public_key = public_key.parse().with_context(|| {
+            format!(
+                "signer command `{program}` returned an invalid public key",
+                program = program.display()
+            )
+        })?;
+
+        Ok(Self {
+            program,
+            args,
+            public_key,
+        })
+    }
+}
+
+impl Signer for CommandSigner {
+    fn public_key(&self) -> PublicKey {
+        self.public_key.clone()
+    }
+
+    fn sign(&self, msg: &[u8]) -> Result<Signature> {
+        run(&self.program, &self.args, "sign", Some(msg))?
+            .parse()
+            .with_context(|| {
+                format!(
+                    "signer command `{program}` returned an invalid signature",
+                    program = self.program.display()
+                )
+            })
+    }
+}
+
+/// Runs the signer command for the given operation, returning its trimmed
+/// output.
+fn run(program: &Path, args: &[OsString], operation: &str, input: Option<&[u8]>) -> Result<String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .arg(operation)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| {
+            format!(
+                "failed to run signer command `{program}`",
+                program = program.display()
+            )
+        })?;
+
+    // Closing stdin signals the end of the message to the command
+    let mut stdin = child.stdin.take().expect("stdin should be piped");
+    if let Some(input) = input {
+        stdin.write_all(input).with_context(|| {
+            format!(
+                "failed to write message to signer command `{program}`",
+                program = program.display()
+            )
+        })?;
+    }
+    drop(stdin);
+
+    let output = child.wait_with_output().with_context(|| {
+        format!(
+            "failed to wait for signer command `{program}`",
+            program = program.display()
+        )
+    })?;
+
+    if !output.status.success() {
+        bail!(
+            "signer command `{program}` failed to perform `{operation}` ({status}): {stderr}",
+            program = program.display(),
+            status = output.status,
+            stderr = String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(String::from_utf8(output.stdout)
+        .with_context(|| {
+            format!(
+                "signer command `{program}` returned output that is not UTF-8",
+                program = program.display()
+            )
+        })?
+        .trim()
+        .to_string())
+}
@@ -1,24 +1,33 @@
 //! A module for client storage implementations.
 
+use crate::PackageRecordBuilder;
 use anyhow::Result;
 use async_trait::async_trait;
 use bytes::Bytes;
-use futures_util::Stream;
+use futures_util::{Stream, StreamExt, TryStreamExt};
 use serde::{Deserialize, Serialize};
-use std::{path::PathBuf, pin::Pin, time::SystemTime};
+use std::{collections::HashSet, io, path::PathBuf, pin::Pin, sync::Arc};
+use thiserror::Error;
+use tokio::io::AsyncRead;
+use tokio_util::io::StreamReader;
 use warg_crypto::{
-    hash::{AnyHash, HashAlgorithm},
+    hash::{AnyHash, HashAlgorithm, Sha256},
     signing::{self, KeyID, PublicKey},
 };
 use warg_protocol::{
     operator,
-    package::{self, PackageRecord, Permission, PACKAGE_RECORD_VERSION},
-    registry::{Checkpoint, PackageId, RecordId, RegistryIndex, TimestampedCheckpoint},
-    ProtoEnvelope, SerdeEnvelope, Version,
+    package::{self, PackageRecord, Permission},
+    registry::{
+        Checkpoint, LogId, LogLeaf, PackageId, RecordId, RegistryIndex, RegistryLen,
+        TimestampedCheckpoint,
+    },
+    ProtoEnvelope, PublishedProtoEnvelopeBody, SerdeEnvelope, Version,
 };
 
 mod fs;
+mod memory;
 pub use fs::*;
+pub use memory::*;
 
 /// Trait for registry storage implementations.
 ///
@@ -27,12 +36,30 @@ pub use fs::*;
 ///
 /// Registry storage data must be synchronized if shared between
 /// multiple threads and processes.
+///
+/// Implementations must uphold the following for every method:
+///
+/// * a load following a completed store returns the stored value;
+/// * a store is atomic: a load never observes a partially stored value, even
+///   if the process is terminated while storing;
+/// * a store replaces any value previously stored under the same key.
+///
+/// Stores made by different method calls need not be atomic together. The
+/// client stores the operator and package logs before the checkpoint they
+/// were validated against, so an interrupted update leaves logs that are at
+/// most ahead of the stored checkpoint.
 #[async_trait]
 pub trait RegistryStorage: Send + Sync {
     /// Loads most recent checkpoint
+    ///
+    /// The client only accepts a newer checkpoint from the registry if it is
+    /// consistent with this checkpoint.
     async fn load_checkpoint(&self) -> Result<Option<SerdeEnvelope<TimestampedCheckpoint>>>;
 
     /// Stores most recent checkpoint
+    ///
+    /// The checkpoint was verified to be consistent with the previously
+    /// stored checkpoint, which it replaces.
     async fn store_checkpoint(
         &self,
         ts_checkpoint: &SerdeEnvelope<TimestampedCheckpoint>,
@@ -46,7 +73,19 @@ pub trait RegistryStorage: Send + Sync {
     /// Stores the operator information in the storage.
     async fn store_operator(&self, operator: OperatorInfo) -> Result<()>;
 
+    /// Loads the pinned public key of the registry operator.
+    ///
+    /// Returns `Ok(None)` if no key has been pinned.
+    async fn load_registry_key(&self) -> Result<Option<PublicKey>>;
+
+    /// Stores the pinned public key of the registry operator.
+    ///
+    /// If the key is `None`, any pinned key is deleted.
+    async fn store_registry_key(&self, key: Option<&PublicKey>) -> Result<()>;
+
     /// Loads the package information for all packages in the storage.
+    ///
+    /// Each stored package is returned exactly once, in no particular order.
     async fn load_packages(&self) -> Result<Vec<PackageInfo>>;
 
     /// Loads the package information from the storage.
@@ -55,8 +94,72 @@ pub trait RegistryStorage: Send + Sync {
     async fn load_package(&self, package: &PackageId) -> Result<Option<PackageInfo>>;
 
     /// Stores the package information in the storage.
+    ///
+    /// The information is keyed by the id of the package.
     async fn store_package(&self, info: &PackageInfo) -> Result<()>;
 
+    /// Removes the information about a package from the storage.
+    ///
+    /// Removing information that is not present is not an error.
+    async fn remove_package(&self, package: &PackageId) -> Result<()>;
+
+    /// Loads a handle to the package log of the given package without
+    /// loading its releases.
+    ///
+    /// Returns `Ok(None)` if the information is not present.
+    ///
+    /// The default implementation loads the entire package information;
+    /// storage that can read part of it should override this method.
+    async fn load_package_handle(&self, package: &PackageId) -> Result<Option<PackageHandle>> {
+        Ok(self
+            .load_package(package)
+            .await?
+            .as_ref()
+            .map(PackageHandle::new))
+    }
+
+    /// Loads a page of the releases of the given package, in log order.
+    ///
+    /// At most `limit` releases are returned, starting with the release at
+    /// index `offset`; no releases are returned if the information is not
+    /// present.
+    ///
+    /// The default implementation loads the entire package information;
+    /// storage that can read part of it should override this method.
+    async fn load_releases(
+        &self,
+        package: &PackageId,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<package::Release>> {
+        Ok(self
+            .load_package(package)
+            .await?
+            .map(|info| {
+                info.state
+                    .releases()
+                    .skip(offset)
+                    .take(limit)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    /// Computes the entry of the given package log in the registry
+    /// checkpoint from the package log in storage.
+    ///
+    /// Returns `Ok(None)` if the package log is not present, has no records
+    /// or was never updated to a checkpoint.
+    ///
+    /// The default implementation loads the package handle.
+    async fn compute_checkpoint(&self, package: &PackageId) -> Result<Option<PackageCheckpoint>> {
+        Ok(self
+            .load_package_handle(package)
+            .await?
+            .and_then(|handle| PackageCheckpoint::new(&handle)))
+    }
+
     /// Loads information about a pending publish operation.
     ///
     /// Returns `Ok(None)` if the information is not present.
@@ -66,6 +169,87 @@ pub trait RegistryStorage: Send + Sync {
     ///
     /// If the info is `None`, the any existing publish information is deleted.
     async fn store_publish(&self, info: Option<&PublishInfo>) -> Result<()>;
+
+    /// Gets statistics about the logs currently in the storage.
+    ///
+    /// Implementations should compute the statistics without loading the
+    /// stored logs.
+    async fn registry_stats(&self) -> Result<RegistryStats>;
+}
+
+#[async_trait]
+impl<T: RegistryStorage + ?Sized> RegistryStorage for Arc<T> {
+    async fn load_checkpoint(&self) -> Result<Option<SerdeEnvelope<TimestampedCheckpoint>>> {
+        self.as_ref().load_checkpoint().await
+    }
+
+    async fn store_checkpoint(
+        &self,
+        ts_checkpoint: &SerdeEnvelope<TimestampedCheckpoint>,
+    ) -> Result<()> {
+        self.as_ref().store_checkpoint(ts_checkpoint).await
+    }
+
+    async fn load_operator(&self) -> Result<Option<OperatorInfo>> {
+        self.as_ref().load_operator().await
+    }
+
+    async fn store_operator(&self, operator: OperatorInfo) -> Result<()> {
+        self.as_ref().store_operator(operator).await
+    }
+
+    async fn load_registry_key(&self) -> Result<Option<PublicKey>> {
+        self.as_ref().load_registry_key().await
+    }
+
+    async fn store_registry_key(&self, key: Option<&PublicKey>) -> Result<()> {
+        self.as_ref().store_registry_key(key).await
+    }
+
+    async fn load_packages(&self) -> Result<Vec<PackageInfo>> {
+        self.as_ref().load_packages().await
+    }
+
+    async fn load_package(&self, package: &PackageId) -> Result<Option<PackageInfo>> {
+        self.as_ref().load_package(package).await
+    }
+
+    async fn store_package(&self, info: &PackageInfo) -> Result<()> {
+        self.as_ref().store_package(info).await
+    }
+
+    async fn remove_package(&self, package: &PackageId) -> Result<()> {
+        self.as_ref().remove_package(package).await
+    }
+
+    async fn load_package_handle(&self, package: &PackageId) -> Result<Option<PackageHandle>> {
+        self.as_ref().load_package_handle(package).await
+    }
+
+    async fn load_releases(
+        &self,
+        package: &PackageId,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<package::Release>> {
+        self.as_ref().load_releases(package, offset, limit).await
+    }
+
+    async fn compute_checkpoint(&self, package: &PackageId) -> Result<Option<PackageCheckpoint>> {
+        self.as_ref().compute_checkpoint(package).await
+    }
+
+    async fn load_publish(&self) -> Result<Option<PublishInfo>> {
+        self.as_ref().load_publish().await
+    }
+
+    async fn store_publish(&self, info: Option<&PublishInfo>) -> Result<()> {
+        self.as_ref().store_publish(info).await
+    }
+
+    async fn registry_stats(&self) -> Result<RegistryStats> {
+        self.as_ref().registry_stats().await
+    }
 }
 
 /// Trait for content storage implementations.
@@ -80,6 +264,23 @@ pub trait ContentStorage: Send + Sync {
     /// Returns `None` if the content is not present on disk.
     fn content_location(&self, digest: &AnyHash) -> Option<PathBuf>;
 
+    /// Determines if the content associated with the given digest is present
+    /// in the storage.
+    ///
+    /// The default implementation checks for the content on disk; storage
+    /// that is not backed by the local file system should override it.
+    async fn contains_content(&self, digest: &AnyHash) -> Result<bool> {
+        Ok(self.content_location(digest).is_some())
+    }
+
+    /// Rebuilds any index the storage keeps of its stored content.
+    ///
+    /// This must be called after the stored content is modified by means
+    /// other than this storage. The default implementation does nothing.
+    async fn refresh_index(&self) -> Result<()> {
+        Ok(())
+    }
+
     /// Loads the content associated with the given digest as a stream.
     ///
     /// If the content is not found, `Ok(None)` is returned.
@@ -88,6 +289,63 @@ pub trait ContentStorage: Send + Sync {
         digest: &AnyHash,
     ) -> Result<Option<Pin<Box<dyn Stream<Item = Result<Bytes>> + Send + Sync>>>>;
 
+    /// Reads the content associated with the given digest.
+    ///
+    /// The content is verified against the digest as it is read; reading
+    /// the end of content that does not match the digest fails with an error
+    /// of kind [`std::io::ErrorKind::InvalidData`].
+    ///
+    /// If the content is not found, `Ok(None)` is returned.
+    async fn read_stream(
+        &self,
+        digest: &AnyHash,
+    ) -> Result<Option<Pin<Box<dyn AsyncRead + Send + Sync>>>> {
+        let Some(stream) = self.load_content(digest).await? else {
+            return Ok(None);
+        };
+
+        let digest = digest.clone();
+        let hasher = digest.algorithm().hasher();
+        let verified = futures_util::stream::try_unfold(
+            (stream, Some(hasher)),
+            move |(mut stream, hasher)| {
+                let digest = digest.clone();
+                async move {
+                    let Some(mut hasher) = hasher else {
+                        return Ok(None);
+                    };
+
+                    match stream
+                        .next()
+                        .await
+                        .transpose()
+                        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
+                    {
+                        Some(bytes) => {
+                            hasher.update(&bytes);
+                            Ok(Some((bytes, (stream, Some(hasher)))))
+                        }
+                        None => {
+                            let actual = hasher.finalize();
+                            if actual != digest {
+                                return Err(io::Error::new(
+                                    io::ErrorKind::InvalidData,
+                                    format!(
+                                        "content has digest `{actual}` but a digest of `{digest}` was expected"
+                                    ),
+                                ));
+                            }
+
+                            Ok(None)
+                        }
+                    }
+                }
+            },
+        );
+
+        Ok(Some(Box::pin(StreamReader::new(verified))))
+    }
+
     /// Stores the given stream as content.
     ///
     /// If `expected_digest` is `Some`, the storage will verify that the written
@@ -100,6 +358,339 @@ pub trait ContentStorage: Send + Sync {
         stream: Pin<Box<dyn Stream<Item = Result<Bytes>> + Send + Sync>>,
         expected_digest: Option<&AnyHash>,
     ) -> Result<AnyHash>;
+
+    /// Removes the content associated with the given digest.
+    ///
+    /// Removing content that is not stored is not an error.
+    async fn remove_content(&self, digest: &AnyHash) -> Result<()>;
+
+    /// Gets the number of bytes kept from an interrupted download of the
+    /// given content.
+    ///
+    /// Returns zero if no partial download of the content is kept.
+    async fn partial_download_len(&self, digest: &AnyHash) -> Result<u64>;
+
+    /// Stores downloaded content, resuming any partial download of it.
+    ///
+    /// The stream provides the content starting at the given byte offset,
+    /// which must not exceed [`ContentStorage::partial_download_len`]; kept
+    /// bytes past the offset are discarded. If the stream fails, the bytes
+    /// received so far are kept so that the download can be resumed.
+    ///
+    /// Once the stream completes, the full content is verified against the
+    /// digest before it is stored; content that fails verification is
+    /// discarded and [`ClientError::ContentDigestMismatch`] is returned.
+    ///
+    /// [`ClientError::ContentDigestMismatch`]: crate::ClientError::ContentDigestMismatch
+    async fn store_download(
+        &self,
+        digest: &AnyHash,
+        offset: u64,
+        stream: Pin<Box<dyn Stream<Item = Result<Bytes>> + Send + Sync>>,
+    ) -> Result<()>;
+
+    /// Discards any bytes kept from an interrupted download of the given
+    /// content.
+    async fn discard_download(&self, digest: &AnyHash) -> Result<()>;
+
+    /// Loads the content associated with the given digest as a stream
+    /// starting at the given byte offset.
+    ///
+    /// This is used to resume an interrupted upload of the content.
+    ///
+    /// If the content is not found, `Ok(None)` is returned.
+    async fn resume_upload(
+        &self,
+        digest: &AnyHash,
+        offset: u64,
+    ) -> Result<Option<Pin<Box<dyn Stream<Item = Result<Bytes>> + Send + Sync>>>>;
+
+    /// Loads information about an in-progress upload of the given content.
+    ///
+    /// Returns `Ok(None)` if the information is not present.
+    async fn load_upload(&self, digest: &AnyHash) -> Result<Option<UploadInfo>>;
+
+    /// Stores information about an in-progress upload of the given content.
+    ///
+    /// If the info is `None`, any existing upload information is deleted.
+    async fn store_upload(&self, digest: &AnyHash, info: Option<&UploadInfo>) -> Result<()>;
+
+    /// Deletes all stored content whose digest is not in the given reachable
+    /// set.
+    ///
+    /// Content with an in-progress upload is never deleted.
+    ///
+    /// Returns statistics about the content that was reclaimed.
+    async fn gc(&self, reachable: &HashSet<AnyHash>) -> Result<GcStats>;
+
+    /// Verifies the integrity of all stored content.
+    ///
+    /// Every stored content blob is re-hashed as a stream and compared
+    /// against its digest. Up to `concurrency` blobs are re-hashed at once; a
+    /// concurrency of zero is treated as one.
+    ///
+    /// A blob that fails verification does not stop the verification of the
+    /// others. Returns the digest of each blob that failed verification, in
+    /// no particular order.
+    async fn verify_all(&self, concurrency: usize) -> Result<Vec<(AnyHash, VerifyError)>>;
+
+    /// Verifies the integrity of the content associated with the given
+    /// digest.
+    ///
+    /// Storage may remember content it has verified and skip re-hashing it
+    /// while it is unchanged; if `force` is set, the content is always
+    /// re-hashed. The default implementation always re-hashes the content.
+    ///
+    /// Returns the reason the content failed verification, if it did;
+    /// content that is not found is unreadable.
+    async fn verify_content(&self, digest: &AnyHash, force: bool) -> Result<Option<VerifyError>> {
+        let _ = force;
+        let Some(mut stream) = self.load_content(digest).await? else {
+            return Ok(Some(VerifyError::Unreadable {
+                message: "content not found".to_string(),
+            }));
+        };
+
+        let mut hasher = digest.algorithm().hasher();
+        while let Some(bytes) = stream.next().await {
+            match bytes {
+                Ok(bytes) => hasher.update(&bytes),
+                Err(e) => {
+                    return Ok(Some(VerifyError::Unreadable {
+                        message: e.to_string(),
+                    }))
+                }
+            }
+        }
+
+        let actual = hasher.finalize();
+        Ok((actual != *digest).then_some(VerifyError::DigestMismatch { actual }))
+    }
+
+    /// Gets statistics about the content currently in the storage.
+    async fn cache_stats(&self) -> Result<CacheStats>;
+
+    /// Gets the total size, in bytes, of partially downloaded content and of
+    /// temporary files in the storage.
+    async fn partial_bytes(&self) -> Result<u64>;
+}
+
+#[async_trait]
+impl<T: ContentStorage + ?Sized> ContentStorage for Arc<T> {
+    fn content_location(&self, digest: &AnyHash) -> Option<PathBuf> {
+        self.as_ref().content_location(digest)
+    }
+
+    async fn contains_content(&self, digest: &AnyHash) -> Result<bool> {
+        self.as_ref().contains_content(digest).await
+    }
+
+    async fn refresh_index(&self) -> Result<()> {
+        self.as_ref().refresh_index().await
+    }
+
+    async fn load_content(
+        &self,
+        digest: &AnyHash,
+    ) -> Result<Option<Pin<Box<dyn Stream<Item = Result<Bytes>> + Send + Sync>>>> {
+        self.as_ref().load_content(digest).await
+    }
+
+    async fn read_stream(
+        &self,
+        digest: &AnyHash,
+    ) -> Result<Option<Pin<Box<dyn AsyncRead + Send + Sync>>>> {
+        self.as_ref().read_stream(digest).await
+    }
+
+    async fn store_content(
+        &self,
+        stream: Pin<Box<dyn Stream<Item = Result<Bytes>> + Send + Sync>>,
+        expected_digest: Option<&AnyHash>,
+    ) -> Result<AnyHash> {
+        self.as_ref().store_content(stream, expected_digest).await
+    }
+
+    async fn remove_content(&self, digest: &AnyHash) -> Result<()> {
+        self.as_ref().remove_content(digest).await
+    }
+
+    async fn partial_download_len(&self, digest: &AnyHash) -> Result<u64> {
+        self.as_ref().partial_download_len(digest).await
+    }
+
+    async fn store_download(
+        &self,
+        digest: &AnyHash,
+        offset: u64,
+        stream: Pin<Box<dyn Stream<Item = Result<Bytes>> + Send + Sync>>,
+    ) -> Result<()> {
+        self.as_ref().store_download(digest, offset, stream).await
+    }
+
+    async fn discard_download(&self, digest: &AnyHash) -> Result<()> {
+        self.as_ref().discard_download(digest).await
+    }
+
+    async fn resume_upload(
+        &self,
+        digest: &AnyHash,
+        offset: u64,
+    ) -> Result<Option<Pin<Box<dyn Stream<Item = Result<Bytes>> + Send + Sync>>>> {
+        self.as_ref().resume_upload(digest, offset).await
+    }
+
+    async fn load_upload(&self, digest: &AnyHash) -> Result<Option<UploadInfo>> {
+        self.as_ref().load_upload(digest).await
+    }
+
+    async fn store_upload(&self, digest: &AnyHash, info: Option<&UploadInfo>) -> Result<()> {
+        self.as_ref().store_upload(digest, info).await
+    }
+
+    async fn gc(&self, reachable: &HashSet<AnyHash>) -> Result<GcStats> {
+        self.as_ref().gc(reachable).await
+    }
+
+    async fn verify_all(&self, concurrency: usize) -> Result<Vec<(AnyHash, VerifyError)>> {
+        self.as_ref().verify_all(concurrency).await
+    }
+
+    async fn verify_content(&self, digest: &AnyHash, force: bool) -> Result<Option<VerifyError>> {
+        self.as_ref().verify_content(digest, force).await
+    }
+
+    async fn cache_stats(&self) -> Result<CacheStats> {
+        self.as_ref().cache_stats().await
+    }
+
+    async fn partial_bytes(&self) -> Result<u64> {
+        self.as_ref().partial_bytes().await
+    }
+}
+
+/// Represents statistics about content reclaimed by garbage collection.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GcStats {
+    /// The number of content blobs that were deleted.
+    pub blobs: usize,
+    /// The total size, in bytes, of the content that was deleted.
+    pub bytes: u64,
+}
+
+/// Represents statistics about the content in a content storage.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// The number of stored content blobs.
+    pub entries: usize,
+    /// The total size, in bytes, of the stored content.
+    pub bytes: u64,
+}
+
+/// Represents statistics about the logs in a registry storage.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RegistryStats {
+    /// The number of stored package logs.
+    pub packages: usize,
+    /// The total size, in bytes, of the stored logs, checkpoint and other
+    /// registry state.
+    pub bytes: u64,
+}
+
+/// Represents the disk usage of client storage.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StorageUsage {
+    /// The number of stored content blobs.
+    pub blobs: usize,
+    /// The total size, in bytes, of the stored content blobs.
+    pub content_bytes: u64,
+    /// The number of stored package logs, including those of routed
+    /// registries.
+    pub packages: usize,
+    /// The total size, in bytes, of the stored registry logs and state,
+    /// including those of routed registries.
+    pub registry_bytes: u64,
+    /// The total size, in bytes, of partial downloads and temporary files.
+    pub partial_bytes: u64,
+}
+
+impl StorageUsage {
+    /// Gets the total size, in bytes, of client storage.
+    pub fn total_bytes(&self) -> u64 {
+        self.content_bytes + self.registry_bytes + self.partial_bytes
+    }
+}
+
+/// Represents an error with stored content found by verification.
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum VerifyError {
+    /// The stored content does not match its digest.
+    #[error("stored content has digest `{actual}`")]
+    DigestMismatch {
+        /// The actual digest of the stored content.
+        actual: AnyHash,
+    },
+    /// The stored content could not be read.
+    #[error("failed to read stored content: {message}")]
+    Unreadable {
+        /// The message of the read error.
+        message: String,
+    },
+}
+
+/// Represents an inconsistency in a stored log found by verification.
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum LogVerifyError {
+    /// The log has records but no registry checkpoint is stored.
+    #[error("log has records but no registry checkpoint is stored")]
+    MissingCheckpoint,
+    /// The log was updated to a checkpoint newer than the stored registry
+    /// checkpoint.
+    #[error(
+        "log checkpoint has length {log_length} but the registry checkpoint has length {registry_log_length}"
+    )]
+    CheckpointAhead {
+        /// The log length of the log's checkpoint.
+        log_length: RegistryLen,
+        /// The log length of the stored registry checkpoint.
+        registry_log_length: RegistryLen,
+    },
+    /// The head record of the log is not covered by its checkpoint.
+    #[error("log head has registry index {index} but the checkpoint has length {log_length}")]
+    HeadBeyondCheckpoint {
+        /// The registry index of the log's head record.
+        index: RegistryIndex,
+        /// The log length of the log's checkpoint.
+        log_length: RegistryLen,
+    },
+}
+
+/// Represents the result of verifying client storage.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StorageReport {
+    /// The stored content that failed verification.
+    pub content: Vec<(AnyHash, VerifyError)>,
+    /// The inconsistency found in the operator log, if any.
+    pub operator: Option<LogVerifyError>,
+    /// The package logs that are inconsistent.
+    pub packages: Vec<(PackageId, LogVerifyError)>,
+}
+
+impl StorageReport {
+    /// Determines if verification found no problems with the storage.
+    pub fn is_ok(&self) -> bool {
+        self.content.is_empty() && self.operator.is_none() && self.packages.is_empty()
+    }
+}
+
+/// Represents information about an in-progress content upload.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadInfo {
+    /// The digest of the content being uploaded.
+    pub digest: AnyHash,
+    /// The number of bytes of the content that were sent to the registry.
+    pub offset: u64,
 }
 
 /// Represents information about a registry operator.
@@ -112,6 +703,109 @@ pub struct OperatorInfo {
     /// The registry log index of the most recent record
     #[serde(default)]
     pub head_registry_index: Option<RegistryIndex>,
+    /// The validated records of the operator log, in order.
+    ///
+    /// The records are only kept by [`Client::fetch_operator_log`] and may
+    /// be ahead of or behind `state`.
+    ///
+    /// [`Client::fetch_operator_log`]: crate::Client::fetch_operator_log
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub records: Vec<PublishedProtoEnvelopeBody>,
+}
+
+/// Represents a package log in registry storage whose releases are loaded
+/// on demand.
+///
+/// Only the checkpoint and head of the log are held by the handle; releases
+/// are read from storage a page at a time with [`PackageHandle::releases`].
+/// This bounds the memory used for packages with many releases at the cost
+/// of more storage reads.
+///
+/// See [`RegistryStorage::load_package_handle`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageHandle {
+    /// The id of the package.
+    pub id: PackageId,
+    /// The last known checkpoint of the package.
+    pub checkpoint: Option<Checkpoint>,
+    /// The head of the package log.
+    pub head: Option<package::Head>,
+    /// The registry log index of the most recent record.
+    pub head_registry_index: Option<RegistryIndex>,
+}
+
+impl PackageHandle {
+    /// Creates a handle to the package log of the given package
+    /// information.
+    pub fn new(info: &PackageInfo) -> Self {
+        Self {
+            id: info.id.clone(),
+            checkpoint: info.checkpoint.clone(),
+            head: info.state.head().clone(),
+            head_registry_index: info.head_registry_index,
+        }
+    }
+
+    /// Gets the releases of the package log, in log order, reading them from
+    /// the given storage in pages of the given size.
+    ///
+    /// At most one page of releases is held in memory at a time; a page size
+    /// of zero reads one release at a time.
+    pub fn releases<'a, R: RegistryStorage + ?Sized>(
+        &'a self,
+        storage: &'a R,
+        page_size: usize,
+    ) -> impl Stream<Item = Result<package::Release>> + Send + 'a {
+        let page_size = page_size.max(1);
+        futures_util::stream::try_unfold(Some(0), move |offset| async move {
+            let Some(offset) = offset else {
+                return Ok::<_, anyhow::Error>(None);
+            };
+
+            let page = storage.load_releases(&self.id, offset, page_size).await?;
+            let next = (page.len() == page_size).then_some(offset + page_size);
+            Ok(Some((
+                futures_util::stream::iter(page.into_iter().map(Ok)),
+                next,
+            )))
+        })
+        .try_flatten()
+    }
+}
+
+/// Represents the entry of a package log in a registry checkpoint, as
+/// computed from client storage.
+///
+/// The registry log leaf of the head of the package log is included in the
+/// log root of the checkpoint at the registry index, and the map root of
+/// the checkpoint maps the package log to the head.
+///
+/// See [`RegistryStorage::compute_checkpoint`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageCheckpoint {
+    /// The checkpoint the package log was last updated to.
+    pub checkpoint: Checkpoint,
+    /// The registry log leaf of the head of the package log.
+    pub leaf: LogLeaf,
+    /// The registry log index of the head of the package log.
+    pub registry_index: RegistryIndex,
+}
+
+impl PackageCheckpoint {
+    /// Creates the checkpoint entry of the package log of the given handle.
+    ///
+    /// Returns `None` if the package log has no records or was never
+    /// updated to a checkpoint.
+    pub fn new(handle: &PackageHandle) -> Option<Self> {
+        Some(Self {
+            checkpoint: handle.checkpoint.clone()?,
+            leaf: LogLeaf {
+                log_id: LogId::package_log::<Sha256>(&handle.id),
+                record_id: handle.head.as_ref()?.digest.clone(),
+            },
+            registry_index: handle.head_registry_index?,
+        })
+    }
 }
 
 /// Represents information about a registry package.
@@ -123,12 +817,19 @@ pub struct PackageInfo {
     /// The last known checkpoint of the package.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub checkpoint: Option<Checkpoint>,
+    /// The timestamp of the last known checkpoint of the package.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checkpoint_timestamp: Option<u64>,
     /// The current package log state
     #[serde(default)]
     pub state: package::LogState,
     /// The registry log index of the most recent record
     #[serde(default)]
     pub head_registry_index: Option<RegistryIndex>,
+    /// Whether the package log was stored without verifying its inclusion
+    /// and consistency proofs.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub unverified: bool,
 }
 
 impl PackageInfo {
@@ -137,10 +838,31 @@ impl PackageInfo {
         Self {
             id: id.into(),
             checkpoint: None,
+            checkpoint_timestamp: None,
             state: package::LogState::default(),
             head_registry_index: None,
+            unverified: false,
         }
     }
+
+    /// Gets the last known checkpoint of the package with its timestamp.
+    ///
+    /// Returns `None` if the package has no checkpoint or its timestamp is
+    /// not known.
+    pub fn timestamped_checkpoint(&self) -> Option<TimestampedCheckpoint> {
+        Some(TimestampedCheckpoint {
+            checkpoint: self.checkpoint.clone()?,
+            timestamp: self.checkpoint_timestamp?,
+        })
+    }
+
+    /// Gets the deprecation of the given version of the package.
+    ///
+    /// Returns `None` if the version was not released or has not been
+    /// deprecated.
+    pub fn deprecation(&self, version: &Version) -> Option<&package::Deprecation> {
+        self.state.release(version)?.deprecation.as_ref()
+    }
 }
 
 /// Represents a record entry being published.
@@ -161,6 +883,21 @@ pub enum PublishEntry {
         /// The version of the release being yanked.
         version: Version,
     },
+    /// A yanked release is being unyanked.
+    Unyank {
+        /// The version of the release being unyanked.
+        version: Version,
+    },
+    /// A release is being deprecated.
+    Deprecate {
+        /// The version of the release being deprecated.
+        version: Version,
+        /// The message explaining the deprecation.
+        message: String,
+        /// The version that supersedes the deprecated release.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        superseded_by: Option<Version>,
+    },
     /// A key is being granted permission(s).
     Grant {
         /// The public key being granted to.
@@ -188,6 +925,15 @@ pub struct PublishInfo {
     /// If `None` and the package is not being initialized,
     /// the latest head of the package log will be fetched prior to publishing.
     pub head: Option<RecordId>,
+    /// The head the package log is expected to have in the registry.
+    ///
+    /// If set, the record is built on the expected head and is only published
+    /// if the package log has not advanced past it; otherwise
+    /// [`ClientError::PublishConflict`](crate::ClientError::PublishConflict)
+    /// is returned with the actual head. The expected head takes precedence
+    /// over `head`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_head: Option<RecordId>,
     /// The new record entries to publish.
     pub entries: Vec<PublishEntry>,
 }
@@ -200,7 +946,7 @@ impl PublishInfo {
 
     pub(crate) fn finalize(
         self,
-        signing_key: &signing::PrivateKey,
+        signing_key: &dyn signing::Signer,
     ) -> Result<ProtoEnvelope<PackageRecord>> {
         let mut entries = Vec::with_capacity(self.entries.len());
         for entry in self.entries {
@@ -217,6 +963,18 @@ impl PublishInfo {
                 PublishEntry::Yank { version } => {
                     entries.push(package::PackageEntry::Yank { version })
                 }
+                PublishEntry::Unyank { version } => {
+                    entries.push(package::PackageEntry::Unyank { version })
+                }
+                PublishEntry::Deprecate {
+                    version,
+                    message,
+                    superseded_by,
+                } => entries.push(package::PackageEntry::Deprecate {
+                    version,
+                    message,
+                    superseded_by,
+                }),
                 PublishEntry::Grant { key, permissions } => {
                     entries.push(package::PackageEntry::GrantFlat { key, permissions })
                 }
@@ -230,16 +988,8 @@ impl PublishInfo {
             }
         }
 
-        let record = package::PackageRecord {
-            prev: self.head,
-            version: PACKAGE_RECORD_VERSION,
-            // TODO: this seems wrong to record the current time client-side
-            // How can we guarantee that the timestamps are monotonic?
-            // Should incrementing timestamps even be a requirement?
-            timestamp: SystemTime::now(),
-            entries,
-        };
-
-        Ok(ProtoEnvelope::signed_contents(signing_key, record)?)
+        PackageRecordBuilder::new(self.head)
+            .with_entries(entries)
+            .sign(signing_key)
     }
 }
@@ -1,32 +1,52 @@
 //! A module for file system client storage.
 
-use super::{ContentStorage, OperatorInfo, PackageInfo, PublishInfo, RegistryStorage};
-use crate::lock::FileLock;
+use super::{
+    CacheStats, ContentStorage, GcStats, OperatorInfo, PackageHandle, PackageInfo, PublishInfo,
+    RegistryStats, RegistryStorage, UploadInfo, VerifyError,
+};
+use crate::{lock::FileLock, ClientError};
 use anyhow::{anyhow, bail, Context, Result};
 use async_trait::async_trait;
 use bytes::Bytes;
 use futures_util::{Stream, StreamExt, TryStreamExt};
-use serde::{Deserialize, Serialize};
+use serde::{
+    de::{DeserializeSeed, IgnoredAny, MapAccess, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
 use std::{
+    collections::{HashMap, HashSet},
     ffi::OsStr,
-    fs,
+    fmt, fs,
+    io::{Read, SeekFrom, Write},
+    marker::PhantomData,
     path::{Path, PathBuf},
     pin::Pin,
+    sync::Mutex,
+    time::{Duration, SystemTime},
 };
 use tempfile::NamedTempFile;
-use tokio::io::{AsyncWriteExt, BufReader, BufWriter};
+use tokio::io::{AsyncSeekExt, AsyncWriteExt, BufReader, BufWriter};
 use tokio_util::io::ReaderStream;
-use walkdir::WalkDir;
-use warg_crypto::hash::{AnyHash, Digest, Hash, Sha256};
+use walkdir::{DirEntry, WalkDir};
+use warg_crypto::{
+    hash::{AnyHash, HashAlgorithm, Sha256},
+    signing::PublicKey,
+};
 use warg_protocol::{
-    registry::{LogId, PackageId, TimestampedCheckpoint},
+    package::{Head, Release},
+    registry::{Checkpoint, LogId, PackageId, RegistryIndex, TimestampedCheckpoint},
     SerdeEnvelope,
 };
 
 const TEMP_DIRECTORY: &str = "temp";
 const PENDING_PUBLISH_FILE: &str = "pending-publish.json";
+const REGISTRY_KEY_FILE: &str = "registry-key.json";
 const LOCK_FILE_NAME: &str = ".lock";
 const PACKAGE_LOGS_DIR: &str = "package-logs";
+const PENDING_UPLOADS_DIR: &str = "uploads";
+const PARTIAL_DOWNLOADS_DIR: &str = "downloads";
+const ACCESS_INDEX_FILE: &str = "access.json";
+const CONTENT_INDEX_FILE: &str = "content-index.json";
 
 /// Represents a package storage using the local file system.
 pub struct FileSystemRegistryStorage {
@@ -51,6 +71,23 @@ impl FileSystemRegistryStorage {
         }
     }
 
+    /// Locks the package storage, waiting at most the given timeout for the
+    /// lock to be acquired.
+    ///
+    /// The base directory will be created if it does not exist.
+    ///
+    /// If the lock cannot be acquired before the timeout elapses, `Ok(None)`
+    /// is returned.
+    pub fn lock_timeout(base_dir: impl Into<PathBuf>, timeout: Duration) -> Result<Option<Self>> {
+        let base_dir = base_dir.into();
+        Ok(
+            FileLock::open_rw_timeout(base_dir.join(LOCK_FILE_NAME), timeout)?.map(|lock| Self {
+                _lock: lock,
+                base_dir,
+            }),
+        )
+    }
+
     /// Locks a new package storage at the given base directory.
     ///
     /// The base directory will be created if it does not exist.
@@ -142,6 +179,18 @@ impl RegistryStorage for FileSystemRegistryStorage {
         store(&self.operator_path(), info).await
     }
 
+    async fn load_registry_key(&self) -> Result<Option<PublicKey>> {
+        load(&self.base_dir.join(REGISTRY_KEY_FILE)).await
+    }
+
+    async fn store_registry_key(&self, key: Option<&PublicKey>) -> Result<()> {
+        let path = self.base_dir.join(REGISTRY_KEY_FILE);
+        match key {
+            Some(key) => store(&path, key).await,
+            None => delete(&path).await,
+        }
+    }
+
     async fn load_package(&self, package: &PackageId) -> Result<Option<PackageInfo>> {
         Ok(load(&self.package_path(package)).await?)
     }
@@ -150,6 +199,62 @@ impl RegistryStorage for FileSystemRegistryStorage {
         store(&self.package_path(&info.id), info).await
     }
 
+    async fn remove_package(&self, package: &PackageId) -> Result<()> {
+        delete(&self.package_path(package)).await
+    }
+
+    async fn load_package_handle(&self, package: &PackageId) -> Result<Option<PackageHandle>> {
+        /// The parts of stored package information kept by a handle.
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Stored {
+            id: PackageId,
+            #[serde(default)]
+            checkpoint: Option<Checkpoint>,
+            #[serde(default)]
+            state: StoredState,
+            #[serde(default)]
+            head_registry_index: Option<RegistryIndex>,
+        }
+
+        #[derive(Default, Deserialize)]
+        struct StoredState {
+            #[serde(default)]
+            head: Option<Head>,
+        }
+
+        Ok(
+            load_partial(&self.package_path(package), PhantomData::<Stored>)?.map(|stored| {
+                PackageHandle {
+                    id: stored.id,
+                    checkpoint: stored.checkpoint,
+                    head: stored.state.head,
+                    head_registry_index: stored.head_registry_index,
+                }
+            }),
+        )
+    }
+
+    async fn load_releases(
+        &self,
+        package: &PackageId,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<Release>> {
+        let seed = Field {
+            name: "state",
+            seed: Field {
+                name: "releases",
+                seed: Page { offset, limit },
+            },
+        };
+
+        Ok(load_partial(&self.package_path(package), seed)?
+            .flatten()
+            .flatten()
+            .unwrap_or_default())
+    }
+
     async fn load_publish(&self) -> Result<Option<PublishInfo>> {
         Ok(load(&self.base_dir.join(PENDING_PUBLISH_FILE))
             .await?
@@ -163,6 +268,64 @@ impl RegistryStorage for FileSystemRegistryStorage {
             None => delete(&path).await,
         }
     }
+
+    async fn registry_stats(&self) -> Result<RegistryStats> {
+        // Hidden files are the lock file and temporary files of interrupted
+        // stores, neither of which is registry state
+        let (_, bytes) = dir_usage(&self.base_dir, |e| !is_hidden(e))?;
+        let (packages, _) = dir_usage(&self.base_dir.join(PACKAGE_LOGS_DIR), |e| !is_hidden(e))?;
+        Ok(RegistryStats { packages, bytes })
+    }
+}
+
+/// Records the order in which stored content was last accessed.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AccessIndex {
+    /// The sequence number of the next access.
+    next: u64,
+    /// The sequence number of the last access of each content digest.
+    accessed: HashMap<AnyHash, u64>,
+    /// The size of each stored content.
+    ///
+    /// Sizes are measured when the storage is opened rather than persisted,
+    /// as content may be stored by clients that do not limit its size.
+    #[serde(skip)]
+    sizes: HashMap<AnyHash, u64>,
+    /// The total size of the stored content.
+    #[serde(skip)]
+    total: u64,
+    /// Whether accesses were recorded since the index was last persisted.
+    #[serde(skip)]
+    dirty: bool,
+}
+
+impl AccessIndex {
+    fn touch(&mut self, digest: &AnyHash) {
+        self.accessed.insert(digest.clone(), self.next);
+        self.next += 1;
+        self.dirty = true;
+    }
+
+    fn insert(&mut self, digest: &AnyHash, len: u64) {
+        if let Some(prev) = self.sizes.insert(digest.clone(), len) {
+            self.total -= prev;
+        }
+
+        self.total += len;
+    }
+
+    /// Removes the given content from the index, returning whether it was
+    /// present.
+    fn remove(&mut self, digest: &AnyHash) -> bool {
+        if let Some(len) = self.sizes.remove(digest) {
+            self.total -= len;
+        }
+
+        let removed = self.accessed.remove(digest).is_some();
+        self.dirty |= removed;
+        removed
+    }
 }
 
 /// Represents a content storage using the local file system.
@@ -170,6 +333,11 @@ pub struct FileSystemContentStorage {
     _lock: FileLock,
     base_dir: PathBuf,
     temp_dir: PathBuf,
+    downloads_dir: PathBuf,
+    max_cache_bytes: Option<u64>,
+    access: Mutex<AccessIndex>,
+    index: Mutex<Option<HashSet<AnyHash>>>,
+    verified: Mutex<HashMap<AnyHash, SystemTime>>,
 }
 
 impl FileSystemContentStorage {
@@ -180,17 +348,27 @@ impl FileSystemContentStorage {
     /// If the lock cannot be acquired, `Ok(None)` is returned.
     pub fn try_lock(base_dir: impl Into<PathBuf>) -> Result<Option<Self>> {
         let base_dir = base_dir.into();
-        let temp_dir = base_dir.join(TEMP_DIRECTORY);
         match FileLock::try_open_rw(base_dir.join(LOCK_FILE_NAME))? {
-            Some(lock) => Ok(Some(Self {
-                _lock: lock,
-                base_dir,
-                temp_dir,
-            })),
+            Some(lock) => Ok(Some(Self::new(lock, base_dir))),
             None => Ok(None),
         }
     }
 
+    /// Locks the content storage, waiting at most the given timeout for the
+    /// lock to be acquired.
+    ///
+    /// The base directory will be created if it does not exist.
+    ///
+    /// If the lock cannot be acquired before the timeout elapses, `Ok(None)`
+    /// is returned.
+    pub fn lock_timeout(base_dir: impl Into<PathBuf>, timeout: Duration) -> Result<Option<Self>> {
+        let base_dir = base_dir.into();
+        Ok(
+            FileLock::open_rw_timeout(base_dir.join(LOCK_FILE_NAME), timeout)?
+                .map(|lock| Self::new(lock, base_dir)),
+        )
+    }
+
     /// Locks a new content storage at the given base directory.
     ///
     /// The base directory will be created if it does not exist.
@@ -199,13 +377,247 @@ impl FileSystemContentStorage {
     /// will block.
     pub fn lock(base_dir: impl Into<PathBuf>) -> Result<Self> {
         let base_dir = base_dir.into();
-        let temp_dir = base_dir.join(TEMP_DIRECTORY);
         let lock = FileLock::open_rw(base_dir.join(LOCK_FILE_NAME))?;
-        Ok(Self {
+        Ok(Self::new(lock, base_dir))
+    }
+
+    fn new(lock: FileLock, base_dir: PathBuf) -> Self {
+        Self {
             _lock: lock,
+            temp_dir: base_dir.join(TEMP_DIRECTORY),
+            downloads_dir: base_dir.join(PARTIAL_DOWNLOADS_DIR),
             base_dir,
-            temp_dir,
-        })
+            max_cache_bytes: None,
+            access: Default::default(),
+            index: Default::default(),
+            verified: Default::default(),
+        }
+    }
+
+    /// Uses the given directory for temporary files and partial downloads.
+    ///
+    /// By default, a subdirectory of the base directory is used.
+    ///
+    /// Completed files are moved into the base directory; if the directories
+    /// are on different file systems, the files are copied instead.
+    pub fn with_temp_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        let dir = dir.into();
+        self.downloads_dir = dir.join(PARTIAL_DOWNLOADS_DIR);
+        self.temp_dir = dir;
+        self
+    }
+
+    /// Limits the total size of the stored content.
+    ///
+    /// When storing content causes the limit to be exceeded, the least
+    /// recently accessed content is evicted until the storage is within the
+    /// limit. Content with an in-progress upload is never evicted.
+    ///
+    /// Accesses are recorded in an index within the base directory, which is
+    /// written when content is stored or removed and when the storage is
+    /// dropped.
+    pub fn with_max_cache_bytes(mut self, max: u64) -> Result<Self> {
+        let path = self.base_dir.join(ACCESS_INDEX_FILE);
+        let mut access = AccessIndex::default();
+        if path.is_file() {
+            let contents = fs::read_to_string(&path)
+                .with_context(|| format!("failed to read `{path}`", path = path.display()))?;
+
+            // The index only orders eviction, so an unreadable index is
+            // replaced rather than failing to open the storage
+            match serde_json::from_str(&contents) {
+                Ok(index) => access = index,
+                Err(e) => tracing::warn!(
+                    "discarding invalid content access index `{path}`: {e}",
+                    path = path.display()
+                ),
+            }
+        }
+
+        for entry in self.stored_content() {
+            let (digest, entry) = entry?;
+            let len = entry
+                .metadata()
+                .with_context(|| {
+                    format!(
+                        "failed to read metadata of `{path}`",
+                        path = entry.path().display()
+                    )
+                })?
+                .len();
+            access.insert(&digest, len);
+        }
+
+        self.access = Mutex::new(access);
+        self.max_cache_bytes = Some(max);
+        Ok(self)
+    }
+
+    /// Records an access of the given content when the content size is
+    /// limited.
+    fn touch(&self, digest: &AnyHash) {
+        if self.max_cache_bytes.is_some() {
+            self.access.lock().unwrap().touch(digest);
+        }
+    }
+
+    /// Records newly stored content when the content size is limited,
+    /// evicting the least recently accessed content if the limit is exceeded.
+    fn cache(&self, digest: &AnyHash, len: u64) -> Result<()> {
+        let Some(max) = self.max_cache_bytes else {
+            return Ok(());
+        };
+
+        let mut access = self.access.lock().unwrap();
+        access.insert(digest, len);
+        access.touch(digest);
+        if access.total > max {
+            self.evict(&mut access, max, digest)?;
+        }
+
+        self.store_access_index(&mut access)
+    }
+
+    /// Removes the given content from the access index when the content size
+    /// is limited.
+    fn uncache(&self, digests: &[AnyHash]) -> Result<()> {
+        if self.max_cache_bytes.is_none() {
+            return Ok(());
+        }
+
+        let mut access = self.access.lock().unwrap();
+        let mut removed = false;
+        for digest in digests {
+            removed |= access.remove(digest);
+        }
+
+        if removed {
+            self.store_access_index(&mut access)?;
+        }
+
+        Ok(())
+    }
+
+    fn store_access_index(&self, index: &mut AccessIndex) -> Result<()> {
+        let path = self.base_dir.join(ACCESS_INDEX_FILE);
+        let contents = serde_json::to_vec(index).with_context(|| {
+            format!(
+                "failed to serialize contents of `{path}`",
+                path = path.display()
+            )
+        })?;
+
+        write_atomic(&path, &contents)?;
+        index.dirty = false;
+        Ok(())
+    }
+
+    /// Calls the given function with the index of stored content.
+    ///
+    /// The index is loaded from its manifest on first use; if there is no
+    /// manifest, the index is built from the stored content.
+    fn with_index<T>(&self, f: impl FnOnce(&mut HashSet<AnyHash>) -> T) -> Result<T> {
+        let mut index = self.index.lock().unwrap();
+        let index = match &mut *index {
+            Some(index) => index,
+            None => index.insert(self.load_index()?),
+        };
+
+        Ok(f(index))
+    }
+
+    /// Updates the index of stored content with the given function, which
+    /// returns whether the index changed.
+    fn update_index(&self, f: impl FnOnce(&mut HashSet<AnyHash>) -> bool) -> Result<()> {
+        self.with_index(|index| {
+            if f(index) {
+                self.store_index(index)
+            } else {
+                Ok(())
+            }
+        })?
+    }
+
+    fn load_index(&self) -> Result<HashSet<AnyHash>> {
+        let path = self.base_dir.join(CONTENT_INDEX_FILE);
+        if path.is_file() {
+            let contents = fs::read_to_string(&path)
+                .with_context(|| format!("failed to read `{path}`", path = path.display()))?;
+            match serde_json::from_str(&contents) {
+                Ok(index) => return Ok(index),
+                Err(e) => tracing::warn!(
+                    "rebuilding invalid content index `{path}`: {e}",
+                    path = path.display()
+                ),
+            }
+        }
+
+        let index = self.scan_index()?;
+        self.store_index(&index)?;
+        Ok(index)
+    }
+
+    fn scan_index(&self) -> Result<HashSet<AnyHash>> {
+        self.stored_content()
+            .map(|entry| entry.map(|(digest, _)| digest))
+            .collect()
+    }
+
+    fn store_index(&self, index: &HashSet<AnyHash>) -> Result<()> {
+        let path = self.base_dir.join(CONTENT_INDEX_FILE);
+        let contents = serde_json::to_vec(index).with_context(|| {
+            format!(
+                "failed to serialize contents of `{path}`",
+                path = path.display()
+            )
+        })?;
+
+        write_atomic(&path, &contents)
+    }
+
+    /// Evicts the least recently accessed content until the stored content
+    /// is within the given size limit.
+    ///
+    /// The given content is never evicted.
+    fn evict(&self, access: &mut AccessIndex, max: u64, keep: &AnyHash) -> Result<()> {
+        let mut entries = access
+            .sizes
+            .keys()
+            .map(|digest| (access.accessed.get(digest).copied().unwrap_or(0), digest))
+            .collect::<Vec<_>>();
+        entries.sort();
+        let entries = entries
+            .into_iter()
+            .map(|(_, digest)| digest.clone())
+            .collect::<Vec<_>>();
+
+        let mut evicted = Vec::new();
+        for digest in entries {
+            if access.total <= max {
+                break;
+            }
+
+            if digest == *keep || self.pending_upload_path(&digest).is_file() {
+                continue;
+            }
+
+            let path = self.content_path(&digest);
+            match fs::remove_file(&path) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    return Err(anyhow!(e)).with_context(|| {
+                        format!("failed to delete file `{path}`", path = path.display())
+                    })
+                }
+            }
+            access.remove(&digest);
+
+            tracing::debug!("evicted content `{digest}` from storage");
+            evicted.push(digest);
+        }
+
+        self.update_index(|index| remove_all(index, &evicted))
     }
 
     fn temp_file(&self) -> Result<NamedTempFile> {
@@ -227,6 +639,118 @@ impl FileSystemContentStorage {
     fn content_path(&self, digest: &AnyHash) -> PathBuf {
         self.base_dir.join(digest.to_string().replace(':', "/"))
     }
+
+    /// Hashes the given content file and compares it against its digest.
+    ///
+    /// The modification time of intact content is remembered so that
+    /// `verify_content` does not hash it again while it is unchanged.
+    async fn verify_file(&self, digest: &AnyHash, path: &Path) -> Option<VerifyError> {
+        // The modification time is read first so that a change made while
+        // hashing is not mistaken for verified content
+        let modified = tokio::fs::metadata(path)
+            .await
+            .and_then(|metadata| metadata.modified())
+            .ok();
+
+        // Hashing is CPU-bound, so it is done on a blocking thread
+        let error = {
+            let digest = digest.clone();
+            let path = path.to_path_buf();
+            tokio::task::spawn_blocking(move || hash_file(&digest, &path))
+                .await
+                .unwrap_or_else(|e| {
+                    Some(VerifyError::Unreadable {
+                        message: e.to_string(),
+                    })
+                })
+        };
+
+        let mut verified = self.verified.lock().unwrap();
+        match (&error, modified) {
+            (None, Some(modified)) => {
+                verified.insert(digest.clone(), modified);
+            }
+            _ => {
+                verified.remove(digest);
+            }
+        }
+
+        error
+    }
+
+    fn pending_upload_path(&self, digest: &AnyHash) -> PathBuf {
+        self.base_dir.join(PENDING_UPLOADS_DIR).join(format!(
+            "{name}.json",
+            name = digest.to_string().replace(':', "-")
+        ))
+    }
+
+    fn partial_download_path(&self, digest: &AnyHash) -> PathBuf {
+        self.downloads_dir.join(format!(
+            "{name}.partial",
+            name = digest.to_string().replace(':', "-")
+        ))
+    }
+
+    /// Walks the stored content files, yielding the digest and directory
+    /// entry of each.
+    fn stored_content(&self) -> impl Iterator<Item = Result<(AnyHash, DirEntry)>> + '_ {
+        WalkDir::new(&self.base_dir)
+            .min_depth(1)
+            .max_depth(2)
+            .into_iter()
+            .filter_entry(|e| {
+                e.depth() > 1
+                    || !matches!(
+                        e.file_name().to_str(),
+                        Some(
+                            TEMP_DIRECTORY
+                                | PENDING_UPLOADS_DIR
+                                | PARTIAL_DOWNLOADS_DIR
+                                | LOCK_FILE_NAME
+                        )
+                    )
+            })
+            .filter_map(|entry| {
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(e) => {
+                        return Some(Err(anyhow!(e).context(format!(
+                            "failed to walk directory `{path}`",
+                            path = self.base_dir.display()
+                        ))))
+                    }
+                };
+
+                if entry.depth() != 2 || !entry.file_type().is_file() {
+                    return None;
+                }
+
+                // Content is stored as `<algorithm>/<hex>`; skip anything else
+                let path = entry.path();
+                let algorithm = path
+                    .parent()
+                    .and_then(Path::file_name)
+                    .and_then(OsStr::to_str)?;
+                let hex = path.file_name().and_then(OsStr::to_str)?;
+                let digest = format!("{algorithm}:{hex}").parse::<AnyHash>().ok()?;
+                Some(Ok((digest, entry)))
+            })
+    }
+}
+
+impl Drop for FileSystemContentStorage {
+    fn drop(&mut self) {
+        let Ok(mut access) = self.access.lock() else {
+            return;
+        };
+
+        if access.dirty {
+            if let Err(e) = self.store_access_index(&mut access) {
+                tracing::warn!("failed to store content access index: {e:#}");
+            }
+        }
+    }
 }
 
 #[async_trait]
@@ -234,12 +758,35 @@ impl ContentStorage for FileSystemContentStorage {
     fn content_location(&self, digest: &AnyHash) -> Option<PathBuf> {
         let path = self.content_path(digest);
         if path.is_file() {
+            self.touch(digest);
             Some(path)
         } else {
             None
         }
     }
 
+    async fn contains_content(&self, digest: &AnyHash) -> Result<bool> {
+        if self.with_index(|index| index.contains(digest))? {
+            return Ok(true);
+        }
+
+        // Content missing from the index may have been stored by a client
+        // that did not maintain it
+        if !self.content_path(digest).is_file() {
+            return Ok(false);
+        }
+
+        self.update_index(|index| index.insert(digest.clone()))?;
+        Ok(true)
+    }
+
+    async fn refresh_index(&self) -> Result<()> {
+        let index = self.scan_index()?;
+        self.store_index(&index)?;
+        *self.index.lock().unwrap() = Some(index);
+        Ok(())
+    }
+
     async fn load_content(
         &self,
         digest: &AnyHash,
@@ -249,6 +796,8 @@ impl ContentStorage for FileSystemContentStorage {
             return Ok(None);
         }
 
+        self.touch(digest);
+
         Ok(Some(Box::pin(
             ReaderStream::new(BufReader::new(
                 tokio::fs::File::open(&path)
@@ -266,17 +815,21 @@ impl ContentStorage for FileSystemContentStorage {
     ) -> Result<AnyHash> {
         let (file, path) = self.temp_file()?.into_parts();
         let mut writer = BufWriter::new(tokio::fs::File::from_std(file));
-        let mut hasher = Sha256::new();
+        let mut hasher = expected_digest
+            .map_or(HashAlgorithm::Sha256, AnyHash::algorithm)
+            .hasher();
 
+        let mut len = 0;
         while let Some(bytes) = stream.next().await.transpose()? {
             hasher.update(&bytes);
+            len += bytes.len() as u64;
             writer
                 .write_all(&bytes)
                 .await
                 .with_context(|| format!("failed to write to `{path}`", path = path.display()))?;
         }
 
-        let hash = AnyHash::from(Hash::<Sha256>::from(hasher.finalize()));
+        let hash = hasher.finalize();
 
         if let Some(expected) = expected_digest {
             if hash != *expected {
@@ -304,15 +857,279 @@ impl ContentStorage for FileSystemContentStorage {
                 })?;
             }
 
-            path.persist(&content_path).with_context(|| {
+            if let Err(e) = path.persist(&content_path) {
+                copy_unmoved(&e.path, &content_path, &e.error)?;
+            }
+        }
+
+        self.update_index(|index| index.insert(hash.clone()))?;
+        self.cache(&hash, len)?;
+
+        Ok(hash)
+    }
+
+    async fn remove_content(&self, digest: &AnyHash) -> Result<()> {
+        delete(&self.content_path(digest)).await?;
+        self.update_index(|index| index.remove(digest))?;
+        self.verified.lock().unwrap().remove(digest);
+        self.uncache(std::slice::from_ref(digest))
+    }
+
+    async fn partial_download_len(&self, digest: &AnyHash) -> Result<u64> {
+        let path = self.partial_download_path(digest);
+        match tokio::fs::metadata(&path).await {
+            Ok(metadata) => Ok(metadata.len()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+            Err(e) => Err(anyhow!(e))
+                .with_context(|| format!("failed to read `{path}`", path = path.display())),
+        }
+    }
+
+    async fn store_download(
+        &self,
+        digest: &AnyHash,
+        offset: u64,
+        mut stream: Pin<Box<dyn Stream<Item = Result<Bytes>> + Send + Sync>>,
+    ) -> Result<()> {
+        let path = self.partial_download_path(digest);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!(
+                    "failed to create directory `{path}`",
+                    path = parent.display()
+                )
+            })?;
+        }
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .await
+            .with_context(|| format!("failed to open `{path}`", path = path.display()))?;
+
+        let len = file
+            .metadata()
+            .await
+            .with_context(|| format!("failed to read `{path}`", path = path.display()))?
+            .len();
+        if offset > len {
+            bail!(
+                "cannot resume the download of content `{digest}` at offset {offset} as only {len} bytes were downloaded"
+            );
+        }
+
+        // Discard any kept bytes past the offset and append to the rest
+        file.set_len(offset)
+            .await
+            .with_context(|| format!("failed to truncate `{path}`", path = path.display()))?;
+        file.seek(SeekFrom::Start(offset))
+            .await
+            .with_context(|| format!("failed to seek `{path}`", path = path.display()))?;
+
+        let mut writer = BufWriter::new(file);
+        let mut failure = None;
+        while let Some(bytes) = stream.next().await {
+            match bytes {
+                Ok(bytes) => writer.write_all(&bytes).await.with_context(|| {
+                    format!("failed to write to `{path}`", path = path.display())
+                })?,
+                Err(e) => {
+                    failure = Some(e);
+                    break;
+                }
+            }
+        }
+
+        // Keep the bytes received even if the stream failed
+        writer
+            .flush()
+            .await
+            .with_context(|| format!("failed to write `{path}`", path = path.display()))?;
+        if let Some(e) = failure {
+            return Err(e);
+        }
+
+        drop(writer);
+
+        let mut hasher = digest.algorithm().hasher();
+        let mut len = 0;
+        let mut reader = ReaderStream::new(BufReader::new(
+            tokio::fs::File::open(&path)
+                .await
+                .with_context(|| format!("failed to open `{path}`", path = path.display()))?,
+        ));
+        while let Some(bytes) = reader.next().await {
+            let bytes =
+                bytes.with_context(|| format!("failed to read `{path}`", path = path.display()))?;
+            hasher.update(&bytes);
+            len += bytes.len() as u64;
+        }
+
+        let actual = hasher.finalize();
+        if actual != *digest {
+            fs::remove_file(&path)
+                .with_context(|| format!("failed to remove `{path}`", path = path.display()))?;
+            return Err(ClientError::ContentDigestMismatch {
+                expected: digest.clone(),
+                actual,
+            }
+            .into());
+        }
+
+        let content_path = self.content_path(digest);
+        if let Some(parent) = content_path.parent() {
+            fs::create_dir_all(parent).with_context(|| {
                 format!(
-                    "failed to persist temporary file to `{path}`",
-                    path = content_path.display()
+                    "failed to create directory `{path}`",
+                    path = parent.display()
                 )
             })?;
         }
 
-        Ok(hash)
+        if let Err(e) = fs::rename(&path, &content_path) {
+            copy_unmoved(&path, &content_path, &e)?;
+            fs::remove_file(&path)
+                .with_context(|| format!("failed to remove `{path}`", path = path.display()))?;
+        }
+
+        self.update_index(|index| index.insert(digest.clone()))?;
+        self.cache(digest, len)?;
+
+        Ok(())
+    }
+
+    async fn discard_download(&self, digest: &AnyHash) -> Result<()> {
+        delete(&self.partial_download_path(digest)).await
+    }
+
+    async fn resume_upload(
+        &self,
+        digest: &AnyHash,
+        offset: u64,
+    ) -> Result<Option<Pin<Box<dyn Stream<Item = Result<Bytes>> + Send + Sync>>>> {
+        let path = self.content_path(digest);
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        let mut file = tokio::fs::File::open(&path)
+            .await
+            .with_context(|| format!("failed to open `{path}`", path = path.display()))?;
+
+        file.seek(SeekFrom::Start(offset))
+            .await
+            .with_context(|| format!("failed to seek `{path}`", path = path.display()))?;
+
+        Ok(Some(Box::pin(
+            ReaderStream::new(BufReader::new(file)).map_err(|e| anyhow!(e)),
+        )))
+    }
+
+    async fn load_upload(&self, digest: &AnyHash) -> Result<Option<UploadInfo>> {
+        load(&self.pending_upload_path(digest)).await
+    }
+
+    async fn store_upload(&self, digest: &AnyHash, info: Option<&UploadInfo>) -> Result<()> {
+        let path = self.pending_upload_path(digest);
+        match info {
+            Some(info) => store(&path, info).await,
+            None => delete(&path).await,
+        }
+    }
+
+    async fn gc(&self, reachable: &HashSet<AnyHash>) -> Result<GcStats> {
+        let mut stats = GcStats::default();
+        let mut deleted = Vec::new();
+
+        // The storage lock is held for the lifetime of `self`, so no other
+        // client can be storing content while the directory is walked.
+        for entry in self.stored_content() {
+            let (digest, entry) = entry?;
+            let path = entry.path();
+            if reachable.contains(&digest) || self.pending_upload_path(&digest).is_file() {
+                continue;
+            }
+
+            let len = entry
+                .metadata()
+                .with_context(|| {
+                    format!("failed to read metadata of `{path}`", path = path.display())
+                })?
+                .len();
+
+            tokio::fs::remove_file(path).await.with_context(|| {
+                format!("failed to delete file `{path}`", path = path.display())
+            })?;
+
+            stats.blobs += 1;
+            stats.bytes += len;
+            deleted.push(digest);
+        }
+
+        self.update_index(|index| remove_all(index, &deleted))?;
+        self.uncache(&deleted)?;
+
+        Ok(stats)
+    }
+
+    async fn verify_all(&self, concurrency: usize) -> Result<Vec<(AnyHash, VerifyError)>> {
+        // The storage lock is held for the lifetime of the storage, so the
+        // content cannot be changed by another client while it is verified
+        let entries = self.stored_content().collect::<Result<Vec<_>>>()?;
+        Ok(futures_util::stream::iter(entries)
+            .map(|(digest, entry)| async move {
+                let error = self.verify_file(&digest, entry.path()).await?;
+                Some((digest, error))
+            })
+            .buffer_unordered(concurrency.max(1))
+            .filter_map(std::future::ready)
+            .collect()
+            .await)
+    }
+
+    async fn verify_content(&self, digest: &AnyHash, force: bool) -> Result<Option<VerifyError>> {
+        let path = self.content_path(digest);
+        if !force {
+            let modified = tokio::fs::metadata(&path)
+                .await
+                .and_then(|metadata| metadata.modified())
+                .ok();
+            if modified.is_some() && self.verified.lock().unwrap().get(digest) == modified.as_ref()
+            {
+                tracing::debug!("content `{digest}` is unchanged since it was last verified");
+                return Ok(None);
+            }
+        }
+
+        Ok(self.verify_file(digest, &path).await)
+    }
+
+    async fn cache_stats(&self) -> Result<CacheStats> {
+        let mut stats = CacheStats::default();
+        for entry in self.stored_content() {
+            let (_, entry) = entry?;
+            stats.entries += 1;
+            stats.bytes += entry
+                .metadata()
+                .with_context(|| {
+                    format!(
+                        "failed to read metadata of `{path}`",
+                        path = entry.path().display()
+                    )
+                })?
+                .len();
+        }
+
+        Ok(stats)
+    }
+
+    async fn partial_bytes(&self) -> Result<u64> {
+        let (_, temp) = dir_usage(&self.temp_dir, |_| true)?;
+        let (_, downloads) = dir_usage(&self.downloads_dir, |_| true)?;
+        Ok(temp + downloads)
     }
 }
 
@@ -333,16 +1150,115 @@ async fn load<T: for<'a> Deserialize<'a>>(path: &Path) -> Result<Option<T>> {
     })
 }
 
-async fn store(path: &Path, value: impl Serialize) -> Result<()> {
-    if let Some(parent) = path.parent() {
-        std::fs::create_dir_all(parent).with_context(|| {
+/// Loads part of the given file with the given seed.
+///
+/// The file is deserialized as it is read, so parts of the file that the
+/// seed skips are never held in memory.
+fn load_partial<S, T>(path: &Path, seed: S) -> Result<Option<T>>
+where
+    S: for<'de> DeserializeSeed<'de, Value = T>,
+{
+    if !path.is_file() {
+        return Ok(None);
+    }
+
+    let file = fs::File::open(path)
+        .with_context(|| format!("failed to read `{path}`", path = path.display()))?;
+    let mut deserializer = serde_json::Deserializer::from_reader(std::io::BufReader::new(file));
+    let value = seed
+        .deserialize(&mut deserializer)
+        .and_then(|value| deserializer.end().map(|_| value))
+        .with_context(|| {
             format!(
-                "failed to create parent directory for `{path}`",
+                "failed to deserialize contents of `{path}`",
                 path = path.display()
             )
         })?;
+
+    Ok(Some(value))
+}
+
+/// Deserializes a single field of a map with the given seed, skipping the
+/// other fields.
+///
+/// Deserializes to `None` if the map has no such field.
+struct Field<S> {
+    name: &'static str,
+    seed: S,
+}
+
+impl<'de, S: DeserializeSeed<'de>> DeserializeSeed<'de> for Field<S> {
+    type Value = Option<S::Value>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, S: DeserializeSeed<'de>> Visitor<'de> for Field<S> {
+    type Value = Option<S::Value>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a map with an optional `{name}` field", name = self.name)
     }
 
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut seed = Some(self.seed);
+        let mut value = None;
+        while let Some(key) = map.next_key::<String>()? {
+            match seed.take() {
+                Some(seed) if key == self.name => value = Some(map.next_value_seed(seed)?),
+                unused => {
+                    seed = unused;
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        Ok(value)
+    }
+}
+
+/// Deserializes a page of the values of a map of releases, skipping the
+/// other entries.
+struct Page {
+    offset: usize,
+    limit: usize,
+}
+
+impl<'de> DeserializeSeed<'de> for Page {
+    type Value = Vec<Release>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Page {
+    type Value = Vec<Release>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map of releases")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut page = Vec::new();
+        let mut index = 0;
+        while map.next_key::<IgnoredAny>()?.is_some() {
+            if index >= self.offset && page.len() < self.limit {
+                page.push(map.next_value()?);
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+
+            index += 1;
+        }
+
+        Ok(page)
+    }
+}
+
+async fn store(path: &Path, value: impl Serialize) -> Result<()> {
     let contents = serde_json::to_vec_pretty(&value).with_context(|| {
         format!(
             "failed to serialize contents of `{path}`",
@@ -350,9 +1266,110 @@ async fn store(path: &Path, value: impl Serialize) -> Result<()> {
         )
     })?;
 
-    tokio::fs::write(path, contents)
-        .await
-        .with_context(|| format!("failed to write `{path}`", path = path.display()))
+    write_atomic(path, &contents)
+}
+
+/// Writes the given contents to a file, creating its parent directory if
+/// needed.
+fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).with_context(|| {
+            format!(
+                "failed to create parent directory for `{path}`",
+                path = path.display()
+            )
+        })?;
+    }
+
+    // Write to a temporary file that is renamed over the path so that a
+    // partially written file is never observed
+    let write = || -> std::io::Result<()> {
+        let mut file = tempfile::Builder::new()
+            .prefix(".tmp")
+            .tempfile_in(path.parent().unwrap_or_else(|| Path::new(".")))?;
+        file.write_all(contents)?;
+        file.persist(path)?;
+        Ok(())
+    };
+
+    write().with_context(|| format!("failed to write `{path}`", path = path.display()))
+}
+
+/// Removes the given digests from an index of stored content, returning
+/// whether any was removed.
+fn remove_all(index: &mut HashSet<AnyHash>, digests: &[AnyHash]) -> bool {
+    let mut removed = false;
+    for digest in digests {
+        removed |= index.remove(digest);
+    }
+
+    removed
+}
+
+/// Counts the files under the given directory that match the given filter,
+/// returning the number of files and their total size in bytes.
+///
+/// Only file metadata is read; a directory that does not exist is empty.
+fn dir_usage(dir: &Path, filter: impl Fn(&DirEntry) -> bool) -> Result<(usize, u64)> {
+    let mut usage = (0, 0);
+    if !dir.exists() {
+        return Ok(usage);
+    }
+
+    for entry in WalkDir::new(dir) {
+        let entry = entry
+            .with_context(|| format!("failed to walk directory `{path}`", path = dir.display()))?;
+        if !entry.file_type().is_file() || !filter(&entry) {
+            continue;
+        }
+
+        usage.0 += 1;
+        usage.1 += entry
+            .metadata()
+            .with_context(|| {
+                format!(
+                    "failed to read metadata of `{path}`",
+                    path = entry.path().display()
+                )
+            })?
+            .len();
+    }
+
+    Ok(usage)
+}
+
+/// Hashes the given content file and compares it against its digest,
+/// blocking the current thread.
+fn hash_file(digest: &AnyHash, path: &Path) -> Option<VerifyError> {
+    let unreadable = |e: std::io::Error| VerifyError::Unreadable {
+        message: e.to_string(),
+    };
+
+    let mut file = match fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) => return Some(unreadable(e)),
+    };
+    let mut hasher = digest.algorithm().hasher();
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        match file.read(&mut buf) {
+            Ok(0) => break,
+            Ok(len) => hasher.update(&buf[..len]),
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Some(unreadable(e)),
+        }
+    }
+
+    let actual = hasher.finalize();
+    (actual != *digest).then_some(VerifyError::DigestMismatch { actual })
+}
+
+/// Determines if the given directory entry is a hidden file.
+fn is_hidden(entry: &DirEntry) -> bool {
+    entry
+        .file_name()
+        .to_str()
+        .map_or(false, |name| name.starts_with('.'))
 }
 
 async fn delete(path: &Path) -> Result<()> {
@@ -364,3 +1381,41 @@ async fn delete(path: &Path) -> Result<()> {
 
     Ok(())
 }
+
+/// Copies a file that could not be moved to the given path, such as when the
+/// paths are on different file systems.
+///
+/// The copy is written next to the destination and then renamed so that it
+/// appears atomically.
+fn copy_unmoved(from: &Path, to: &Path, error: &std::io::Error) -> Result<()> {
+    tracing::warn!(
+        "failed to move `{from}` to `{to}` ({error}); copying it instead",
+        from = from.display(),
+        to = to.display()
+    );
+
+    let parent = to.parent().unwrap_or_else(|| Path::new("."));
+    let mut file = tempfile::Builder::new()
+        .prefix(".")
+        .tempfile_in(parent)
+        .with_context(|| {
+            format!(
+                "failed to create temporary file in `{path}`",
+                path = parent.display()
+            )
+        })?;
+    std::io::copy(
+        &mut fs::File::open(from)
+            .with_context(|| format!("failed to open `{path}`", path = from.display()))?,
+        &mut file,
+    )
+    .with_context(|| format!("failed to copy `{path}`", path = from.display()))?;
+    file.persist(to).with_context(|| {
+        format!(
+            "failed to persist temporary file to `{path}`",
+            path = to.display()
+        )
+    })?;
+
+    Ok(())
+}
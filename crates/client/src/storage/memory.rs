@@ -0,0 +1,348 @@
+//! A module for in-memory client storage.
+
+use super::{
+    CacheStats, ContentStorage, GcStats, OperatorInfo, PackageInfo, PublishInfo, RegistryStats,
+    RegistryStorage, UploadInfo, VerifyError,
+};
+use crate::ClientError;
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use bytes::{Bytes, BytesMut};
+use futures_util::{Stream, StreamExt};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    pin::Pin,
+    sync::RwLock,
+};
+use warg_crypto::{
+    hash::{AnyHash, HashAlgorithm},
+    signing::PublicKey,
+};
+use warg_protocol::{
+    registry::{PackageId, TimestampedCheckpoint},
+    SerdeEnvelope,
+};
+
+/// Represents a registry storage that keeps package logs and checkpoints in
+/// memory.
+///
+/// The storage is empty when created and its contents are lost when it is
+/// dropped; it is primarily intended for tests and for short-lived clients.
+#[derive(Default)]
+pub struct InMemoryRegistryStorage {
+    checkpoint: RwLock<Option<SerdeEnvelope<TimestampedCheckpoint>>>,
+    operator: RwLock<Option<OperatorInfo>>,
+    registry_key: RwLock<Option<PublicKey>>,
+    packages: RwLock<HashMap<PackageId, PackageInfo>>,
+    publish: RwLock<Option<PublishInfo>>,
+}
+
+impl InMemoryRegistryStorage {
+    /// Creates a new, empty in-memory registry storage.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl RegistryStorage for InMemoryRegistryStorage {
+    async fn load_checkpoint(&self) -> Result<Option<SerdeEnvelope<TimestampedCheckpoint>>> {
+        Ok(self.checkpoint.read().unwrap().clone())
+    }
+
+    async fn store_checkpoint(
+        &self,
+        ts_checkpoint: &SerdeEnvelope<TimestampedCheckpoint>,
+    ) -> Result<()> {
+        *self.checkpoint.write().unwrap() = Some(ts_checkpoint.clone());
+        Ok(())
+    }
+
+    async fn load_operator(&self) -> Result<Option<OperatorInfo>> {
+        Ok(self.operator.read().unwrap().clone())
+    }
+
+    async fn store_operator(&self, operator: OperatorInfo) -> Result<()> {
+        *self.operator.write().unwrap() = Some(operator);
+        Ok(())
+    }
+
+    async fn load_registry_key(&self) -> Result<Option<PublicKey>> {
+        Ok(self.registry_key.read().unwrap().clone())
+    }
+
+    async fn store_registry_key(&self, key: Option<&PublicKey>) -> Result<()> {
+        *self.registry_key.write().unwrap() = key.cloned();
+        Ok(())
+    }
+
+    async fn load_packages(&self) -> Result<Vec<PackageInfo>> {
+        Ok(self.packages.read().unwrap().values().cloned().collect())
+    }
+
+    async fn load_package(&self, package: &PackageId) -> Result<Option<PackageInfo>> {
+        Ok(self.packages.read().unwrap().get(package).cloned())
+    }
+
+    async fn store_package(&self, info: &PackageInfo) -> Result<()> {
+        self.packages
+            .write()
+            .unwrap()
+            .insert(info.id.clone(), info.clone());
+        Ok(())
+    }
+
+    async fn remove_package(&self, package: &PackageId) -> Result<()> {
+        self.packages.write().unwrap().remove(package);
+        Ok(())
+    }
+
+    async fn load_publish(&self) -> Result<Option<PublishInfo>> {
+        Ok(self.publish.read().unwrap().clone())
+    }
+
+    async fn store_publish(&self, info: Option<&PublishInfo>) -> Result<()> {
+        *self.publish.write().unwrap() = info.cloned();
+        Ok(())
+    }
+
+    async fn registry_stats(&self) -> Result<RegistryStats> {
+        // Logs held in memory use no disk space
+        Ok(RegistryStats {
+            packages: self.packages.read().unwrap().len(),
+            bytes: 0,
+        })
+    }
+}
+
+/// Represents a content storage that keeps content in memory.
+///
+/// As content is not stored on disk, the content has no location; this
+/// storage is primarily intended for tests and for environments without a
+/// writable file system.
+///
+/// Content is never evicted from the storage.
+#[derive(Default)]
+pub struct InMemoryContentStorage {
+    content: RwLock<HashMap<AnyHash, Bytes>>,
+    uploads: RwLock<HashMap<AnyHash, UploadInfo>>,
+    downloads: RwLock<HashMap<AnyHash, BytesMut>>,
+}
+
+impl InMemoryContentStorage {
+    /// Creates a new, empty in-memory content storage.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn stream(
+        bytes: Option<Bytes>,
+    ) -> Option<Pin<Box<dyn Stream<Item = Result<Bytes>> + Send + Sync>>> {
+        bytes.map(|bytes| {
+            Box::pin(futures_util::stream::once(async move { Ok(bytes) }))
+                as Pin<Box<dyn Stream<Item = Result<Bytes>> + Send + Sync>>
+        })
+    }
+}
+
+#[async_trait]
+impl ContentStorage for InMemoryContentStorage {
+    fn content_location(&self, _digest: &AnyHash) -> Option<PathBuf> {
+        None
+    }
+
+    async fn contains_content(&self, digest: &AnyHash) -> Result<bool> {
+        Ok(self.content.read().unwrap().contains_key(digest))
+    }
+
+    async fn load_content(
+        &self,
+        digest: &AnyHash,
+    ) -> Result<Option<Pin<Box<dyn Stream<Item = Result<Bytes>> + Send + Sync>>>> {
+        Ok(Self::stream(
+            self.content.read().unwrap().get(digest).cloned(),
+        ))
+    }
+
+    async fn store_content(
+        &self,
+        mut stream: Pin<Box<dyn Stream<Item = Result<Bytes>> + Send + Sync>>,
+        expected_digest: Option<&AnyHash>,
+    ) -> Result<AnyHash> {
+        let mut buffer = BytesMut::new();
+        let mut hasher = expected_digest
+            .map_or(HashAlgorithm::Sha256, AnyHash::algorithm)
+            .hasher();
+
+        while let Some(bytes) = stream.next().await.transpose()? {
+            hasher.update(&bytes);
+            buffer.extend_from_slice(&bytes);
+        }
+
+        let hash = hasher.finalize();
+
+        if let Some(expected) = expected_digest {
+            if hash != *expected {
+                bail!(
+                    "stored content has digest `{hash}` but a digest of `{expected}` was expected",
+                );
+            }
+        }
+
+        self.content
+            .write()
+            .unwrap()
+            .entry(hash.clone())
+            .or_insert_with(|| buffer.freeze());
+
+        Ok(hash)
+    }
+
+    async fn remove_content(&self, digest: &AnyHash) -> Result<()> {
+        self.content.write().unwrap().remove(digest);
+        Ok(())
+    }
+
+    async fn partial_download_len(&self, digest: &AnyHash) -> Result<u64> {
+        Ok(self
+            .downloads
+            .read()
+            .unwrap()
+            .get(digest)
+            .map_or(0, |bytes| bytes.len() as u64))
+    }
+
+    async fn store_download(
+        &self,
+        digest: &AnyHash,
+        offset: u64,
+        mut stream: Pin<Box<dyn Stream<Item = Result<Bytes>> + Send + Sync>>,
+    ) -> Result<()> {
+        let mut buffer = self
+            .downloads
+            .write()
+            .unwrap()
+            .remove(digest)
+            .unwrap_or_default();
+        if offset > buffer.len() as u64 {
+            bail!(
+                "cannot resume the download of content `{digest}` at offset {offset} as only {len} bytes were downloaded",
+                len = buffer.len()
+            );
+        }
+
+        buffer.truncate(offset as usize);
+        while let Some(bytes) = stream.next().await {
+            match bytes {
+                Ok(bytes) => buffer.extend_from_slice(&bytes),
+                Err(e) => {
+                    self.downloads
+                        .write()
+                        .unwrap()
+                        .insert(digest.clone(), buffer);
+                    return Err(e);
+                }
+            }
+        }
+
+        let actual = digest.algorithm().digest(&buffer);
+        if actual != *digest {
+            return Err(ClientError::ContentDigestMismatch {
+                expected: digest.clone(),
+                actual,
+            }
+            .into());
+        }
+
+        self.content
+            .write()
+            .unwrap()
+            .entry(digest.clone())
+            .or_insert_with(|| buffer.freeze());
+
+        Ok(())
+    }
+
+    async fn discard_download(&self, digest: &AnyHash) -> Result<()> {
+        self.downloads.write().unwrap().remove(digest);
+        Ok(())
+    }
+
+    async fn resume_upload(
+        &self,
+        digest: &AnyHash,
+        offset: u64,
+    ) -> Result<Option<Pin<Box<dyn Stream<Item = Result<Bytes>> + Send + Sync>>>> {
+        Ok(Self::stream(self.content.read().unwrap().get(digest).map(
+            |bytes| bytes.slice((offset as usize).min(bytes.len())..),
+        )))
+    }
+
+    async fn load_upload(&self, digest: &AnyHash) -> Result<Option<UploadInfo>> {
+        Ok(self.uploads.read().unwrap().get(digest).cloned())
+    }
+
+    async fn store_upload(&self, digest: &AnyHash, info: Option<&UploadInfo>) -> Result<()> {
+        let mut uploads = self.uploads.write().unwrap();
+        match info {
+            Some(info) => {
+                uploads.insert(digest.clone(), info.clone());
+            }
+            None => {
+                uploads.remove(digest);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn gc(&self, reachable: &HashSet<AnyHash>) -> Result<GcStats> {
+        let uploads = self.uploads.read().unwrap();
+        let mut stats = GcStats::default();
+        self.content.write().unwrap().retain(|digest, bytes| {
+            if reachable.contains(digest) || uploads.contains_key(digest) {
+                return true;
+            }
+
+            stats.blobs += 1;
+            stats.bytes += bytes.len() as u64;
+            false
+        });
+
+        Ok(stats)
+    }
+
+    async fn verify_all(&self, _concurrency: usize) -> Result<Vec<(AnyHash, VerifyError)>> {
+        // Content in memory is hashed without blocking on I/O
+        Ok(self
+            .content
+            .read()
+            .unwrap()
+            .iter()
+            .filter_map(|(digest, bytes)| {
+                let actual = digest.algorithm().digest(bytes);
+                (actual != *digest)
+                    .then(|| (digest.clone(), VerifyError::DigestMismatch { actual }))
+            })
+            .collect())
+    }
+
+    async fn cache_stats(&self) -> Result<CacheStats> {
+        let content = self.content.read().unwrap();
+        Ok(CacheStats {
+            entries: content.len(),
+            bytes: content.values().map(|bytes| bytes.len() as u64).sum(),
+        })
+    }
+
+    async fn partial_bytes(&self) -> Result<u64> {
+        Ok(self
+            .downloads
+            .read()
+            .unwrap()
+            .values()
+            .map(|bytes| bytes.len() as u64)
+            .sum())
+    }
+}
@@ -0,0 +1,65 @@
+//! A module for handling downloaded content that fails verification.
+
+use warg_crypto::hash::AnyHash;
+
+/// Represents the decision of a [`VerificationFailureHandler`] on how to
+/// proceed with a download that failed verification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RetryDecision {
+    /// Fail the download with [`ClientError::ContentDigestMismatch`].
+    ///
+    /// [`ClientError::ContentDigestMismatch`]: crate::ClientError::ContentDigestMismatch
+    GiveUp,
+    /// Download the content again from the content sources of its record.
+    Retry,
+    /// Download the content again from the given URL, such as a mirror.
+    RetryFrom(String),
+}
+
+/// A trait implemented by types that decide whether a download of content
+/// that failed verification is retried.
+///
+/// Handlers are invoked from the async runtime and therefore must be `Send`
+/// and `Sync`.
+pub trait VerificationFailureHandler: Send + Sync {
+    /// Called when downloaded content does not match its digest.
+    ///
+    /// `source` is the URL the content was downloaded from and `attempt` is
+    /// the number of the failed attempt, starting at 1. A handler that keeps
+    /// retrying is responsible for giving up eventually.
+    fn on_verification_failure(
+        &self,
+        digest: &AnyHash,
+        source: &str,
+        attempt: u32,
+    ) -> RetryDecision;
+}
+
+/// A verification failure handler that never retries.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoRetry;
+
+impl VerificationFailureHandler for NoRetry {
+    fn on_verification_failure(
+        &self,
+        _digest: &AnyHash,
+        _source: &str,
+        _attempt: u32,
+    ) -> RetryDecision {
+        RetryDecision::GiveUp
+    }
+}
+
+impl<F> VerificationFailureHandler for F
+where
+    F: Fn(&AnyHash, &str, u32) -> RetryDecision + Send + Sync,
+{
+    fn on_verification_failure(
+        &self,
+        digest: &AnyHash,
+        source: &str,
+        attempt: u32,
+    ) -> RetryDecision {
+        self(digest, source, attempt)
+    }
+}
@@ -40,6 +40,11 @@ pub trait Signable: Encode {
         private_key.sign(&prefixed_content)
     }
 
+    fn sign_with(&self, signer: &dyn signing::Signer) -> anyhow::Result<signing::Signature> {
+        let prefixed_content = [Self::PREFIX, b":", self.encode().as_slice()].concat();
+        signer.sign(&prefixed_content)
+    }
+
     fn verify(
         public_key: &signing::PublicKey,
         msg: &[u8],
@@ -8,10 +8,12 @@ use crate::hash::HashAlgorithm;
 mod private_key;
 mod public_key;
 mod signature;
+mod signer;
 
 pub use self::private_key::{PrivateKey, PrivateKeyParseError, SignatureError};
 pub use self::public_key::{KeyID, PublicKey, PublicKeyParseError};
 pub use self::signature::{Signature, SignatureParseError};
+pub use self::signer::Signer;
 
 /// A signature algorithm supported by WARG
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
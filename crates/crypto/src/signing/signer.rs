@@ -0,0 +1,35 @@
+use super::{PrivateKey, PublicKey, Signature};
+use anyhow::Result;
+use std::sync::Arc;
+
+/// A signer of messages.
+///
+/// Unlike a [`PrivateKey`], a signer may hold its private key outside of
+/// the process, such as in a hardware token.
+pub trait Signer: Send + Sync {
+    /// Gets the public key that verifies the signer's signatures.
+    fn public_key(&self) -> PublicKey;
+
+    /// Signs the given message.
+    fn sign(&self, msg: &[u8]) -> Result<Signature>;
+}
+
+impl Signer for PrivateKey {
+    fn public_key(&self) -> PublicKey {
+        PrivateKey::public_key(self)
+    }
+
+    fn sign(&self, msg: &[u8]) -> Result<Signature> {
+        Ok(PrivateKey::sign(self, msg)?)
+    }
+}
+
+impl<T: Signer + ?Sized> Signer for Arc<T> {
+    fn public_key(&self) -> PublicKey {
+        self.as_ref().public_key()
+    }
+
+    fn sign(&self, msg: &[u8]) -> Result<Signature> {
+        self.as_ref().sign(msg)
+    }
+}
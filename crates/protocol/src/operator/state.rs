@@ -132,6 +132,19 @@ impl LogState {
         self.keys.get(key_id)
     }
 
+    /// Determines if the given key id currently has the given permission.
+    ///
+    /// Returns `false` if the key id is not recognized.
+    pub fn key_has_permission(
+        &self,
+        key_id: &signing::KeyID,
+        permission: model::Permission,
+    ) -> bool {
+        self.permissions
+            .get(key_id)
+            .map_or(false, |permissions| permissions.contains(&permission))
+    }
+
     fn initialized(&self) -> bool {
         // The package log is initialized if the hash algorithm is set
         self.algorithm.is_some()
@@ -450,6 +463,59 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_key_has_permission() {
+        let (alice_pub, alice_priv) = generate_p256_pair();
+        let (bob_pub, _) = generate_p256_pair();
+        let bob_id = bob_pub.fingerprint();
+
+        let timestamp = SystemTime::now();
+        let record = model::OperatorRecord {
+            prev: None,
+            version: 0,
+            timestamp,
+            entries: vec![
+                model::OperatorEntry::Init {
+                    hash_algorithm: HashAlgorithm::Sha256,
+                    key: alice_pub.clone(),
+                },
+                model::OperatorEntry::GrantFlat {
+                    key: bob_pub,
+                    permissions: vec![model::Permission::Commit],
+                },
+            ],
+        };
+
+        let envelope =
+            ProtoEnvelope::signed_contents(&alice_priv, record).expect("failed to sign envelope");
+        let mut validator = LogState::default();
+        validator.validate(&envelope).unwrap();
+
+        assert!(validator.key_has_permission(&alice_pub.fingerprint(), model::Permission::Commit));
+        assert!(validator.key_has_permission(&bob_id, model::Permission::Commit));
+
+        let record = model::OperatorRecord {
+            prev: Some(RecordId::operator_record::<Sha256>(&envelope)),
+            version: 0,
+            timestamp,
+            entries: vec![model::OperatorEntry::RevokeFlat {
+                key_id: bob_id.clone(),
+                permissions: vec![model::Permission::Commit],
+            }],
+        };
+
+        let envelope =
+            ProtoEnvelope::signed_contents(&alice_priv, record).expect("failed to sign envelope");
+        validator.validate(&envelope).unwrap();
+
+        // A revoked key is still known but no longer has the permission
+        assert!(validator.public_key(&bob_id).is_some());
+        assert!(!validator.key_has_permission(&bob_id, model::Permission::Commit));
+
+        let (carol_pub, _) = generate_p256_pair();
+        assert!(!validator.key_has_permission(&carol_pub.fingerprint(), model::Permission::Commit));
+    }
+
     #[test]
     fn test_rollback() {
         let (alice_pub, alice_priv) = generate_p256_pair();
@@ -10,7 +10,7 @@ mod model;
 mod state;
 
 pub use model::{PackageEntry, PackageRecord, Permission};
-pub use state::{LogState, Release, ReleaseState, ValidationError};
+pub use state::{Deprecation, Head, LogState, Release, ReleaseState, ValidationError};
 
 /// The currently supported package protocol version.
 pub const PACKAGE_RECORD_VERSION: u32 = 0;
@@ -93,6 +93,17 @@ impl TryFrom<protobuf::PackageEntry> for model::PackageEntry {
             Contents::Yank(yank) => model::PackageEntry::Yank {
                 version: yank.version.parse()?,
             },
+            Contents::Unyank(unyank) => model::PackageEntry::Unyank {
+                version: unyank.version.parse()?,
+            },
+            Contents::Deprecate(deprecate) => model::PackageEntry::Deprecate {
+                version: deprecate.version.parse()?,
+                message: deprecate.message,
+                superseded_by: deprecate
+                    .superseded_by
+                    .map(|version| version.parse())
+                    .transpose()?,
+            },
         };
         Ok(output)
     }
@@ -181,6 +192,18 @@ impl<'a> From<&'a model::PackageEntry> for protobuf::PackageEntry {
             model::PackageEntry::Yank { version } => Contents::Yank(protobuf::PackageYank {
                 version: version.to_string(),
             }),
+            model::PackageEntry::Unyank { version } => Contents::Unyank(protobuf::PackageUnyank {
+                version: version.to_string(),
+            }),
+            model::PackageEntry::Deprecate {
+                version,
+                message,
+                superseded_by,
+            } => Contents::Deprecate(protobuf::PackageDeprecate {
+                version: version.to_string(),
+                message: message.clone(),
+                superseded_by: superseded_by.as_ref().map(ToString::to_string),
+            }),
         };
         let contents = Some(contents);
         protobuf::PackageEntry { contents }
@@ -95,6 +95,17 @@ pub enum PackageEntry {
     /// Yank a version of a package.
     /// The version must have been released and not yanked.
     Yank { version: Version },
+    /// Unyank a version of a package.
+    /// The version must have been released and yanked.
+    Unyank { version: Version },
+    /// Deprecate a version of a package.
+    /// The version must have been released; unlike a yank, a deprecated
+    /// version is still considered when resolving version requirements.
+    Deprecate {
+        version: Version,
+        message: String,
+        superseded_by: Option<Version>,
+    },
 }
 
 impl PackageEntry {
@@ -103,7 +114,9 @@ impl PackageEntry {
         match self {
             Self::Init { .. } | Self::GrantFlat { .. } | Self::RevokeFlat { .. } => None,
             Self::Release { .. } => Some(Permission::Release),
-            Self::Yank { .. } => Some(Permission::Yank),
+            Self::Yank { .. } | Self::Unyank { .. } | Self::Deprecate { .. } => {
+                Some(Permission::Yank)
+            }
         }
     }
 
@@ -38,12 +38,27 @@ pub enum ValidationError {
     #[error("an entry attempted to release version {version} which is already released")]
     ReleaseOfReleased { version: Version },
 
+    #[error("content does not match the released digest `{digest}`")]
+    ContentDigestMismatch { digest: AnyHash },
+
     #[error("an entry attempted to yank version {version} which had not yet been released")]
     YankOfUnreleased { version: Version },
 
     #[error("an entry attempted to yank version {version} which is already yanked")]
     YankOfYanked { version: Version },
 
+    #[error("an entry attempted to unyank version {version} which had not yet been released")]
+    UnyankOfUnreleased { version: Version },
+
+    #[error("an entry attempted to unyank version {version} which is not yanked")]
+    UnyankOfUnyanked { version: Version },
+
+    #[error("an entry attempted to unyank version {version} whose released content is unknown")]
+    UnyankOfUnknownContent { version: Version },
+
+    #[error("an entry attempted to deprecate version {version} which had not yet been released")]
+    DeprecateOfUnreleased { version: Version },
+
     #[error("unable to verify signature")]
     SignatureError(#[from] signing::SignatureError),
 
@@ -70,7 +85,7 @@ pub enum ValidationError {
 }
 
 /// Represents the current state of a release.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "status", rename_all = "camelCase")]
 pub enum ReleaseState {
     /// The release is currently available.
@@ -80,6 +95,12 @@ pub enum ReleaseState {
     },
     /// The release has been yanked.
     Yanked {
+        /// The content digest associated with the release.
+        ///
+        /// This is retained so that the release may be unyanked; it is `None`
+        /// for state stored before yanked content was retained.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        content: Option<AnyHash>,
         /// The key id that yanked the package.
         by: signing::KeyID,
         /// The timestamp of the yank.
@@ -88,6 +109,47 @@ pub enum ReleaseState {
     },
 }
 
+// The content retained by a yanked release is not part of its state: it is
+// not present in state stored before it was retained, so it is not compared.
+impl PartialEq for ReleaseState {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Released { content: a }, Self::Released { content: b }) => a == b,
+            (
+                Self::Yanked {
+                    by: a_by,
+                    timestamp: a_timestamp,
+                    ..
+                },
+                Self::Yanked {
+                    by: b_by,
+                    timestamp: b_timestamp,
+                    ..
+                },
+            ) => a_by == b_by && a_timestamp == b_timestamp,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for ReleaseState {}
+
+/// Represents the deprecation of a release.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Deprecation {
+    /// The message explaining the deprecation.
+    pub message: String,
+    /// The version that supersedes the deprecated release.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub superseded_by: Option<Version>,
+    /// The key id that deprecated the release.
+    pub by: signing::KeyID,
+    /// The timestamp of the deprecation.
+    #[serde(with = "crate::timestamp")]
+    pub timestamp: SystemTime,
+}
+
 /// Represents information about a release.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -103,6 +165,11 @@ pub struct Release {
     pub timestamp: SystemTime,
     /// The current state of the release.
     pub state: ReleaseState,
+    /// The deprecation of the release.
+    ///
+    /// This is `None` if the release has not been deprecated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deprecation: Option<Deprecation>,
 }
 
 impl Release {
@@ -111,6 +178,11 @@ impl Release {
         matches!(self.state, ReleaseState::Yanked { .. })
     }
 
+    /// Determines if the release has been deprecated.
+    pub fn deprecated(&self) -> bool {
+        self.deprecation.is_some()
+    }
+
     /// Gets the content associated with the release.
     ///
     /// Returns `None` if the release has been yanked.
@@ -120,6 +192,20 @@ impl Release {
             ReleaseState::Yanked { .. } => None,
         }
     }
+
+    /// Gets the content the release was published with.
+    ///
+    /// Unlike [`Release::content`], the content is returned even if the
+    /// release has been yanked.
+    ///
+    /// Returns `None` if the release was yanked in state stored before
+    /// yanked content was retained.
+    pub fn released_content(&self) -> Option<&AnyHash> {
+        match &self.state {
+            ReleaseState::Released { content } => Some(content),
+            ReleaseState::Yanked { content, .. } => content.as_ref(),
+        }
+    }
 }
 
 /// Information about the current head of the package log.
@@ -224,6 +310,16 @@ impl LogState {
         self.keys.get(key_id)
     }
 
+    /// Gets the permissions of the given key id.
+    ///
+    /// Returns an empty iterator if the key id has no permissions.
+    pub fn key_permissions(
+        &self,
+        key_id: &signing::KeyID,
+    ) -> impl Iterator<Item = model::Permission> + '_ {
+        self.permissions.get(key_id).into_iter().flatten().copied()
+    }
+
     fn initialized(&self) -> bool {
         // The package log is initialized if the hash algorithm is set
         self.algorithm.is_some()
@@ -371,6 +467,18 @@ impl LogState {
                 model::PackageEntry::Yank { version } => {
                     self.validate_yank_entry(signer_key_id, timestamp, version)?
                 }
+                model::PackageEntry::Unyank { version } => self.validate_unyank_entry(version)?,
+                model::PackageEntry::Deprecate {
+                    version,
+                    message,
+                    superseded_by,
+                } => self.validate_deprecate_entry(
+                    signer_key_id,
+                    timestamp,
+                    version,
+                    message,
+                    superseded_by,
+                )?,
             }
         }
 
@@ -475,6 +583,7 @@ impl LogState {
                     state: ReleaseState::Released {
                         content: content.clone(),
                     },
+                    deprecation: None,
                 });
             }
         }
@@ -489,12 +598,13 @@ impl LogState {
         version: &Version,
     ) -> Result<(), ValidationError> {
         match self.releases.get_mut(version) {
-            Some(e) => match e.state {
+            Some(e) => match &e.state {
                 ReleaseState::Yanked { .. } => Err(ValidationError::YankOfYanked {
                     version: version.clone(),
                 }),
-                ReleaseState::Released { .. } => {
+                ReleaseState::Released { content } => {
                     e.state = ReleaseState::Yanked {
+                        content: Some(content.clone()),
                         by: signer_key_id.clone(),
                         timestamp,
                     };
@@ -507,6 +617,58 @@ impl LogState {
         }
     }
 
+    fn validate_unyank_entry(&mut self, version: &Version) -> Result<(), ValidationError> {
+        match self.releases.get_mut(version) {
+            Some(e) => match &e.state {
+                ReleaseState::Released { .. } => Err(ValidationError::UnyankOfUnyanked {
+                    version: version.clone(),
+                }),
+                ReleaseState::Yanked {
+                    content: Some(content),
+                    ..
+                } => {
+                    e.state = ReleaseState::Released {
+                        content: content.clone(),
+                    };
+                    Ok(())
+                }
+                ReleaseState::Yanked { content: None, .. } => {
+                    Err(ValidationError::UnyankOfUnknownContent {
+                        version: version.clone(),
+                    })
+                }
+            },
+            None => Err(ValidationError::UnyankOfUnreleased {
+                version: version.clone(),
+            }),
+        }
+    }
+
+    fn validate_deprecate_entry(
+        &mut self,
+        signer_key_id: &signing::KeyID,
+        timestamp: SystemTime,
+        version: &Version,
+        message: &str,
+        superseded_by: &Option<Version>,
+    ) -> Result<(), ValidationError> {
+        match self.releases.get_mut(version) {
+            Some(e) => {
+                // A later deprecation replaces an earlier one
+                e.deprecation = Some(Deprecation {
+                    message: message.to_string(),
+                    superseded_by: superseded_by.clone(),
+                    by: signer_key_id.clone(),
+                    timestamp,
+                });
+                Ok(())
+            }
+            None => Err(ValidationError::DeprecateOfUnreleased {
+                version: version.clone(),
+            }),
+        }
+    }
+
     fn check_key_permissions(
         &self,
         key_id: &signing::KeyID,
@@ -683,7 +845,8 @@ mod tests {
                 timestamp: timestamp1,
                 state: ReleaseState::Released {
                     content: content.clone()
-                }
+                },
+                deprecation: None,
             })
         );
         assert!(validator
@@ -696,7 +859,10 @@ mod tests {
                 version: Version::new(1, 1, 0),
                 by: bob_id.clone(),
                 timestamp: timestamp1,
-                state: ReleaseState::Released { content }
+                state: ReleaseState::Released {
+                    content: content.clone()
+                },
+                deprecation: None,
             }]
         );
 
@@ -731,9 +897,11 @@ mod tests {
                 by: bob_id.clone(),
                 timestamp: timestamp1,
                 state: ReleaseState::Yanked {
+                    content: Some(content.clone()),
                     by: alice_id.clone(),
                     timestamp: timestamp2
-                }
+                },
+                deprecation: None,
             }]
         );
 
@@ -760,9 +928,11 @@ mod tests {
                         by: bob_id.clone(),
                         timestamp: timestamp1,
                         state: ReleaseState::Yanked {
+                            content: Some(content),
                             by: alice_id.clone(),
                             timestamp: timestamp2
-                        }
+                        },
+                        deprecation: None,
                     }
                 )]),
                 keys: IndexMap::from([(alice_id, alice_pub), (bob_id, bob_pub),]),
@@ -770,6 +940,194 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_validate_unyank() {
+        let (alice_pub, alice_priv) = generate_p256_pair();
+        let version = Version::new(1, 0, 0);
+        let content = HashAlgorithm::Sha256.digest(&[0, 1, 2, 3]);
+        let mut validator = LogState::default();
+
+        let timestamp0 = SystemTime::now();
+        let record0 = model::PackageRecord {
+            prev: None,
+            version: PACKAGE_RECORD_VERSION,
+            timestamp: timestamp0,
+            entries: vec![
+                model::PackageEntry::Init {
+                    hash_algorithm: HashAlgorithm::Sha256,
+                    key: alice_pub,
+                },
+                model::PackageEntry::Release {
+                    version: version.clone(),
+                    content: content.clone(),
+                },
+                model::PackageEntry::Yank {
+                    version: version.clone(),
+                },
+            ],
+        };
+        let envelope0 = ProtoEnvelope::signed_contents(&alice_priv, record0).unwrap();
+        validator.validate(&envelope0).unwrap();
+
+        let release = validator.release(&version).unwrap();
+        assert!(release.yanked());
+        assert_eq!(release.content(), None);
+        assert_eq!(release.released_content(), Some(&content));
+
+        // Unyanking restores the release's content
+        let timestamp1 = timestamp0 + Duration::from_secs(1);
+        let record1 = model::PackageRecord {
+            prev: Some(RecordId::package_record::<Sha256>(&envelope0)),
+            version: PACKAGE_RECORD_VERSION,
+            timestamp: timestamp1,
+            entries: vec![model::PackageEntry::Unyank {
+                version: version.clone(),
+            }],
+        };
+        let envelope1 = ProtoEnvelope::signed_contents(&alice_priv, record1).unwrap();
+        validator.validate(&envelope1).unwrap();
+
+        let release = validator.find_latest_release(&"^1".parse().unwrap());
+        assert_eq!(release.and_then(Release::content), Some(&content));
+
+        // Unyanking a release that is not yanked is an error
+        let record2 = model::PackageRecord {
+            prev: Some(RecordId::package_record::<Sha256>(&envelope1)),
+            version: PACKAGE_RECORD_VERSION,
+            timestamp: timestamp1 + Duration::from_secs(1),
+            entries: vec![model::PackageEntry::Unyank {
+                version: version.clone(),
+            }],
+        };
+        let envelope2 = ProtoEnvelope::signed_contents(&alice_priv, record2).unwrap();
+        assert!(matches!(
+            validator.validate(&envelope2),
+            Err(ValidationError::UnyankOfUnyanked { version: v }) if v == version
+        ));
+    }
+
+    #[test]
+    fn test_validate_unyank_of_unknown_content() {
+        let (alice_pub, alice_priv) = generate_p256_pair();
+        let version = Version::new(1, 0, 0);
+        let mut validator = LogState::default();
+
+        let timestamp0 = SystemTime::now();
+        let record0 = model::PackageRecord {
+            prev: None,
+            version: PACKAGE_RECORD_VERSION,
+            timestamp: timestamp0,
+            entries: vec![
+                model::PackageEntry::Init {
+                    hash_algorithm: HashAlgorithm::Sha256,
+                    key: alice_pub,
+                },
+                model::PackageEntry::Release {
+                    version: version.clone(),
+                    content: HashAlgorithm::Sha256.digest(&[0, 1, 2, 3]),
+                },
+                model::PackageEntry::Yank {
+                    version: version.clone(),
+                },
+            ],
+        };
+        let envelope0 = ProtoEnvelope::signed_contents(&alice_priv, record0).unwrap();
+        validator.validate(&envelope0).unwrap();
+
+        // State stored before yanked content was retained has none
+        let mut state = serde_json::to_value(&validator).unwrap();
+        state["releases"]["1.0.0"]["state"]
+            .as_object_mut()
+            .unwrap()
+            .remove("content");
+        let mut validator: LogState = serde_json::from_value(state).unwrap();
+        assert_eq!(
+            validator.release(&version).unwrap().released_content(),
+            None
+        );
+
+        let record1 = model::PackageRecord {
+            prev: Some(RecordId::package_record::<Sha256>(&envelope0)),
+            version: PACKAGE_RECORD_VERSION,
+            timestamp: timestamp0 + Duration::from_secs(1),
+            entries: vec![model::PackageEntry::Unyank {
+                version: version.clone(),
+            }],
+        };
+        let envelope1 = ProtoEnvelope::signed_contents(&alice_priv, record1).unwrap();
+        assert!(matches!(
+            validator.validate(&envelope1),
+            Err(ValidationError::UnyankOfUnknownContent { version: v }) if v == version
+        ));
+    }
+
+    #[test]
+    fn test_validate_deprecate() {
+        let (alice_pub, alice_priv) = generate_p256_pair();
+        let alice_id = alice_pub.fingerprint();
+        let version = Version::new(1, 0, 0);
+        let replacement = Version::new(2, 0, 0);
+        let content = HashAlgorithm::Sha256.digest(&[0, 1, 2, 3]);
+        let mut validator = LogState::default();
+
+        let timestamp0 = SystemTime::now();
+        let record0 = model::PackageRecord {
+            prev: None,
+            version: PACKAGE_RECORD_VERSION,
+            timestamp: timestamp0,
+            entries: vec![
+                model::PackageEntry::Init {
+                    hash_algorithm: HashAlgorithm::Sha256,
+                    key: alice_pub,
+                },
+                model::PackageEntry::Release {
+                    version: version.clone(),
+                    content: content.clone(),
+                },
+                model::PackageEntry::Deprecate {
+                    version: version.clone(),
+                    message: "use 2.0.0 instead".to_string(),
+                    superseded_by: Some(replacement.clone()),
+                },
+            ],
+        };
+        let envelope0 = ProtoEnvelope::signed_contents(&alice_priv, record0).unwrap();
+        validator.validate(&envelope0).unwrap();
+
+        // A deprecated release is still resolved
+        let release = validator
+            .find_latest_release(&"^1".parse().unwrap())
+            .unwrap();
+        assert!(release.deprecated());
+        assert_eq!(release.content(), Some(&content));
+        assert_eq!(
+            release.deprecation,
+            Some(Deprecation {
+                message: "use 2.0.0 instead".to_string(),
+                superseded_by: Some(replacement.clone()),
+                by: alice_id,
+                timestamp: timestamp0,
+            })
+        );
+
+        // Deprecating a release that was not released is an error
+        let record1 = model::PackageRecord {
+            prev: Some(RecordId::package_record::<Sha256>(&envelope0)),
+            version: PACKAGE_RECORD_VERSION,
+            timestamp: timestamp0 + Duration::from_secs(1),
+            entries: vec![model::PackageEntry::Deprecate {
+                version: replacement.clone(),
+                message: "unreleased".to_string(),
+                superseded_by: None,
+            }],
+        };
+        let envelope1 = ProtoEnvelope::signed_contents(&alice_priv, record1).unwrap();
+        assert!(matches!(
+            validator.validate(&envelope1),
+            Err(ValidationError::DeprecateOfUnreleased { version: v }) if v == replacement
+        ));
+    }
+
     #[test]
     fn test_rollback() {
         let (alice_pub, alice_priv) = generate_p256_pair();
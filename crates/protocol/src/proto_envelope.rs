@@ -54,6 +54,27 @@ impl<Contents> ProtoEnvelope<Contents> {
         })
     }
 
+    /// Create an envelope for some contents using a signature from the
+    /// given signer.
+    pub fn signed_contents_with(
+        signer: &dyn signing::Signer,
+        contents: Contents,
+    ) -> Result<Self, Error>
+    where
+        Contents: Signable,
+    {
+        let content_bytes: Vec<u8> = contents.encode();
+
+        let key_id = signer.public_key().fingerprint();
+        let signature = contents.sign_with(signer)?;
+        Ok(ProtoEnvelope {
+            contents,
+            content_bytes,
+            key_id,
+            signature,
+        })
+    }
+
     /// Get the byte representation of the envelope contents.
     pub fn content_bytes(&self) -> &[u8] {
         &self.content_bytes
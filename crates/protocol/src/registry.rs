@@ -1,9 +1,9 @@
 use crate::{operator::OperatorRecord, package::PackageRecord, ProtoEnvelope};
-use anyhow::bail;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::str::FromStr;
 use std::time::SystemTime;
+use thiserror::Error;
 use warg_crypto::hash::{AnyHash, Hash, HashAlgorithm, SupportedDigest};
 use warg_crypto::prefix::VisitPrefixEncode;
 use warg_crypto::{prefix, ByteVisitor, Signable, VisitBytes};
@@ -122,6 +122,16 @@ impl VisitBytes for LogLeaf {
     }
 }
 
+/// Represents an error for an invalid package identifier.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("invalid package identifier `{id}`: {reason}")]
+pub struct InvalidPackageIdError {
+    /// The invalid package identifier.
+    pub id: String,
+    /// The reason the package identifier is invalid.
+    pub reason: String,
+}
+
 /// Represents a valid package identifier in the registry.
 ///
 /// Valid package identifiers conform to the component model specification
@@ -140,16 +150,38 @@ impl PackageId {
     ///
     /// Returns an error if the given string is not a valid package identifier.
     pub fn new(id: impl Into<String>) -> anyhow::Result<Self> {
+        Ok(Self::parse(id)?)
+    }
+
+    /// Parses a package identifier from the given string.
+    ///
+    /// Unlike [`PackageId::new`], the returned error describes why the
+    /// identifier is invalid.
+    pub fn parse(id: impl Into<String>) -> Result<Self, InvalidPackageIdError> {
         let id = id.into();
+        let invalid = |reason: String| InvalidPackageIdError {
+            id: id.clone(),
+            reason,
+        };
+
+        let colon = id
+            .find(':')
+            .ok_or_else(|| invalid("expected format is `<namespace>:<name>`".to_string()))?;
+
+        // Validate the namespace and name parts are valid kebab strings
+        for (part, value) in [("namespace", &id[..colon]), ("name", &id[colon + 1..])] {
+            if value.is_empty() {
+                return Err(invalid(format!("the {part} is empty")));
+            }
 
-        if let Some(colon) = id.find(':') {
-            // Validate the namespace and name parts are valid kebab strings
-            if KebabStr::new(&id[..colon]).is_some() && KebabStr::new(&id[colon + 1..]).is_some() {
-                return Ok(Self { id, colon });
+            if KebabStr::new(value).is_none() {
+                return Err(invalid(format!(
+                    "the {part} `{value}` is not a legal kebab-case identifier"
+                )));
             }
         }
 
-        bail!("invalid package identifier `{id}`: expected format is `<namespace>:<name>`")
+        Ok(Self { id, colon })
     }
 
     /// Gets the namespace part of the package identifier.
@@ -307,4 +339,51 @@ mod tests {
             proof.evaluate(&LogId::operator_log::<Sha256>(), &"foobar")
         );
     }
+
+    #[test]
+    fn parse_package_ids() {
+        for (id, namespace, name) in [
+            ("a:b", "a", "b"),
+            ("my-namespace:my-package", "my-namespace", "my-package"),
+            ("wasi:http2", "wasi", "http2"),
+            ("WASI:HTTP", "WASI", "HTTP"),
+            ("foo-BAR:baz-v1", "foo-BAR", "baz-v1"),
+        ] {
+            let parsed = PackageId::parse(id).unwrap();
+            assert_eq!(parsed.namespace(), namespace);
+            assert_eq!(parsed.name(), name);
+        }
+
+        for (id, reason) in [
+            ("", "expected format is `<namespace>:<name>`"),
+            ("foo", "expected format is `<namespace>:<name>`"),
+            (":bar", "the namespace is empty"),
+            ("foo:", "the name is empty"),
+            (
+                "foo:bar:baz",
+                "the name `bar:baz` is not a legal kebab-case identifier",
+            ),
+            (
+                "Foo:bar",
+                "the namespace `Foo` is not a legal kebab-case identifier",
+            ),
+            (
+                "foo--bar:baz",
+                "the namespace `foo--bar` is not a legal kebab-case identifier",
+            ),
+            (
+                "foo:1bar",
+                "the name `1bar` is not a legal kebab-case identifier",
+            ),
+            (
+                "foo:bar baz",
+                "the name `bar baz` is not a legal kebab-case identifier",
+            ),
+        ] {
+            let e = PackageId::parse(id).unwrap_err();
+            assert_eq!(e.id, id);
+            assert_eq!(e.reason, reason, "for `{id}`");
+            assert!(PackageId::new(id).is_err());
+        }
+    }
 }
@@ -155,6 +155,16 @@ async fn get_package_info(
                             version: Some(version.clone()),
                             ..Default::default()
                         },
+                        Unyank { version } => EntryInfo {
+                            kind: "unyank",
+                            version: Some(version.clone()),
+                            ..Default::default()
+                        },
+                        Deprecate { version, .. } => EntryInfo {
+                            kind: "deprecate",
+                            version: Some(version.clone()),
+                            ..Default::default()
+                        },
                         _ => EntryInfo {
                             kind: "UNKNOWN",
                             ..Default::default()
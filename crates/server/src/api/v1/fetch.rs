@@ -4,7 +4,7 @@ use crate::services::CoreService;
 use axum::http::StatusCode;
 use axum::{
     debug_handler,
-    extract::State,
+    extract::{Path, State},
     response::IntoResponse,
     routing::{get, post},
     Router,
@@ -32,6 +32,10 @@ impl Config {
         Router::new()
             .route("/logs", post(fetch_logs))
             .route("/checkpoint", get(fetch_checkpoint))
+            .route(
+                "/checkpoint/before/:timestamp",
+                get(fetch_checkpoint_before),
+            )
             .with_state(self)
     }
 }
@@ -131,3 +135,17 @@ async fn fetch_checkpoint(
         config.core_service.store().get_latest_checkpoint().await?,
     ))
 }
+
+#[debug_handler]
+async fn fetch_checkpoint_before(
+    State(config): State<Config>,
+    Path(timestamp): Path<u64>,
+) -> Result<Json<Option<SerdeEnvelope<TimestampedCheckpoint>>>, FetchApiError> {
+    Ok(Json(
+        config
+            .core_service
+            .store()
+            .get_checkpoint_before(timestamp)
+            .await?,
+    ))
+}
@@ -5,7 +5,7 @@ use crate::{
 use anyhow::Result;
 use axum::{
     extract::{
-        rejection::{JsonRejection, PathRejection},
+        rejection::{JsonRejection, PathRejection, QueryRejection},
         FromRequest, FromRequestParts,
     },
     http::StatusCode,
@@ -82,6 +82,22 @@ impl From<PathRejection> for Error {
     }
 }
 
+/// An extractor that wraps the query extractor of Axum.
+///
+/// This extractor returns an API error on rejection.
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Query), rejection(Error))]
+pub struct Query<T>(T);
+
+impl From<QueryRejection> for Error {
+    fn from(rejection: QueryRejection) -> Self {
+        Self {
+            status: rejection.status(),
+            message: rejection.body_text(),
+        }
+    }
+}
+
 pub async fn not_found() -> impl IntoResponse {
     Error {
         status: StatusCode::NOT_FOUND,
@@ -1,4 +1,4 @@
-use super::{Json, Path};
+use super::{Json, Path, Query};
 use crate::{
     datastore::{DataStoreError, RecordStatus},
     policy::{
@@ -18,14 +18,13 @@ use axum::{
 use futures::StreamExt;
 use std::sync::Arc;
 use std::{collections::HashMap, path::PathBuf};
-use tempfile::NamedTempFile;
-use tokio::io::AsyncWriteExt;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use url::Url;
 use warg_api::v1::package::{
     ContentSource, MissingContent, PackageError, PackageRecord, PackageRecordState,
-    PublishRecordRequest, UploadEndpoint,
+    PublishRecordRequest, UploadContentQuery, UploadEndpoint, UploadStatus,
 };
-use warg_crypto::hash::{AnyHash, Sha256};
+use warg_crypto::hash::{AnyHash, HashAlgorithm, Sha256};
 use warg_protocol::{
     package,
     registry::{LogId, RecordId},
@@ -67,7 +66,9 @@ impl Config {
             .route("/:log_id/record/:record_id", get(get_record))
             .route(
                 "/:log_id/record/:record_id/content/:digest",
-                post(upload_content),
+                get(get_upload_status)
+                    .head(head_content)
+                    .post(upload_content),
             )
             .with_state(self)
     }
@@ -84,6 +85,13 @@ impl Config {
         self.files_dir.join(self.content_file_name(digest))
     }
 
+    fn partial_content_path(&self, digest: &AnyHash) -> PathBuf {
+        self.temp_dir.join(format!(
+            "{name}.partial",
+            name = self.content_file_name(digest)
+        ))
+    }
+
     fn content_url(&self, digest: &AnyHash) -> String {
         self.content_base_url
             .join("content/")
@@ -227,6 +235,25 @@ async fn publish_record(
         .await?;
 
     let record_id = RecordId::package_record::<Sha256>(&record);
+
+    // A retried publish of a record that was already stored is answered with
+    // the state of the original record
+    if let Some(key) = &body.idempotency_key {
+        if *key != record_id.to_string() {
+            return Err(PackageApiError::bad_request(format!(
+                "idempotency key `{key}` does not match record identifier `{record_id}`"
+            )));
+        }
+
+        match get_record_state(&config, &log_id, record_id.clone()).await {
+            Ok(record) => return Ok((StatusCode::OK, Json(record))),
+            Err(PackageApiError(
+                PackageError::LogNotFound(_) | PackageError::RecordNotFound(_),
+            )) => {}
+            Err(e) => return Err(e),
+        }
+    }
+
     let mut missing = record.as_ref().contents();
     missing.retain(|d| !config.content_present(d));
 
@@ -267,29 +294,38 @@ async fn get_record(
     State(config): State<Config>,
     Path((log_id, record_id)): Path<(LogId, RecordId)>,
 ) -> Result<Json<PackageRecord>, PackageApiError> {
+    Ok(Json(get_record_state(&config, &log_id, record_id).await?))
+}
+
+/// Gets the current state of a stored package record.
+async fn get_record_state(
+    config: &Config,
+    log_id: &LogId,
+    record_id: RecordId,
+) -> Result<PackageRecord, PackageApiError> {
     let record = config
         .core_service
         .store()
-        .get_package_record(&log_id, &record_id)
+        .get_package_record(log_id, &record_id)
         .await?;
 
     match record.status {
         RecordStatus::MissingContent(missing) => {
-            let missing_content = config.build_missing_content(&log_id, &record_id, &missing);
-            Ok(Json(PackageRecord {
+            let missing_content = config.build_missing_content(log_id, &record_id, &missing);
+            Ok(PackageRecord {
                 id: record_id,
                 state: PackageRecordState::Sourcing { missing_content },
-            }))
+            })
         }
         // Validated is considered still processing until included in a checkpoint
-        RecordStatus::Pending | RecordStatus::Validated => Ok(Json(PackageRecord {
+        RecordStatus::Pending | RecordStatus::Validated => Ok(PackageRecord {
             id: record_id,
             state: PackageRecordState::Processing,
-        })),
-        RecordStatus::Rejected(reason) => Ok(Json(PackageRecord {
+        }),
+        RecordStatus::Rejected(reason) => Ok(PackageRecord {
             id: record_id,
             state: PackageRecordState::Rejected { reason },
-        })),
+        }),
         RecordStatus::Published => {
             let content_sources = record
                 .envelope
@@ -308,71 +344,168 @@ async fn get_record(
 
             let registry_index = record.registry_index.unwrap();
 
-            Ok(Json(PackageRecord {
+            Ok(PackageRecord {
                 id: record_id,
                 state: PackageRecordState::Published {
                     record: record.envelope.into(),
                     registry_index,
                     content_sources,
                 },
-            }))
+            })
         }
     }
 }
 
-#[debug_handler]
-async fn upload_content(
-    State(config): State<Config>,
-    Path((log_id, record_id, digest)): Path<(LogId, RecordId, AnyHash)>,
-    stream: BodyStream,
-) -> Result<impl IntoResponse, PackageApiError> {
+async fn check_content_missing(
+    config: &Config,
+    log_id: &LogId,
+    record_id: &RecordId,
+    digest: &AnyHash,
+) -> Result<(), PackageApiError> {
     match config
         .core_service
         .store()
-        .is_content_missing(&log_id, &record_id, &digest)
+        .is_content_missing(log_id, record_id, digest)
         .await
     {
-        Ok(true) => {}
-        Ok(false) => {
-            return Err(PackageApiError::bad_request(
-                "content digest `{digest}` is not required for package record `{record_id}`",
-            ));
-        }
+        Ok(true) => Ok(()),
+        Ok(false) => Err(PackageApiError::bad_request(format!(
+            "content digest `{digest}` is not required for package record `{record_id}`"
+        ))),
         Err(DataStoreError::RecordNotPending(_)) => {
-            return Err(PackageApiError(PackageError::RecordNotSourcing))
+            Err(PackageApiError(PackageError::RecordNotSourcing))
         }
-        Err(e) => return Err(e.into()),
+        Err(e) => Err(e.into()),
     }
+}
 
-    let tmp_path = NamedTempFile::new_in(&config.temp_dir)
-        .map_err(PackageApiError::internal_error)?
-        .into_temp_path();
+#[debug_handler]
+async fn get_upload_status(
+    State(config): State<Config>,
+    Path((log_id, record_id, digest)): Path<(LogId, RecordId, AnyHash)>,
+) -> Result<Json<UploadStatus>, PackageApiError> {
+    check_content_missing(&config, &log_id, &record_id, &digest).await?;
+
+    let path = config.partial_content_path(&digest);
+    if !path.is_file() {
+        return Ok(Json(UploadStatus {
+            received: 0,
+            digest: None,
+        }));
+    }
 
-    tracing::debug!(
-        "uploading content for record `{record_id}` from `{log_id}` to `{path}`",
-        path = tmp_path.display()
-    );
+    let (received, partial) = hash_file(&path, digest.algorithm())
+        .await
+        .map_err(PackageApiError::internal_error)?;
+
+    Ok(Json(UploadStatus {
+        received,
+        digest: (received > 0).then_some(partial),
+    }))
+}
 
-    let res = process_content(&tmp_path, &digest, stream, config.content_policy.as_deref()).await;
+/// Determines if the registry already holds missing content of a record.
+///
+/// Responds with `200 OK` if the content is present and `404 Not Found` if
+/// it must be uploaded. As present content need not be uploaded again, it
+/// is no longer considered missing for the record.
+#[debug_handler]
+async fn head_content(
+    State(config): State<Config>,
+    Path((log_id, record_id, digest)): Path<(LogId, RecordId, AnyHash)>,
+) -> Result<StatusCode, PackageApiError> {
+    check_content_missing(&config, &log_id, &record_id, &digest).await?;
 
-    // If the error was a rejection, transition the record itself to rejected
-    if let Err(PackageApiError(PackageError::Rejection(reason))) = &res {
+    if !config.content_present(&digest) {
+        return Ok(StatusCode::NOT_FOUND);
+    }
+
+    // If this is the last content needed, submit the record for processing now
+    if config
+        .core_service
+        .store()
+        .set_content_present(&log_id, &record_id, &digest)
+        .await?
+    {
         config
             .core_service
-            .store()
-            .reject_package_record(
-                &log_id,
-                &record_id,
-                &format!("content with digest `{digest}` was rejected by policy: {reason}"),
-            )
-            .await?;
+            .submit_package_record(log_id, record_id)
+            .await;
     }
 
-    // Only persist the file if the content was successfully processed
-    res?;
+    Ok(StatusCode::OK)
+}
 
-    tmp_path
-        .persist(config.content_path(&digest))
+#[debug_handler]
+async fn upload_content(
+    State(config): State<Config>,
+    Path((log_id, record_id, digest)): Path<(LogId, RecordId, AnyHash)>,
+    Query(query): Query<UploadContentQuery>,
+    stream: BodyStream,
+) -> Result<impl IntoResponse, PackageApiError> {
+    check_content_missing(&config, &log_id, &record_id, &digest).await?;
+
+    // Content received by an interrupted upload is kept in a partial file
+    // so that the upload may be resumed from where it left off
+    let partial_path = config.partial_content_path(&digest);
+    let received = match tokio::fs::metadata(&partial_path).await {
+        Ok(metadata) => metadata.len(),
+        Err(_) => 0,
+    };
+
+    if query.offset > received {
+        return Err(PackageApiError::bad_request(format!(
+            "upload offset {offset} exceeds the {received} byte(s) received for content digest `{digest}`",
+            offset = query.offset
+        )));
+    }
+
+    tracing::debug!(
+        "uploading content for record `{record_id}` from `{log_id}` to `{path}` at offset {offset}",
+        path = partial_path.display(),
+        offset = query.offset,
+    );
+
+    let res = process_content(
+        &partial_path,
+        query.offset,
+        &digest,
+        stream,
+        config.content_policy.as_deref(),
+    )
+    .await;
+
+    match res {
+        Ok(true) => {}
+        Ok(false) => {
+            return Err(PackageApiError::bad_request(format!(
+                "the upload of content digest `{digest}` was interrupted"
+            )));
+        }
+        Err(e) => {
+            // The received content is invalid, so it cannot be resumed
+            tokio::fs::remove_file(&partial_path).await.ok();
+
+            // If the error was a rejection, transition the record itself to rejected
+            if let PackageApiError(PackageError::Rejection(reason)) = &e {
+                config
+                    .core_service
+                    .store()
+                    .reject_package_record(
+                        &log_id,
+                        &record_id,
+                        &format!("content with digest `{digest}` was rejected by policy: {reason}"),
+                    )
+                    .await?;
+            }
+
+            return Err(e);
+        }
+    }
+
+    // Only persist the file if the content was successfully processed
+    tokio::fs::rename(&partial_path, config.content_path(&digest))
+        .await
         .map_err(PackageApiError::internal_error)?;
 
     // If this is the last content needed, submit the record for processing now
@@ -394,25 +527,92 @@ async fn upload_content(
     ))
 }
 
+async fn hash_file(
+    path: &std::path::Path,
+    algorithm: HashAlgorithm,
+) -> std::io::Result<(u64, AnyHash)> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut hasher = algorithm.hasher();
+    let mut buf = vec![0; 8192];
+    let mut len = 0;
+
+    loop {
+        let n = file.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+
+        hasher.update(&buf[..n]);
+        len += n as u64;
+    }
+
+    Ok((len, hasher.finalize()))
+}
+
+/// Processes uploaded content into the file at the given path.
+///
+/// Any content before `offset` is read back from the file and validated
+/// along with the uploaded stream.
+///
+/// Returns `Ok(false)` if the upload was interrupted before all content was
+/// received; the content received so far is kept in the file.
 async fn process_content(
     path: &std::path::Path,
+    offset: u64,
     digest: &AnyHash,
     mut stream: BodyStream,
     policy: Option<&dyn ContentPolicy>,
-) -> Result<(), PackageApiError> {
-    let mut tmp_file = tokio::fs::File::create(&path)
+) -> Result<bool, PackageApiError> {
+    let mut hasher = digest.algorithm().hasher();
+    let mut policy = policy.map(|p| p.new_stream_policy(digest)).transpose()?;
+
+    let mut tmp_file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .read(true)
+        .write(true)
+        .truncate(false)
+        .open(&path)
         .await
         .map_err(PackageApiError::internal_error)?;
 
-    let mut hasher = digest.algorithm().hasher();
-    let mut policy = policy.map(|p| p.new_stream_policy(digest)).transpose()?;
+    // Replay the previously received content
+    let mut prefix = (&mut tmp_file).take(offset);
+    let mut buf = vec![0; 8192];
+    loop {
+        let n = prefix
+            .read(&mut buf)
+            .await
+            .map_err(PackageApiError::internal_error)?;
+        if n == 0 {
+            break;
+        }
 
-    while let Some(chunk) = stream
-        .next()
+        if let Some(policy) = policy.as_mut() {
+            policy.check(&buf[..n])?;
+        }
+
+        hasher.update(&buf[..n]);
+    }
+
+    // Discard anything beyond the offset as it is being uploaded again
+    tmp_file
+        .set_len(offset)
         .await
-        .transpose()
-        .map_err(PackageApiError::internal_error)?
-    {
+        .map_err(PackageApiError::internal_error)?;
+
+    while let Some(chunk) = stream.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                tracing::debug!("content upload was interrupted: {e}");
+                tmp_file
+                    .flush()
+                    .await
+                    .map_err(PackageApiError::internal_error)?;
+                return Ok(false);
+            }
+        };
+
         if let Some(policy) = policy.as_mut() {
             policy.check(&chunk)?;
         }
@@ -424,6 +624,11 @@ async fn process_content(
             .map_err(PackageApiError::internal_error)?;
     }
 
+    tmp_file
+        .flush()
+        .await
+        .map_err(PackageApiError::internal_error)?;
+
     let result = hasher.finalize();
     if &result != digest {
         return Err(PackageApiError::bad_request(format!(
@@ -435,5 +640,5 @@ async fn process_content(
         policy.finalize()?;
     }
 
-    Ok(())
+    Ok(true)
 }
@@ -449,6 +449,19 @@ impl DataStore for MemoryDataStore {
         Ok(checkpoint.clone())
     }
 
+    async fn get_checkpoint_before(
+        &self,
+        timestamp: u64,
+    ) -> Result<Option<SerdeEnvelope<TimestampedCheckpoint>>, DataStoreError> {
+        let state = self.0.read().await;
+        Ok(state
+            .checkpoints
+            .values()
+            .rev()
+            .find(|c| c.as_ref().timestamp < timestamp)
+            .cloned())
+    }
+
     async fn get_operator_records(
         &self,
         log_id: &LogId,
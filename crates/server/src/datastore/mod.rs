@@ -226,6 +226,15 @@ pub trait DataStore: Send + Sync {
         &self,
     ) -> Result<SerdeEnvelope<TimestampedCheckpoint>, DataStoreError>;
 
+    /// Gets the latest checkpoint timestamped before the given number of
+    /// seconds since the Unix epoch.
+    ///
+    /// Returns `None` if no checkpoint is timestamped before the given time.
+    async fn get_checkpoint_before(
+        &self,
+        timestamp: u64,
+    ) -> Result<Option<SerdeEnvelope<TimestampedCheckpoint>>, DataStoreError>;
+
     /// Gets the operator records for the given registry log length.
     async fn get_operator_records(
         &self,
@@ -780,6 +780,38 @@ impl DataStore for PostgresDataStore {
         ))
     }
 
+    async fn get_checkpoint_before(
+        &self,
+        timestamp: u64,
+    ) -> Result<Option<SerdeEnvelope<TimestampedCheckpoint>>, DataStoreError> {
+        let mut conn = self.pool.get().await?;
+
+        let Some(checkpoint) = schema::checkpoints::table
+            .filter(schema::checkpoints::timestamp.lt(timestamp as i64))
+            .order_by(schema::checkpoints::id.desc())
+            .first::<CheckpointData>(&mut conn)
+            .await
+            .optional()?
+        else {
+            return Ok(None);
+        };
+
+        let log_length = checkpoint.log_length.try_into().unwrap();
+
+        Ok(Some(SerdeEnvelope::from_parts_unchecked(
+            TimestampedCheckpoint {
+                checkpoint: Checkpoint {
+                    log_root: checkpoint.log_root.0,
+                    log_length,
+                    map_root: checkpoint.map_root.0,
+                },
+                timestamp: checkpoint.timestamp.try_into().unwrap(),
+            },
+            checkpoint.key_id.0,
+            checkpoint.signature.0,
+        )))
+    }
+
     async fn get_operator_records(
         &self,
         log_id: &LogId,
@@ -4,9 +4,11 @@ use anyhow::Context;
 use anyhow::Result;
 use clap::Args;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 use warg_client::RegistryUrl;
-use warg_client::{ClientError, Config, FileSystemClient, StorageLockResult};
-use warg_crypto::signing::PrivateKey;
+use warg_client::{ClientError, CommandSigner, Config, FileSystemClient, StorageLockResult};
+use warg_crypto::signing::{PrivateKey, Signer};
 
 mod config;
 mod download;
@@ -38,6 +40,16 @@ pub struct CommonOptions {
     /// The path to the signing key file.
     #[clap(long, value_name = "KEY_FILE", env = "WARG_SIGNING_KEY_FILE")]
     pub key_file: Option<PathBuf>,
+    /// The path to an external command to sign records with instead of a signing key.
+    ///
+    /// The command is invoked with `public-key` to print its public key and with `sign` to sign the message read from stdin, printing the signature.
+    #[clap(
+        long,
+        value_name = "COMMAND",
+        env = "WARG_SIGNER_COMMAND",
+        conflicts_with = "key_file"
+    )]
+    pub signer_command: Option<PathBuf>,
     /// The path to the client configuration file to use.
     ///
     /// If not specified, the following locations are searched in order: `./warg-config.json`, `<system-config-dir>/warg/config.json`.
@@ -45,12 +57,19 @@ pub struct CommonOptions {
     /// If no configuration file is found, a default configuration is used.
     #[clap(long, value_name = "CONFIG")]
     pub config: Option<PathBuf>,
+    /// The maximum number of seconds to wait for another process to release the client storage.
+    ///
+    /// If not specified, the command waits until the storage is released.
+    #[clap(long, value_name = "SECONDS", env = "WARG_LOCK_TIMEOUT")]
+    pub lock_timeout: Option<u64>,
 }
 
 impl CommonOptions {
     /// Reads the client configuration.
     ///
     /// If a client configuration was not specified, a default configuration is returned.
+    ///
+    /// Settings from `WARG_*` environment variables override the configuration file.
     pub fn read_config(&self) -> Result<Config> {
         Ok(self
             .config
@@ -58,7 +77,8 @@ impl CommonOptions {
             .map_or_else(Config::from_default_file, |p| {
                 Config::from_file(p).map(Some)
             })?
-            .unwrap_or_default())
+            .unwrap_or_default()
+            .merge(Config::from_env()?))
     }
 
     /// Creates the warg client to use.
@@ -71,7 +91,14 @@ impl CommonOptions {
                     path = path.display()
                 );
 
-                FileSystemClient::new_with_config(self.registry.as_deref(), config)
+                match self.lock_timeout {
+                    Some(timeout) => FileSystemClient::lock_storage_timeout(
+                        self.registry.as_deref(),
+                        config,
+                        Duration::from_secs(timeout),
+                    ),
+                    None => FileSystemClient::new_with_config(self.registry.as_deref(), config),
+                }
             }
         }
     }
@@ -89,4 +116,17 @@ impl CommonOptions {
             get_signing_key(registry_url, &self.key_name)
         }
     }
+
+    /// Gets the signer for the given registry URL.
+    ///
+    /// The signer command is used if specified; otherwise, the signing key is used.
+    pub fn signer(&self, registry_url: &RegistryUrl) -> Result<Arc<dyn Signer>> {
+        match &self.signer_command {
+            Some(command) => Ok(Arc::new(
+                CommandSigner::new(command, std::iter::empty::<String>())
+                    .with_context(|| format!("failed to create signer for {command:?}"))?,
+            )),
+            None => Ok(Arc::new(self.signing_key(registry_url)?)),
+        }
+    }
 }
@@ -60,6 +60,7 @@ impl ConfigCommand {
             default_url,
             registries_dir: self.registries_dir.map(|p| cwd.join(p)),
             content_dir: self.content_dir.map(|p| cwd.join(p)),
+            ..Default::default()
         };
 
         config.write_to_file(&path)?;
@@ -65,6 +65,10 @@ pub enum PublishCommand {
     Release(PublishReleaseCommand),
     /// Yank a package version.
     Yank(PublishYankCommand),
+    /// Unyank a package version.
+    Unyank(PublishUnyankCommand),
+    /// Deprecate a package version.
+    Deprecate(PublishDeprecateCommand),
     /// Grant permissions for the package.
     Grant(PublishGrantCommand),
     /// Revoke permissions for the package.
@@ -88,6 +92,8 @@ impl PublishCommand {
             Self::Init(cmd) => cmd.exec().await,
             Self::Release(cmd) => cmd.exec().await,
             Self::Yank(cmd) => cmd.exec().await,
+            Self::Unyank(cmd) => cmd.exec().await,
+            Self::Deprecate(cmd) => cmd.exec().await,
             Self::Grant(cmd) => cmd.exec().await,
             Self::Revoke(cmd) => cmd.exec().await,
             Self::Start(cmd) => cmd.exec().await,
@@ -126,13 +132,14 @@ impl PublishInitCommand {
         .await?
         {
             Some(entry) => {
-                let signing_key = self.common.signing_key(client.url())?;
+                let signer = self.common.signer(client.url())?;
                 let record_id = client
                     .publish_with_info(
-                        &signing_key,
+                        &signer,
                         PublishInfo {
                             id: self.id.clone(),
                             head: None,
+                            expected_head: None,
                             entries: vec![entry],
                         },
                     )
@@ -210,13 +217,14 @@ impl PublishReleaseCommand {
         .await?
         {
             Some(entry) => {
-                let signing_key = self.common.signing_key(client.url())?;
+                let signer = self.common.signer(client.url())?;
                 let record_id = client
                     .publish_with_info(
-                        &signing_key,
+                        &signer,
                         PublishInfo {
                             id: self.id.clone(),
                             head: None,
+                            expected_head: None,
                             entries: vec![entry],
                         },
                     )
@@ -280,13 +288,14 @@ impl PublishYankCommand {
         .await?
         {
             Some(entry) => {
-                let signing_key = self.common.signing_key(client.url())?;
+                let signer = self.common.signer(client.url())?;
                 let record_id = client
                     .publish_with_info(
-                        &signing_key,
+                        &signer,
                         PublishInfo {
                             id: self.id.clone(),
                             head: None,
+                            expected_head: None,
                             entries: vec![entry],
                         },
                     )
@@ -319,6 +328,160 @@ impl PublishYankCommand {
     }
 }
 
+/// Unyank a yanked package release from a warg registry.
+#[derive(Args)]
+#[clap(disable_version_flag = true)]
+pub struct PublishUnyankCommand {
+    /// The common command options.
+    #[clap(flatten)]
+    pub common: CommonOptions,
+    /// The identifier of the package being unyanked.
+    #[clap(long, short, value_name = "PACKAGE")]
+    pub id: PackageId,
+    /// The version of the package being unyanked.
+    #[clap(long, short, value_name = "VERSION")]
+    pub version: Version,
+    /// Whether to wait for the publish to complete.
+    #[clap(long)]
+    pub no_wait: bool,
+}
+
+impl PublishUnyankCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        let config = self.common.read_config()?;
+        let client = self.common.create_client(&config)?;
+
+        let version = self.version.clone();
+        match enqueue(&client, &self.id, move |_| async move {
+            Ok(PublishEntry::Unyank { version })
+        })
+        .await?
+        {
+            Some(entry) => {
+                let signer = self.common.signer(client.url())?;
+                let record_id = client
+                    .publish_with_info(
+                        &signer,
+                        PublishInfo {
+                            id: self.id.clone(),
+                            head: None,
+                            expected_head: None,
+                            entries: vec![entry],
+                        },
+                    )
+                    .await?;
+
+                if self.no_wait {
+                    println!("submitted record `{record_id}` for publishing");
+                } else {
+                    client
+                        .wait_for_publish(&self.id, &record_id, DEFAULT_WAIT_INTERVAL)
+                        .await?;
+
+                    println!(
+                        "unyanked version {version} of package `{id}`",
+                        version = self.version,
+                        id = self.id
+                    );
+                }
+            }
+            None => {
+                println!(
+                    "added unyank of version {version} for package `{id}` to pending publish",
+                    version = self.version,
+                    id = self.id
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Deprecate a package release in a warg registry.
+#[derive(Args)]
+#[clap(disable_version_flag = true)]
+pub struct PublishDeprecateCommand {
+    /// The common command options.
+    #[clap(flatten)]
+    pub common: CommonOptions,
+    /// The identifier of the package being deprecated.
+    #[clap(long, short, value_name = "PACKAGE")]
+    pub id: PackageId,
+    /// The version of the package being deprecated.
+    #[clap(long, short, value_name = "VERSION")]
+    pub version: Version,
+    /// The message explaining the deprecation.
+    #[clap(long, short, value_name = "MESSAGE")]
+    pub message: String,
+    /// The version of the package that supersedes the deprecated version.
+    #[clap(long, value_name = "VERSION")]
+    pub superseded_by: Option<Version>,
+    /// Whether to wait for the publish to complete.
+    #[clap(long)]
+    pub no_wait: bool,
+}
+
+impl PublishDeprecateCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        let config = self.common.read_config()?;
+        let client = self.common.create_client(&config)?;
+
+        let version = self.version.clone();
+        let message = self.message.clone();
+        let superseded_by = self.superseded_by.clone();
+        match enqueue(&client, &self.id, move |_| async move {
+            Ok(PublishEntry::Deprecate {
+                version,
+                message,
+                superseded_by,
+            })
+        })
+        .await?
+        {
+            Some(entry) => {
+                let signer = self.common.signer(client.url())?;
+                let record_id = client
+                    .publish_with_info(
+                        &signer,
+                        PublishInfo {
+                            id: self.id.clone(),
+                            head: None,
+                            expected_head: None,
+                            entries: vec![entry],
+                        },
+                    )
+                    .await?;
+
+                if self.no_wait {
+                    println!("submitted record `{record_id}` for publishing");
+                } else {
+                    client
+                        .wait_for_publish(&self.id, &record_id, DEFAULT_WAIT_INTERVAL)
+                        .await?;
+
+                    println!(
+                        "deprecated version {version} of package `{id}`",
+                        version = self.version,
+                        id = self.id
+                    );
+                }
+            }
+            None => {
+                println!(
+                    "added deprecation of version {version} for package `{id}` to pending publish",
+                    version = self.version,
+                    id = self.id
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
 /// Publish a package to a warg registry.
 #[derive(Args)]
 #[clap(disable_version_flag = true)]
@@ -359,13 +522,14 @@ impl PublishGrantCommand {
         .await?
         {
             Some(entry) => {
-                let signing_key = self.common.signing_key(client.url())?;
+                let signer = self.common.signer(client.url())?;
                 let record_id = client
                     .publish_with_info(
-                        &signing_key,
+                        &signer,
                         PublishInfo {
                             id: self.id.clone(),
                             head: None,
+                            expected_head: None,
                             entries: vec![entry],
                         },
                     )
@@ -440,13 +604,14 @@ impl PublishRevokeCommand {
         .await?
         {
             Some(entry) => {
-                let signing_key = self.common.signing_key(client.url())?;
+                let signer = self.common.signer(client.url())?;
                 let record_id = client
                     .publish_with_info(
-                        &signing_key,
+                        &signer,
                         PublishInfo {
                             id: self.id.clone(),
                             head: None,
+                            expected_head: None,
                             entries: vec![entry],
                         },
                     )
@@ -505,6 +670,7 @@ impl PublishStartCommand {
                 client.registry().store_publish(Some(&PublishInfo {
                     id: self.id.clone(),
                     head: None,
+                    expected_head: None,
                     entries: Default::default(),
                 }))
                 .await?;
@@ -553,6 +719,19 @@ impl PublishListCommand {
                         PublishEntry::Yank { version } => {
                             println!("yank {version}")
                         }
+                        PublishEntry::Unyank { version } => {
+                            println!("unyank {version}")
+                        }
+                        PublishEntry::Deprecate {
+                            version,
+                            superseded_by,
+                            ..
+                        } => match superseded_by {
+                            Some(replacement) => {
+                                println!("deprecate {version} in favor of {replacement}")
+                            }
+                            None => println!("deprecate {version}"),
+                        },
                         PublishEntry::Grant { key, permissions } => println!(
                             "grant ({permissions_str}) to `{key_id}`",
                             permissions_str = permissions.iter().join(","),
@@ -625,8 +804,8 @@ impl PublishSubmitCommand {
             Some(info) => {
                 println!("submitting publish for package `{id}`...", id = info.id);
 
-                let signing_key = self.common.signing_key(client.url())?;
-                let record_id = client.publish_with_info(&signing_key, info.clone()).await?;
+                let signer = self.common.signer(client.url())?;
+                let record_id = client.publish_with_info(&signer, info.clone()).await?;
 
                 client.registry().store_publish(None).await?;
 
@@ -649,6 +828,12 @@ impl PublishSubmitCommand {
                             PublishEntry::Yank { version } => {
                                 println!("yanked version {version} of package `{id}`")
                             }
+                            PublishEntry::Unyank { version } => {
+                                println!("unyanked version {version} of package `{id}`")
+                            }
+                            PublishEntry::Deprecate { version, .. } => {
+                                println!("deprecated version {version} of package `{id}`")
+                            }
                             PublishEntry::Grant { key, permissions } => {
                                 println!(
                                     "granted ({permissions_str}) to `{key_id}`",
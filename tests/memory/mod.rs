@@ -95,6 +95,12 @@ async fn it_rejects_invalid_signature() -> Result<()> {
     test_invalid_signature(&config).await
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_deduplicates_a_retried_publish() -> Result<()> {
+    let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
+    test_idempotent_publish(&config).await
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 async fn it_formats_custom_content_urls() -> Result<()> {
     let (_server, config) = spawn_server(
@@ -106,3 +112,9 @@ async fn it_formats_custom_content_urls() -> Result<()> {
     .await?;
     test_custom_content_url(&config).await
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_resumes_an_interrupted_upload() -> Result<()> {
+    let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
+    test_resumable_upload(&config).await
+}
@@ -53,12 +53,15 @@ async fn it_works_with_postgres() -> TestResult {
     // allows any signing key
     //test_unknown_signing_key(&config).await?;
     test_invalid_signature(&config).await?;
+    test_idempotent_publish(&config).await?;
+    test_resumable_upload(&config).await?;
 
     let mut packages = vec![
         PackageId::new("test:component")?,
         PackageId::new("test:yankee")?,
         PackageId::new("test:wit-package")?,
         PackageId::new("test:unauthorized-key")?,
+        PackageId::new("test:resumed-upload")?,
     ];
 
     // There should be two log entries in the registry
@@ -1,5 +1,6 @@
 use self::support::*;
 use anyhow::{Context, Result};
+use futures::StreamExt;
 use rand_core::OsRng;
 use reqwest::StatusCode;
 use std::{
@@ -9,18 +10,22 @@ use std::{
 };
 use url::Url;
 use warg_api::v1::{
-    package::{ContentSource, PackageRecordState, PublishRecordRequest},
+    package::{ContentSource, PackageRecordState, PublishRecordRequest, UploadEndpoint},
     paths,
 };
 use warg_client::{
     api,
-    storage::{PublishEntry, PublishInfo, RegistryStorage},
+    storage::{ContentStorage, PublishEntry, PublishInfo, RegistryStorage, UploadInfo},
     ClientError, Config,
 };
-use warg_crypto::{hash::Sha256, signing::PrivateKey, Encode, Signable};
+use warg_crypto::{
+    hash::{HashAlgorithm, Sha256},
+    signing::PrivateKey,
+    Encode, Signable,
+};
 use warg_protocol::{
     package::{PackageEntry, PackageRecord, PACKAGE_RECORD_VERSION},
-    registry::{LogId, PackageId},
+    registry::{LogId, PackageId, RecordId},
     ProtoEnvelope, ProtoEnvelopeBody, Version,
 };
 use wit_component::DecodedWasm;
@@ -132,6 +137,7 @@ async fn test_package_yanking(config: &Config) -> Result<()> {
             PublishInfo {
                 id: id.clone(),
                 head: None,
+                expected_head: None,
                 entries: vec![PublishEntry::Yank {
                     version: PACKAGE_VERSION.parse()?,
                 }],
@@ -357,6 +363,7 @@ async fn test_invalid_signature(config: &Config) -> Result<()> {
         id: Cow::Borrowed(&id),
         record: Cow::Owned(ProtoEnvelopeBody::from(record)),
         content_sources: Default::default(),
+        idempotency_key: None,
     };
 
     // Update the signature to one that does not match the contents
@@ -385,6 +392,102 @@ async fn test_invalid_signature(config: &Config) -> Result<()> {
     Ok(())
 }
 
+async fn test_idempotent_publish(config: &Config) -> Result<()> {
+    const PACKAGE_ID: &str = "test:idempotent-publish";
+
+    let id = PackageId::new(PACKAGE_ID)?;
+    let log_id = LogId::package_log::<Sha256>(&id);
+    let url = Url::parse(config.default_url.as_ref().unwrap())?
+        .join(&paths::publish_package_record(&log_id))
+        .unwrap();
+
+    let signing_key = test_signing_key();
+    let record = ProtoEnvelope::signed_contents(
+        &signing_key,
+        PackageRecord {
+            prev: None,
+            version: PACKAGE_RECORD_VERSION,
+            timestamp: SystemTime::now(),
+            entries: vec![PackageEntry::Init {
+                hash_algorithm: HashAlgorithm::Sha256,
+                key: signing_key.public_key(),
+            }],
+        },
+    )?;
+    let record_id = RecordId::package_record::<Sha256>(&record);
+    let body = ProtoEnvelopeBody::from(record);
+    let request = |key: &str| PublishRecordRequest {
+        id: Cow::Borrowed(&id),
+        record: Cow::Borrowed(&body),
+        content_sources: Default::default(),
+        idempotency_key: Some(key.to_string()),
+    };
+
+    let api = api::Client::new(config.default_url.as_ref().unwrap())?;
+    let log_length = api
+        .latest_checkpoint()
+        .await?
+        .as_ref()
+        .checkpoint
+        .log_length;
+    let published = api
+        .publish_package_record(&log_id, request(&record_id.to_string()))
+        .await?;
+    assert_eq!(published.id, record_id);
+
+    // Retrying the same publish is answered with the original record
+    let response = reqwest::Client::new()
+        .post(url.clone())
+        .json(&request(&record_id.to_string()))
+        .send()
+        .await?;
+    let status = response.status();
+    let body = response.text().await?;
+    assert_eq!(
+        status,
+        StatusCode::OK,
+        "unexpected response from server: {status}\n{body}",
+    );
+    assert!(
+        body.contains(&record_id.to_string()),
+        "unexpected response body: {body}"
+    );
+
+    let client = create_client(config)?;
+    client
+        .wait_for_publish(&id, &record_id, Duration::from_millis(100))
+        .await?;
+    assert_eq!(
+        api.latest_checkpoint()
+            .await?
+            .as_ref()
+            .checkpoint
+            .log_length,
+        log_length + 1,
+        "expected the record to be published once"
+    );
+
+    // The retry is also answered once the record is published
+    let retried = api
+        .publish_package_record(&log_id, request(&record_id.to_string()))
+        .await?;
+    assert_eq!(retried.id, record_id);
+    assert!(matches!(
+        retried.state,
+        PackageRecordState::Published { .. }
+    ));
+
+    // A key that is not the record identifier is rejected
+    let response = reqwest::Client::new()
+        .post(url)
+        .json(&request("sha256:0000"))
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    Ok(())
+}
+
 async fn test_custom_content_url(config: &Config) -> Result<()> {
     const PACKAGE_ID: &str = "test:custom-content-url";
     const PACKAGE_VERSION: &str = "0.1.0";
@@ -448,3 +551,150 @@ async fn test_custom_content_url(config: &Config) -> Result<()> {
 
     Ok(())
 }
+
+async fn test_resumable_upload(config: &Config) -> Result<()> {
+    const INTERRUPTED_PACKAGE_ID: &str = "test:interrupted-upload";
+    const PACKAGE_ID: &str = "test:resumed-upload";
+    const PACKAGE_VERSION: &str = "0.1.0";
+
+    let client = create_client(config)?;
+    let signing_key = test_signing_key();
+    let bytes = wat::parse_str("(component)")?;
+    let (prefix, _) = bytes.split_at(bytes.len() / 2);
+    let prefix = prefix.to_vec();
+    let digest = client
+        .content()
+        .store_content(
+            Box::pin(futures::stream::once({
+                let bytes = bytes.clone();
+                async move { Ok(bytes.into()) }
+            })),
+            None,
+        )
+        .await?;
+
+    // Publish a record directly so that the upload can be interrupted
+    let id = PackageId::new(INTERRUPTED_PACKAGE_ID)?;
+    let log_id = LogId::package_log::<Sha256>(&id);
+    let record = ProtoEnvelope::signed_contents(
+        &signing_key,
+        PackageRecord {
+            prev: None,
+            version: PACKAGE_RECORD_VERSION,
+            timestamp: SystemTime::now(),
+            entries: vec![
+                PackageEntry::Init {
+                    hash_algorithm: HashAlgorithm::Sha256,
+                    key: signing_key.public_key(),
+                },
+                PackageEntry::Release {
+                    version: PACKAGE_VERSION.parse()?,
+                    content: digest.clone(),
+                },
+            ],
+        },
+    )?;
+
+    let api = api::Client::new(config.default_url.as_ref().unwrap())?;
+    let record = api
+        .publish_package_record(
+            &log_id,
+            PublishRecordRequest {
+                id: Cow::Borrowed(&id),
+                record: Cow::Owned(record.into()),
+                content_sources: Default::default(),
+                idempotency_key: None,
+            },
+        )
+        .await?;
+
+    let url = match record.missing_content().next() {
+        Some((_, missing)) => match missing.upload.first() {
+            Some(UploadEndpoint::HttpPost { url }) => url.clone(),
+            None => panic!("expected an upload endpoint"),
+        },
+        None => panic!("expected missing content"),
+    };
+
+    // Disconnect after sending only the first half of the content
+    let stream = futures::stream::iter([Ok::<_, std::io::Error>(prefix.clone())]).chain(
+        futures::stream::once(async {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            Err(std::io::Error::new(
+                std::io::ErrorKind::ConnectionReset,
+                "simulated disconnect",
+            ))
+        }),
+    );
+    api.upload_content(&url, reqwest::Body::wrap_stream(stream))
+        .await
+        .expect_err("expected the upload to fail");
+
+    // Wait for the registry to finish processing the interrupted upload
+    let mut status = api.upload_status(&url).await?;
+    for _ in 0..50 {
+        if status.received == prefix.len() as u64 {
+            break;
+        }
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        status = api.upload_status(&url).await?;
+    }
+
+    assert_eq!(status.received, prefix.len() as u64);
+    assert_eq!(
+        status.digest,
+        Some(HashAlgorithm::Sha256.digest(&prefix)),
+        "expected the digest of the received bytes"
+    );
+
+    // Publish another package with the same content; the client should resume the upload
+    client
+        .content()
+        .store_upload(
+            &digest,
+            Some(&UploadInfo {
+                digest: digest.clone(),
+                offset: prefix.len() as u64,
+            }),
+        )
+        .await?;
+
+    let id = PackageId::new(PACKAGE_ID)?;
+    let record_id = client
+        .publish_with_info(
+            &signing_key,
+            PublishInfo {
+                id: id.clone(),
+                head: None,
+                expected_head: None,
+                entries: vec![
+                    PublishEntry::Init,
+                    PublishEntry::Release {
+                        version: PACKAGE_VERSION.parse()?,
+                        content: digest.clone(),
+                    },
+                ],
+            },
+        )
+        .await?;
+
+    client
+        .wait_for_publish(&id, &record_id, Duration::from_millis(100))
+        .await?;
+
+    assert!(
+        client.content().load_upload(&digest).await?.is_none(),
+        "expected the upload information to be removed"
+    );
+
+    // Ensure the resumed content is intact
+    client.upsert([&id]).await?;
+    let download = client
+        .download(&id, &PACKAGE_VERSION.parse()?)
+        .await?
+        .context("failed to resolve package")?;
+    assert_eq!(download.digest, digest);
+
+    Ok(())
+}
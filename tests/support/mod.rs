@@ -156,6 +156,7 @@ pub async fn spawn_server(
         default_url: Some(format!("http://{addr}")),
         registries_dir: Some(root.join("registries")),
         content_dir: Some(root.join("content")),
+        ..Default::default()
     };
 
     Ok((instance, config))
@@ -192,6 +193,7 @@ pub async fn publish(
             PublishInfo {
                 id: id.clone(),
                 head: None,
+                expected_head: None,
                 entries,
             },
         )